@@ -46,6 +46,7 @@ class AppBootstrap {
       fileService: fileService,
       rendererService: rendererService,
       bibleRepository: bibleRepository,
+      libraryRepository: libraryRepository,
     );
     final modelService = ModelService(fileService: fileService);
     final searchRepository = SearchRepository(
@@ -4,8 +4,24 @@ class Index {
   final String header;
   final int? chapter;
   final int? verse;
+  final int? verseEnd;
+  final String? segment;
 
-  Index(this.page, this.book, this.header, [this.chapter, this.verse]);
+  Index(
+    this.page,
+    this.book,
+    this.header, [
+    this.chapter,
+    this.verse,
+    this.verseEnd,
+    this.segment,
+  ]);
+
+  /// The verse as published, such as `3-4` or `12a`.
+  String get verseLabel {
+    final end = verseEnd != null && verseEnd! > verse! ? '-$verseEnd' : '';
+    return '$verse$end${segment ?? ''}';
+  }
 
   String get reference {
     if (chapter == null || verse == null) {
@@ -13,7 +29,7 @@ class Index {
     } else if (verse == null) {
       return "$header $chapter";
     } else {
-      return "$header $chapter:$verse";
+      return "$header $chapter:$verseLabel";
     }
   }
 }
@@ -24,6 +24,14 @@ class LibraryRepository {
     return list;
   }
 
+  Future<Translation?> getTranslation(String translationId) async {
+    final available = await getAvailableTranslations();
+    for (final translation in available) {
+      if (translation.id == translationId) return translation;
+    }
+    return null;
+  }
+
   Future<List<Translation>> getDownloadedTranslations() async {
     if (_downloadedTranslationsCache != null) return _downloadedTranslationsCache!;
     final available = await getAvailableTranslations();
@@ -3,8 +3,10 @@ import 'dart:typed_data';
 
 import 'package:flutter/foundation.dart';
 import 'package:flutter/services.dart';
+import 'package:rust/rust.dart' as rust;
 import 'package:sola/core/models/page_model.dart';
 import 'package:sola/data/repositories/bible_repository.dart';
+import 'package:sola/data/repositories/library_repository.dart';
 import 'package:sola/domain/services/file_service.dart';
 import 'package:sola/domain/services/render_isolate.dart';
 import 'package:sola/domain/services/renderer_service.dart';
@@ -21,19 +23,47 @@ const _canonicalBookOrder = [
   '3JN','JUD','REV',
 ];
 
+/// Where a book rendered at a size is cached. The index version is part of
+/// the path, so books indexed in an older layout are rendered again.
+String renderedBookDir(
+  String translationId,
+  String bookId,
+  double width,
+  double height,
+) =>
+    'rendered/$translationId/$bookId-${width.toInt()}-${height.toInt()}'
+    '-v${rust.indexVersion()}';
+
 class RendererRepository {
   final FileService _fileService;
   final RendererService _rendererService;
   final BibleRepository _bibleRepository;
+  final LibraryRepository _libraryRepository;
   final Map<String, List<PageModel>> _pageCache = {};
 
   RendererRepository({
     required FileService fileService,
     required RendererService rendererService,
     required BibleRepository bibleRepository,
+    required LibraryRepository libraryRepository,
   }) : _fileService = fileService,
        _rendererService = rendererService,
-       _bibleRepository = bibleRepository;
+       _bibleRepository = bibleRepository,
+       _libraryRepository = libraryRepository;
+
+  /// Layout options from the translation's entry in translations.json.
+  Future<rust.LayoutOptions> _layoutOptions(String translationId) async {
+    final translation = await _libraryRepository.getTranslation(translationId);
+    if (translation == null) return const rust.LayoutOptions();
+    return rust.LayoutOptions(
+      textDirection: translation.textDirection == 'rtl'
+          ? rust.TextDirection.TEXT_DIRECTION_RTL
+          : rust.TextDirection.TEXT_DIRECTION_LTR,
+      language: translation.bcp47Tag.isNotEmpty
+          ? translation.bcp47Tag
+          : translation.bcp47,
+    );
+  }
 
   Future<String> _renderBook(
     String translationId,
@@ -42,8 +72,7 @@ class RendererRepository {
     double height, [
     Uint8List? bytes,
   ]) async {
-    final dir =
-        'rendered/$translationId/$bookId-${width.toInt()}-${height.toInt()}';
+    final dir = renderedBookDir(translationId, bookId, width, height);
     final dirExists = await _fileService.openDirectory(dir);
 
     if (!dirExists) {
@@ -62,6 +91,8 @@ class RendererRepository {
         'assets/fonts/AveriaSerifLibre-Regular.ttf',
       );
 
+      final options = await _layoutOptions(translationId);
+
       // Run heavy rendering on background isolate
       final output = await compute(
         renderInBackground,
@@ -70,6 +101,7 @@ class RendererRepository {
           fontBytes: fontData.buffer.asUint8List(),
           width: width,
           height: height,
+          options: options,
         ),
       );
 
@@ -5,6 +5,7 @@ import 'package:sola/core/models/index.dart';
 import 'package:sola/core/models/model_info.dart';
 import 'package:sola/core/models/search_info.dart';
 import 'package:sola/core/models/search_result.dart';
+import 'package:sola/data/repositories/renderer_repository.dart';
 import 'package:sola/domain/services/file_service.dart';
 import 'package:sola/domain/services/model_service.dart';
 import 'package:sola/domain/services/search_isolate.dart';
@@ -46,7 +47,7 @@ class SearchRepository {
 
     final pageMapBytesList = <Uint8List>[];
     for (final bookId in bookIds) {
-      final dir = 'rendered/$translationId/$bookId-${width.toInt()}-${height.toInt()}';
+      final dir = renderedBookDir(translationId, bookId, width, height);
       try {
         pageMapBytesList.add(await _fileService.readBytes('$dir/indices'));
       } catch (e) {
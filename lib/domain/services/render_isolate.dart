@@ -8,12 +8,14 @@ class RenderInput {
   final Uint8List fontBytes;
   final double width;
   final double height;
+  final rust.LayoutOptions options;
 
   RenderInput({
     required this.bookBytes,
     required this.fontBytes,
     required this.width,
     required this.height,
+    this.options = const rust.LayoutOptions(),
   });
 }
 
@@ -48,6 +50,7 @@ RenderOutput renderInBackground(RenderInput input) {
       headerHeight: input.height / 5,
       dropCapPadding: 20,
     ),
+    options: input.options,
   );
   print('[Isolate] Serializing pages/indices/verses...');
 
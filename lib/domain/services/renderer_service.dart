@@ -58,7 +58,12 @@ class RendererService {
     debugPrint('[RendererSvc] Styles registered');
   }
 
-  RendererResponse layout(Pointer<Void> book, double width, double height) {
+  RendererResponse layout(
+    Pointer<Void> book,
+    double width,
+    double height, {
+    rust.LayoutOptions options = const rust.LayoutOptions(),
+  }) {
     debugPrint('[RendererSvc] Layout: ${width.toInt()}x${height.toInt()}');
     final painter = rust.layout(
       renderer,
@@ -69,6 +74,7 @@ class RendererService {
         headerHeight: height / 5,
         dropCapPadding: 20,
       ),
+      options: options,
     );
     debugPrint('[RendererSvc] Layout complete');
     return RendererResponse(painter);
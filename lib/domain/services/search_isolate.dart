@@ -114,6 +114,8 @@ class SearchIsolate {
       index.header,
       index.chapter,
       index.verse,
+      index.verseEnd,
+      index.segment,
     );
   }
 
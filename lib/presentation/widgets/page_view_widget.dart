@@ -41,7 +41,7 @@ class _PagePainter extends CustomPainter {
           : fragment.style;
       final painter = TextPainter(
         text: TextSpan(text: fragment.text, style: style),
-        textDirection: TextDirection.ltr,
+        textDirection: fragment.rtl ? TextDirection.rtl : TextDirection.ltr,
         maxLines: 1,
         textHeightBehavior: const TextHeightBehavior(
           leadingDistribution: TextLeadingDistribution.even,
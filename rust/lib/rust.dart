@@ -2,10 +2,24 @@ import 'dart:ffi';
 import 'dart:io';
 import 'dart:typed_data';
 import 'package:ffi/ffi.dart';
-import 'dart:ui' show Color;
-import 'package:flutter/painting.dart' show TextDecoration, TextStyle, FontWeight, TextBaseline;
+import 'dart:ui' show Color, FontFeature;
+import 'package:flutter/painting.dart'
+    show
+        TextDecoration,
+        TextDecorationStyle,
+        TextStyle,
+        FontStyle,
+        FontWeight,
+        TextBaseline;
 import 'rust_bindings_generated.dart' as bind;
-export 'rust_bindings_generated.dart' show Style;
+export 'rust_bindings_generated.dart'
+    show
+        Style,
+        CallerSequence,
+        CallerReset,
+        NotePlacement,
+        LineBreaking,
+        TextDirection;
 
 // ignore: avoid_print
 void _log(String msg) => print(msg);
@@ -24,12 +38,73 @@ class Dimensions {
   });
 }
 
+/// Reader choices about what goes into the layout; see `LayoutOptions` in
+/// rust.h. The defaults match Rust's `LayoutOptions::default()`.
+class LayoutOptions {
+  final bool includeIntroductions;
+  final bool chapterLabelHeadings;
+  final bind.CallerSequence callerSequence;
+  final bind.CallerReset callerReset;
+  final bind.NotePlacement notePlacement;
+
+  /// Heading of the notes block, in the translation's language; empty
+  /// leaves the block without one.
+  final String notesTitle;
+  final bool showVerseNumbers;
+  final bool showFootnotes;
+  final bool showCrossReferences;
+  final bool showHeadings;
+  final bind.LineBreaking lineBreaking;
+  final bind.TextDirection textDirection;
+
+  /// BCP 47 tag choosing the hyphenation patterns; empty turns it off.
+  final String language;
+  final int orphanLines;
+  final int widowLines;
+
+  const LayoutOptions({
+    this.includeIntroductions = true,
+    this.chapterLabelHeadings = false,
+    this.callerSequence = bind.CallerSequence.CALLER_LETTERS,
+    this.callerReset = bind.CallerReset.CALLER_RESET_PAGE,
+    this.notePlacement = bind.NotePlacement.NOTES_PAGE,
+    this.notesTitle = '',
+    this.showVerseNumbers = true,
+    this.showFootnotes = true,
+    this.showCrossReferences = true,
+    this.showHeadings = true,
+    this.lineBreaking = bind.LineBreaking.LINE_BREAKING_GREEDY,
+    this.textDirection = bind.TextDirection.TEXT_DIRECTION_LTR,
+    this.language = '',
+    this.orphanLines = 2,
+    this.widowLines = 2,
+  });
+}
+
 class Text {
   final String text;
   final bind.Rectangle rect;
   final TextStyle style;
+  final bool rtl;
+
+  Text(this.text, this.rect, this.style, this.rtl);
+}
+
+class PageImage {
+  final String source;
+  final bind.Rectangle rect;
 
-  Text(this.text, this.rect, this.style);
+  PageImage(this.source, this.rect);
+}
+
+/// A boxed block's frame on a page, and whether the block is continued
+/// from the previous page or continues on the next.
+class PageFrame {
+  final bind.Rectangle rect;
+  final bool continued;
+  final bool continues;
+
+  PageFrame(this.rect, this.continued, this.continues);
 }
 
 class Index {
@@ -39,7 +114,25 @@ class Index {
   final int? chapter;
   final int? verse;
 
-  Index(this.page, this.book, this.header, [this.chapter, this.verse]);
+  /// Last verse of a bridge such as `3-4`; the same as [verse] otherwise.
+  final int? verseEnd;
+
+  /// Segment letter of a verse such as `12a`.
+  final String? segment;
+
+  /// Text of the section heading this index marks.
+  final String? heading;
+
+  Index(
+    this.page,
+    this.book,
+    this.header, [
+    this.chapter,
+    this.verse,
+    this.verseEnd,
+    this.segment,
+    this.heading,
+  ]);
 }
 
 /// Allocates error output pointers for FFI calls.
@@ -88,6 +181,30 @@ void registerFontFamily(
   _checkError(e.error, e.errorLen);
 }
 
+/// Registers Liang hyphenation patterns, in TeX format, for a language tag
+/// that [LayoutOptions.language] can name.
+void registerHyphenation(
+  Pointer<Void> renderer,
+  String language,
+  String patterns,
+) {
+  final languagePtr = language.toNativeUtf8();
+  final patternsPtr = patterns.toNativeUtf8();
+  final e = _allocError();
+  _bindings.register_hyphenation(
+    renderer,
+    languagePtr.cast<Char>(),
+    languagePtr.length,
+    patternsPtr.cast<Char>(),
+    patternsPtr.length,
+    e.error,
+    e.errorLen,
+  );
+  malloc.free(languagePtr);
+  malloc.free(patternsPtr);
+  _checkError(e.error, e.errorLen);
+}
+
 void registerStyle(
   Pointer<Void> renderer,
   bind.Style style,
@@ -101,24 +218,71 @@ void registerStyle(
   ctextStyle.ref.height = textStyle.height!;
   ctextStyle.ref.letter_spacing = textStyle.letterSpacing!;
   ctextStyle.ref.word_spacing = textStyle.wordSpacing!;
-  ctextStyle.ref.underline = (textStyle.decoration == TextDecoration.underline) ? 1 : 0;
+  ctextStyle.ref.decorationAsInt = _decoration(textStyle).value;
+  ctextStyle.ref.font_weight = textStyle.fontWeight?.value ?? 0;
+  ctextStyle.ref.italic = textStyle.fontStyle == FontStyle.italic ? 1 : 0;
+  ctextStyle.ref.small_caps = _hasSmallCaps(textStyle) ? 1 : 0;
+  ctextStyle.ref.color = textStyle.color?.toARGB32() ?? 0;
+  ctextStyle.ref.background_color =
+      textStyle.backgroundColor?.toARGB32() ?? 0;
   _bindings.register_style(renderer, style, ctextStyle);
 }
 
+bind.Decoration _decoration(TextStyle textStyle) {
+  final decoration = textStyle.decoration ?? TextDecoration.none;
+  if (decoration.contains(TextDecoration.lineThrough)) {
+    return bind.Decoration.DECORATION_STRIKE;
+  }
+  if (decoration.contains(TextDecoration.underline)) {
+    return textStyle.decorationStyle == TextDecorationStyle.dotted
+        ? bind.Decoration.DECORATION_DOTTED
+        : bind.Decoration.DECORATION_UNDERLINE;
+  }
+  return bind.Decoration.DECORATION_NONE;
+}
+
+bool _hasSmallCaps(TextStyle textStyle) =>
+    textStyle.fontFeatures?.any((f) => f.feature == 'smcp' && f.value != 0) ??
+    false;
+
+/// The nearest [FontWeight] to a CSS-style weight.
+FontWeight _fontWeight(int weight) =>
+    FontWeight.values[(weight ~/ 100 - 1).clamp(0, FontWeight.values.length - 1)];
+
 TextStyle toTextStyle(bind.TextStyle textStyle) {
   final fontFamily = textStyle.font_family.cast<Utf8>().toDartString(
     length: textStyle.font_family_len,
   );
+  final decoration = switch (textStyle.decoration) {
+    bind.Decoration.DECORATION_NONE => null,
+    bind.Decoration.DECORATION_UNDERLINE ||
+    bind.Decoration.DECORATION_DOTTED => TextDecoration.underline,
+    bind.Decoration.DECORATION_STRIKE => TextDecoration.lineThrough,
+  };
+  final underline = decoration == TextDecoration.underline;
   return TextStyle(
     fontFamily: fontFamily,
     fontSize: textStyle.font_size,
-    fontWeight: FontWeight.w700,
+    fontWeight: textStyle.font_weight > 0
+        ? _fontWeight(textStyle.font_weight)
+        : FontWeight.w700,
+    fontStyle: textStyle.italic != 0 ? FontStyle.italic : FontStyle.normal,
+    fontFeatures: textStyle.small_caps != 0
+        ? const [FontFeature.enable('smcp')]
+        : null,
     height: textStyle.height,
     letterSpacing: textStyle.letter_spacing,
     wordSpacing: textStyle.word_spacing,
     textBaseline: TextBaseline.alphabetic,
-    decoration: textStyle.underline != 0 ? TextDecoration.underline : null,
-    decorationColor: textStyle.underline != 0 ? const Color(0xFF71717A) : null,
+    color: textStyle.color != 0 ? Color(textStyle.color) : null,
+    backgroundColor: textStyle.background_color != 0
+        ? Color(textStyle.background_color)
+        : null,
+    decoration: decoration,
+    decorationStyle: textStyle.decoration == bind.Decoration.DECORATION_DOTTED
+        ? TextDecorationStyle.dotted
+        : null,
+    decorationColor: underline ? const Color(0xFF71717A) : null,
   );
 }
 
@@ -169,19 +333,102 @@ String getBookIdentifier(Pointer<Void> book) {
   return out.value.cast<Utf8>().toDartString(length: outLen.value);
 }
 
+/// Collects the names of a translation's books, to resolve references in
+/// cross-references. Add every book with [addBookNames] before [layout].
+Pointer<Void> getBookNames() {
+  return _bindings.book_names();
+}
+
+void addBookNames(Pointer<Void> bookNames, Pointer<Void> book) {
+  _bindings.add_book_names(bookNames, book);
+}
+
+/// Frees the names from [getBookNames] once no [layout] needs them.
+void freeBookNames(Pointer<Void> bookNames) {
+  _bindings.free_book_names(bookNames);
+}
+
+/// Loads the `\k` entries of a glossary (`GLO`) book.
+Pointer<Void> getGlossary(Pointer<Void> book) {
+  return _bindings.glossary(book);
+}
+
+/// Frees a glossary from [getGlossary].
+void freeGlossary(Pointer<Void> glossary) {
+  _bindings.free_glossary(glossary);
+}
+
+/// The glossary entry for a `\w` word on a page, by its index in [getPage].
+/// Throws if the word has no entry.
+String glossaryEntry(
+  Pointer<Void> glossary,
+  Pointer<Void> pages,
+  int pageIndex,
+  int fragmentIndex,
+) {
+  final out = malloc<Pointer<Char>>();
+  final outLen = malloc<Size>();
+  final e = _allocError();
+  _bindings.glossary_entry(
+    glossary,
+    pages,
+    pageIndex,
+    fragmentIndex,
+    out,
+    outLen,
+    e.error,
+    e.errorLen,
+  );
+  _checkError(e.error, e.errorLen);
+  return out.value.cast<Utf8>().toDartString(length: outLen.value);
+}
+
 Pointer<Void> layout(
   Pointer<Void> renderer,
   Pointer<Void> book,
-  Dimensions dim,
-) {
+  Dimensions dim, {
+  LayoutOptions options = const LayoutOptions(),
+  Pointer<Void>? bookNames,
+}) {
   _log('[FFI] layout: ${dim.width.toInt()}x${dim.height.toInt()}');
   final cdim = calloc<bind.Dimensions>();
   cdim.ref.width = dim.width;
   cdim.ref.height = dim.height;
   cdim.ref.header_height = dim.headerHeight;
   cdim.ref.drop_cap_padding = dim.dropCapPadding;
+  final language = options.language.toNativeUtf8();
+  final notesTitle = options.notesTitle.toNativeUtf8();
+  final coptions = calloc<bind.LayoutOptions>();
+  coptions.ref.include_introductions = options.includeIntroductions ? 1 : 0;
+  coptions.ref.chapter_label_headings = options.chapterLabelHeadings ? 1 : 0;
+  coptions.ref.caller_sequenceAsInt = options.callerSequence.value;
+  coptions.ref.caller_resetAsInt = options.callerReset.value;
+  coptions.ref.note_placementAsInt = options.notePlacement.value;
+  coptions.ref.notes_title = notesTitle.cast<Char>();
+  coptions.ref.notes_title_len = notesTitle.length;
+  coptions.ref.show_verse_numbers = options.showVerseNumbers ? 1 : 0;
+  coptions.ref.show_footnotes = options.showFootnotes ? 1 : 0;
+  coptions.ref.show_cross_references = options.showCrossReferences ? 1 : 0;
+  coptions.ref.show_headings = options.showHeadings ? 1 : 0;
+  coptions.ref.line_breakingAsInt = options.lineBreaking.value;
+  coptions.ref.text_directionAsInt = options.textDirection.value;
+  coptions.ref.language = language.cast<Char>();
+  coptions.ref.language_len = language.length;
+  coptions.ref.orphan_lines = options.orphanLines;
+  coptions.ref.widow_lines = options.widowLines;
   final e = _allocError();
-  final result = _bindings.layout(renderer, book, cdim, e.error, e.errorLen);
+  final result = _bindings.layout(
+    renderer,
+    book,
+    cdim,
+    coptions.ref,
+    bookNames ?? nullptr,
+    e.error,
+    e.errorLen,
+  );
+  calloc.free(coptions);
+  malloc.free(language);
+  malloc.free(notesTitle);
   _checkError(e.error, e.errorLen);
   return result;
 }
@@ -235,8 +482,45 @@ List<Text> getPage(Pointer<Void> renderer, Pointer<Void> pages, int pageIndex) {
       text.text.cast<Utf8>().toDartString(length: text.len),
       text.rect,
       toTextStyle(text.style),
+      text.rtl != 0,
+    );
+  });
+}
+
+List<PageImage> getPageImages(Pointer<Void> pages, int pageIndex) {
+  final out = malloc<Pointer<bind.Image>>();
+  final outLen = malloc<Size>();
+  final e = _allocError();
+
+  _bindings.page_images(pages, pageIndex, out, outLen, e.error, e.errorLen);
+  _checkError(e.error, e.errorLen);
+
+  final images = List.generate(outLen.value, (i) {
+    final image = (out.value + i).ref;
+    return PageImage(
+      image.source.cast<Utf8>().toDartString(length: image.source_len),
+      image.rect,
     );
   });
+  _bindings.free_images(out.value, outLen.value);
+  return images;
+}
+
+/// The frames of boxed blocks on a page, to draw behind its text.
+List<PageFrame> getPageFrames(Pointer<Void> pages, int pageIndex) {
+  final out = malloc<Pointer<bind.BoxFrame>>();
+  final outLen = malloc<Size>();
+  final e = _allocError();
+
+  _bindings.page_frames(pages, pageIndex, out, outLen, e.error, e.errorLen);
+  _checkError(e.error, e.errorLen);
+
+  final frames = List.generate(outLen.value, (i) {
+    final frame = (out.value + i).ref;
+    return PageFrame(frame.rect, frame.continued != 0, frame.continues != 0);
+  });
+  _bindings.free_frames(out.value, outLen.value);
+  return frames;
 }
 
 Uint8List serializeIndices(Pointer<Void> painter) {
@@ -274,11 +558,17 @@ Index getIndex(Pointer<Void> indices, Pointer<Void> index) {
   final bookLen = malloc<Size>();
   final header = malloc<Pointer<Utf8>>();
   final headerLen = malloc<Size>();
+  final heading = malloc<Pointer<Utf8>>();
+  final headingLen = malloc<Size>();
   final chapter = malloc<UnsignedShort>();
   final verse = malloc<UnsignedShort>();
+  final verseEnd = malloc<UnsignedShort>();
+  final segment = malloc<UnsignedInt>();
   final e = _allocError();
   chapter.value = 0;
   verse.value = 0;
+  verseEnd.value = 0;
+  segment.value = 0;
   _bindings.get_index(
     indices,
     index,
@@ -287,8 +577,12 @@ Index getIndex(Pointer<Void> indices, Pointer<Void> index) {
     bookLen,
     header.cast<Pointer<Char>>(),
     headerLen,
+    heading.cast<Pointer<Char>>(),
+    headingLen,
     chapter,
     verse,
+    verseEnd,
+    segment,
     e.error,
     e.errorLen,
   );
@@ -299,9 +593,37 @@ Index getIndex(Pointer<Void> indices, Pointer<Void> index) {
     header.value.toDartString(length: headerLen.value),
     chapter.value == 0 ? null : chapter.value,
     verse.value == 0 ? null : verse.value,
+    verseEnd.value == 0 ? null : verseEnd.value,
+    segment.value == 0 ? null : String.fromCharCode(segment.value),
+    heading.value == nullptr
+        ? null
+        : heading.value.toDartString(length: headingLen.value),
+  );
+}
+
+/// The page a verse starts on, including verses inside a bridge such as `3-4`.
+int findVerse(Pointer<Void> indices, String book, int chapter, int verse) {
+  final bookPtr = book.toNativeUtf8();
+  final page = malloc<Size>();
+  final e = _allocError();
+  _bindings.find_verse(
+    indices,
+    bookPtr.cast<Char>(),
+    bookPtr.length,
+    chapter,
+    verse,
+    page,
+    e.error,
+    e.errorLen,
   );
+  malloc.free(bookPtr);
+  _checkError(e.error, e.errorLen);
+  return page.value;
 }
 
+/// Layout of the serialized indices and verses; caches of them are keyed by it.
+int indexVersion() => _bindings.index_version();
+
 Uint8List serializeVerses(Pointer<Void> painter) {
   final out = malloc<Pointer<Uint8>>();
   final outLen = malloc<Size>();
@@ -330,7 +652,65 @@ Uint8List serializeVerseRanges(Pointer<Void> painter) {
   return out.value.asTypedList(outLen.value);
 }
 
-Pointer<Void> loadSearchEngine(
+Uint8List serializeCrossRefs(Pointer<Void> painter) {
+  final out = malloc<Pointer<Uint8>>();
+  final outLen = malloc<Size>();
+  final e = _allocError();
+
+  _bindings.serialize_cross_refs(
+    painter,
+    out.cast<Pointer<Char>>(),
+    outLen,
+    e.error,
+    e.errorLen,
+  );
+  _checkError(e.error, e.errorLen);
+  return out.value.asTypedList(outLen.value);
+}
+
+Pointer<Void> getArchivedCrossRefs(Uint8List crossRefs) {
+  final crossRefsPtr = _toNative(crossRefs);
+  final e = _allocError();
+  final result = _bindings.archived_cross_refs(
+    crossRefsPtr.cast<Char>(),
+    crossRefs.length,
+    e.error,
+    e.errorLen,
+  );
+  _checkError(e.error, e.errorLen);
+  return result;
+}
+
+/// The first and last index of each target of a linked cross-reference,
+/// for [getIndex] with a page map holding the target books. [link] is the
+/// fragment's `x-link` attribute.
+List<({Pointer<Void> start, Pointer<Void> end})> crossRefTargets(
+  Pointer<Void> crossRefs,
+  int link,
+) {
+  final starts = malloc<Pointer<Pointer<Void>>>();
+  final ends = malloc<Pointer<Pointer<Void>>>();
+  final outLen = malloc<Size>();
+  final e = _allocError();
+  _bindings.cross_ref_targets(
+    crossRefs,
+    link,
+    starts,
+    ends,
+    outLen,
+    e.error,
+    e.errorLen,
+  );
+  _checkError(e.error, e.errorLen);
+  final targets = List.generate(
+    outLen.value,
+    (i) => (start: starts.value[i], end: ends.value[i]),
+  );
+  _bindings.free_cross_ref_targets(starts.value, ends.value, outLen.value);
+  return targets;
+}
+
+
   Uint8List model,
   Uint8List tokenizer,
   String hnswDir,
@@ -406,9 +786,13 @@ Index getSearchResult(
   final headerLen = malloc<Size>();
   final chapter = malloc<UnsignedShort>();
   final verse = malloc<UnsignedShort>();
+  final verseEnd = malloc<UnsignedShort>();
+  final segment = malloc<UnsignedInt>();
   final e = _allocError();
   chapter.value = 0;
   verse.value = 0;
+  verseEnd.value = 0;
+  segment.value = 0;
   _bindings.get_search_result(
     engine,
     pageMap,
@@ -420,6 +804,8 @@ Index getSearchResult(
     headerLen,
     chapter,
     verse,
+    verseEnd,
+    segment,
     e.error,
     e.errorLen,
   );
@@ -430,6 +816,8 @@ Index getSearchResult(
     header.value.toDartString(length: headerLen.value),
     chapter.value == 0 ? null : chapter.value,
     verse.value == 0 ? null : verse.value,
+    verseEnd.value == 0 ? null : verseEnd.value,
+    segment.value == 0 ? null : String.fromCharCode(segment.value),
   );
 }
 
@@ -116,6 +116,53 @@ class RustBindings {
         void Function(ffi.Pointer<ffi.Void>, int, ffi.Pointer<TextStyle>)
       >();
 
+  void register_hyphenation(
+    ffi.Pointer<ffi.Void> renderer,
+    ffi.Pointer<ffi.Char> language,
+    int language_len,
+    ffi.Pointer<ffi.Char> patterns,
+    int patterns_len,
+    ffi.Pointer<ffi.Pointer<ffi.Char>> out_error,
+    ffi.Pointer<ffi.Size> out_error_len,
+  ) {
+    return _register_hyphenation(
+      renderer,
+      language,
+      language_len,
+      patterns,
+      patterns_len,
+      out_error,
+      out_error_len,
+    );
+  }
+
+  late final _register_hyphenationPtr =
+      _lookup<
+        ffi.NativeFunction<
+          ffi.Void Function(
+            ffi.Pointer<ffi.Void>,
+            ffi.Pointer<ffi.Char>,
+            ffi.Size,
+            ffi.Pointer<ffi.Char>,
+            ffi.Size,
+            ffi.Pointer<ffi.Pointer<ffi.Char>>,
+            ffi.Pointer<ffi.Size>,
+          )
+        >
+      >('register_hyphenation');
+  late final _register_hyphenation = _register_hyphenationPtr
+      .asFunction<
+        void Function(
+          ffi.Pointer<ffi.Void>,
+          ffi.Pointer<ffi.Char>,
+          int,
+          ffi.Pointer<ffi.Char>,
+          int,
+          ffi.Pointer<ffi.Pointer<ffi.Char>>,
+          ffi.Pointer<ffi.Size>,
+        )
+      >();
+
   void serialize_usfm(
     ffi.Pointer<ffi.Char> usfm,
     int usfm_len,
@@ -222,14 +269,175 @@ class RustBindings {
         )
       >();
 
+  void book_name(
+    ffi.Pointer<ffi.Void> usfm,
+    BookName name,
+    ffi.Pointer<ffi.Pointer<ffi.Char>> out,
+    ffi.Pointer<ffi.Size> out_len,
+    ffi.Pointer<ffi.Pointer<ffi.Char>> out_error,
+    ffi.Pointer<ffi.Size> out_error_len,
+  ) {
+    return _book_name(usfm, name.value, out, out_len, out_error, out_error_len);
+  }
+
+  late final _book_namePtr =
+      _lookup<
+        ffi.NativeFunction<
+          ffi.Void Function(
+            ffi.Pointer<ffi.Void>,
+            ffi.UnsignedInt,
+            ffi.Pointer<ffi.Pointer<ffi.Char>>,
+            ffi.Pointer<ffi.Size>,
+            ffi.Pointer<ffi.Pointer<ffi.Char>>,
+            ffi.Pointer<ffi.Size>,
+          )
+        >
+      >('book_name');
+  late final _book_name = _book_namePtr
+      .asFunction<
+        void Function(
+          ffi.Pointer<ffi.Void>,
+          int,
+          ffi.Pointer<ffi.Pointer<ffi.Char>>,
+          ffi.Pointer<ffi.Size>,
+          ffi.Pointer<ffi.Pointer<ffi.Char>>,
+          ffi.Pointer<ffi.Size>,
+        )
+      >();
+
+  ffi.Pointer<ffi.Void> book_names() {
+    return _book_names();
+  }
+
+  late final _book_namesPtr =
+      _lookup<ffi.NativeFunction<ffi.Pointer<ffi.Void> Function()>>(
+        'book_names',
+      );
+  late final _book_names = _book_namesPtr
+      .asFunction<ffi.Pointer<ffi.Void> Function()>();
+
+  void add_book_names(
+    ffi.Pointer<ffi.Void> book_names,
+    ffi.Pointer<ffi.Void> usfm,
+  ) {
+    return _add_book_names(book_names, usfm);
+  }
+
+  late final _add_book_namesPtr =
+      _lookup<
+        ffi.NativeFunction<
+          ffi.Void Function(ffi.Pointer<ffi.Void>, ffi.Pointer<ffi.Void>)
+        >
+      >('add_book_names');
+  late final _add_book_names = _add_book_namesPtr
+      .asFunction<
+        void Function(ffi.Pointer<ffi.Void>, ffi.Pointer<ffi.Void>)
+      >();
+
+  void free_book_names(ffi.Pointer<ffi.Void> book_names) {
+    return _free_book_names(book_names);
+  }
+
+  late final _free_book_namesPtr =
+      _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Pointer<ffi.Void>)>>(
+        'free_book_names',
+      );
+  late final _free_book_names = _free_book_namesPtr
+      .asFunction<void Function(ffi.Pointer<ffi.Void>)>();
+
+  ffi.Pointer<ffi.Void> glossary(ffi.Pointer<ffi.Void> usfm) {
+    return _glossary(usfm);
+  }
+
+  late final _glossaryPtr =
+      _lookup<
+        ffi.NativeFunction<
+          ffi.Pointer<ffi.Void> Function(ffi.Pointer<ffi.Void>)
+        >
+      >('glossary');
+  late final _glossary = _glossaryPtr
+      .asFunction<ffi.Pointer<ffi.Void> Function(ffi.Pointer<ffi.Void>)>();
+
+  void free_glossary(ffi.Pointer<ffi.Void> glossary) {
+    return _free_glossary(glossary);
+  }
+
+  late final _free_glossaryPtr =
+      _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Pointer<ffi.Void>)>>(
+        'free_glossary',
+      );
+  late final _free_glossary = _free_glossaryPtr
+      .asFunction<void Function(ffi.Pointer<ffi.Void>)>();
+
+  void glossary_entry(
+    ffi.Pointer<ffi.Void> glossary,
+    ffi.Pointer<ffi.Void> archived_pages,
+    int page,
+    int fragment,
+    ffi.Pointer<ffi.Pointer<ffi.Char>> out,
+    ffi.Pointer<ffi.Size> out_len,
+    ffi.Pointer<ffi.Pointer<ffi.Char>> out_error,
+    ffi.Pointer<ffi.Size> out_error_len,
+  ) {
+    return _glossary_entry(
+      glossary,
+      archived_pages,
+      page,
+      fragment,
+      out,
+      out_len,
+      out_error,
+      out_error_len,
+    );
+  }
+
+  late final _glossary_entryPtr =
+      _lookup<
+        ffi.NativeFunction<
+          ffi.Void Function(
+            ffi.Pointer<ffi.Void>,
+            ffi.Pointer<ffi.Void>,
+            ffi.Size,
+            ffi.Size,
+            ffi.Pointer<ffi.Pointer<ffi.Char>>,
+            ffi.Pointer<ffi.Size>,
+            ffi.Pointer<ffi.Pointer<ffi.Char>>,
+            ffi.Pointer<ffi.Size>,
+          )
+        >
+      >('glossary_entry');
+  late final _glossary_entry = _glossary_entryPtr
+      .asFunction<
+        void Function(
+          ffi.Pointer<ffi.Void>,
+          ffi.Pointer<ffi.Void>,
+          int,
+          int,
+          ffi.Pointer<ffi.Pointer<ffi.Char>>,
+          ffi.Pointer<ffi.Size>,
+          ffi.Pointer<ffi.Pointer<ffi.Char>>,
+          ffi.Pointer<ffi.Size>,
+        )
+      >();
+
   ffi.Pointer<ffi.Void> layout(
     ffi.Pointer<ffi.Void> renderer,
     ffi.Pointer<ffi.Void> usfm,
     ffi.Pointer<Dimensions> dim,
+    LayoutOptions options,
+    ffi.Pointer<ffi.Void> book_names,
     ffi.Pointer<ffi.Pointer<ffi.Char>> out_error,
     ffi.Pointer<ffi.Size> out_error_len,
   ) {
-    return _layout(renderer, usfm, dim, out_error, out_error_len);
+    return _layout(
+      renderer,
+      usfm,
+      dim,
+      options,
+      book_names,
+      out_error,
+      out_error_len,
+    );
   }
 
   late final _layoutPtr =
@@ -239,6 +447,8 @@ class RustBindings {
             ffi.Pointer<ffi.Void>,
             ffi.Pointer<ffi.Void>,
             ffi.Pointer<Dimensions>,
+            LayoutOptions,
+            ffi.Pointer<ffi.Void>,
             ffi.Pointer<ffi.Pointer<ffi.Char>>,
             ffi.Pointer<ffi.Size>,
           )
@@ -250,6 +460,8 @@ class RustBindings {
           ffi.Pointer<ffi.Void>,
           ffi.Pointer<ffi.Void>,
           ffi.Pointer<Dimensions>,
+          LayoutOptions,
+          ffi.Pointer<ffi.Void>,
           ffi.Pointer<ffi.Pointer<ffi.Char>>,
           ffi.Pointer<ffi.Size>,
         )
@@ -349,44 +561,411 @@ class RustBindings {
     );
   }
 
-  late final _pagePtr =
+  late final _pagePtr =
+      _lookup<
+        ffi.NativeFunction<
+          ffi.Void Function(
+            ffi.Pointer<ffi.Void>,
+            ffi.Pointer<ffi.Void>,
+            ffi.Size,
+            ffi.Pointer<ffi.Pointer<Text>>,
+            ffi.Pointer<ffi.Size>,
+            ffi.Pointer<ffi.Pointer<ffi.Char>>,
+            ffi.Pointer<ffi.Size>,
+          )
+        >
+      >('page');
+  late final _page = _pagePtr
+      .asFunction<
+        void Function(
+          ffi.Pointer<ffi.Void>,
+          ffi.Pointer<ffi.Void>,
+          int,
+          ffi.Pointer<ffi.Pointer<Text>>,
+          ffi.Pointer<ffi.Size>,
+          ffi.Pointer<ffi.Pointer<ffi.Char>>,
+          ffi.Pointer<ffi.Size>,
+        )
+      >();
+
+  void fragment_attributes(
+    ffi.Pointer<ffi.Void> archived_pages,
+    int page,
+    int fragment,
+    ffi.Pointer<ffi.Pointer<Attribute>> out,
+    ffi.Pointer<ffi.Size> out_len,
+    ffi.Pointer<ffi.Pointer<ffi.Char>> out_error,
+    ffi.Pointer<ffi.Size> out_error_len,
+  ) {
+    return _fragment_attributes(
+      archived_pages,
+      page,
+      fragment,
+      out,
+      out_len,
+      out_error,
+      out_error_len,
+    );
+  }
+
+  late final _fragment_attributesPtr =
+      _lookup<
+        ffi.NativeFunction<
+          ffi.Void Function(
+            ffi.Pointer<ffi.Void>,
+            ffi.Size,
+            ffi.Size,
+            ffi.Pointer<ffi.Pointer<Attribute>>,
+            ffi.Pointer<ffi.Size>,
+            ffi.Pointer<ffi.Pointer<ffi.Char>>,
+            ffi.Pointer<ffi.Size>,
+          )
+        >
+      >('fragment_attributes');
+  late final _fragment_attributes = _fragment_attributesPtr
+      .asFunction<
+        void Function(
+          ffi.Pointer<ffi.Void>,
+          int,
+          int,
+          ffi.Pointer<ffi.Pointer<Attribute>>,
+          ffi.Pointer<ffi.Size>,
+          ffi.Pointer<ffi.Pointer<ffi.Char>>,
+          ffi.Pointer<ffi.Size>,
+        )
+      >();
+
+  void free_attributes(ffi.Pointer<Attribute> attributes, int len) {
+    return _free_attributes(attributes, len);
+  }
+
+  late final _free_attributesPtr =
+      _lookup<
+        ffi.NativeFunction<ffi.Void Function(ffi.Pointer<Attribute>, ffi.Size)>
+      >('free_attributes');
+  late final _free_attributes = _free_attributesPtr
+      .asFunction<void Function(ffi.Pointer<Attribute>, int)>();
+
+  void page_images(
+    ffi.Pointer<ffi.Void> archived_pages,
+    int page,
+    ffi.Pointer<ffi.Pointer<Image>> out,
+    ffi.Pointer<ffi.Size> out_len,
+    ffi.Pointer<ffi.Pointer<ffi.Char>> out_error,
+    ffi.Pointer<ffi.Size> out_error_len,
+  ) {
+    return _page_images(
+      archived_pages,
+      page,
+      out,
+      out_len,
+      out_error,
+      out_error_len,
+    );
+  }
+
+  late final _page_imagesPtr =
+      _lookup<
+        ffi.NativeFunction<
+          ffi.Void Function(
+            ffi.Pointer<ffi.Void>,
+            ffi.Size,
+            ffi.Pointer<ffi.Pointer<Image>>,
+            ffi.Pointer<ffi.Size>,
+            ffi.Pointer<ffi.Pointer<ffi.Char>>,
+            ffi.Pointer<ffi.Size>,
+          )
+        >
+      >('page_images');
+  late final _page_images = _page_imagesPtr
+      .asFunction<
+        void Function(
+          ffi.Pointer<ffi.Void>,
+          int,
+          ffi.Pointer<ffi.Pointer<Image>>,
+          ffi.Pointer<ffi.Size>,
+          ffi.Pointer<ffi.Pointer<ffi.Char>>,
+          ffi.Pointer<ffi.Size>,
+        )
+      >();
+
+  void free_images(ffi.Pointer<Image> images, int len) {
+    return _free_images(images, len);
+  }
+
+  late final _free_imagesPtr =
+      _lookup<
+        ffi.NativeFunction<ffi.Void Function(ffi.Pointer<Image>, ffi.Size)>
+      >('free_images');
+  late final _free_images = _free_imagesPtr
+      .asFunction<void Function(ffi.Pointer<Image>, int)>();
+
+  void page_frames(
+    ffi.Pointer<ffi.Void> archived_pages,
+    int page,
+    ffi.Pointer<ffi.Pointer<BoxFrame>> out,
+    ffi.Pointer<ffi.Size> out_len,
+    ffi.Pointer<ffi.Pointer<ffi.Char>> out_error,
+    ffi.Pointer<ffi.Size> out_error_len,
+  ) {
+    return _page_frames(
+      archived_pages,
+      page,
+      out,
+      out_len,
+      out_error,
+      out_error_len,
+    );
+  }
+
+  late final _page_framesPtr =
+      _lookup<
+        ffi.NativeFunction<
+          ffi.Void Function(
+            ffi.Pointer<ffi.Void>,
+            ffi.Size,
+            ffi.Pointer<ffi.Pointer<BoxFrame>>,
+            ffi.Pointer<ffi.Size>,
+            ffi.Pointer<ffi.Pointer<ffi.Char>>,
+            ffi.Pointer<ffi.Size>,
+          )
+        >
+      >('page_frames');
+  late final _page_frames = _page_framesPtr
+      .asFunction<
+        void Function(
+          ffi.Pointer<ffi.Void>,
+          int,
+          ffi.Pointer<ffi.Pointer<BoxFrame>>,
+          ffi.Pointer<ffi.Size>,
+          ffi.Pointer<ffi.Pointer<ffi.Char>>,
+          ffi.Pointer<ffi.Size>,
+        )
+      >();
+
+  void free_frames(ffi.Pointer<BoxFrame> frames, int len) {
+    return _free_frames(frames, len);
+  }
+
+  late final _free_framesPtr =
+      _lookup<
+        ffi.NativeFunction<ffi.Void Function(ffi.Pointer<BoxFrame>, ffi.Size)>
+      >('free_frames');
+  late final _free_frames = _free_framesPtr
+      .asFunction<void Function(ffi.Pointer<BoxFrame>, int)>();
+
+  int index_version() {
+    return _index_version();
+  }
+
+  late final _index_versionPtr =
+      _lookup<ffi.NativeFunction<ffi.UnsignedInt Function()>>('index_version');
+  late final _index_version = _index_versionPtr.asFunction<int Function()>();
+
+  void serialize_indices(
+    ffi.Pointer<ffi.Void> painter,
+    ffi.Pointer<ffi.Pointer<ffi.Char>> out,
+    ffi.Pointer<ffi.Size> out_len,
+    ffi.Pointer<ffi.Pointer<ffi.Char>> out_error,
+    ffi.Pointer<ffi.Size> out_error_len,
+  ) {
+    return _serialize_indices(painter, out, out_len, out_error, out_error_len);
+  }
+
+  late final _serialize_indicesPtr =
+      _lookup<
+        ffi.NativeFunction<
+          ffi.Void Function(
+            ffi.Pointer<ffi.Void>,
+            ffi.Pointer<ffi.Pointer<ffi.Char>>,
+            ffi.Pointer<ffi.Size>,
+            ffi.Pointer<ffi.Pointer<ffi.Char>>,
+            ffi.Pointer<ffi.Size>,
+          )
+        >
+      >('serialize_indices');
+  late final _serialize_indices = _serialize_indicesPtr
+      .asFunction<
+        void Function(
+          ffi.Pointer<ffi.Void>,
+          ffi.Pointer<ffi.Pointer<ffi.Char>>,
+          ffi.Pointer<ffi.Size>,
+          ffi.Pointer<ffi.Pointer<ffi.Char>>,
+          ffi.Pointer<ffi.Size>,
+        )
+      >();
+
+  ffi.Pointer<ffi.Void> archived_indices(
+    ffi.Pointer<ffi.Char> indices,
+    int indices_len,
+    ffi.Pointer<ffi.Pointer<ffi.Char>> out_error,
+    ffi.Pointer<ffi.Size> out_error_len,
+  ) {
+    return _archived_indices(indices, indices_len, out_error, out_error_len);
+  }
+
+  late final _archived_indicesPtr =
+      _lookup<
+        ffi.NativeFunction<
+          ffi.Pointer<ffi.Void> Function(
+            ffi.Pointer<ffi.Char>,
+            ffi.Size,
+            ffi.Pointer<ffi.Pointer<ffi.Char>>,
+            ffi.Pointer<ffi.Size>,
+          )
+        >
+      >('archived_indices');
+  late final _archived_indices = _archived_indicesPtr
+      .asFunction<
+        ffi.Pointer<ffi.Void> Function(
+          ffi.Pointer<ffi.Char>,
+          int,
+          ffi.Pointer<ffi.Pointer<ffi.Char>>,
+          ffi.Pointer<ffi.Size>,
+        )
+      >();
+
+  void get_index(
+    ffi.Pointer<ffi.Void> archived_indices,
+    ffi.Pointer<ffi.Void> index,
+    ffi.Pointer<ffi.Size> out_page,
+    ffi.Pointer<ffi.Pointer<ffi.Char>> out_book,
+    ffi.Pointer<ffi.Size> out_book_len,
+    ffi.Pointer<ffi.Pointer<ffi.Char>> out_header,
+    ffi.Pointer<ffi.Size> out_header_len,
+    ffi.Pointer<ffi.Pointer<ffi.Char>> out_heading,
+    ffi.Pointer<ffi.Size> out_heading_len,
+    ffi.Pointer<ffi.UnsignedShort> out_chapter,
+    ffi.Pointer<ffi.UnsignedShort> out_verse,
+    ffi.Pointer<ffi.UnsignedShort> out_verse_end,
+    ffi.Pointer<ffi.UnsignedInt> out_segment,
+    ffi.Pointer<ffi.Pointer<ffi.Char>> out_error,
+    ffi.Pointer<ffi.Size> out_error_len,
+  ) {
+    return _get_index(
+      archived_indices,
+      index,
+      out_page,
+      out_book,
+      out_book_len,
+      out_header,
+      out_header_len,
+      out_heading,
+      out_heading_len,
+      out_chapter,
+      out_verse,
+      out_verse_end,
+      out_segment,
+      out_error,
+      out_error_len,
+    );
+  }
+
+  late final _get_indexPtr =
+      _lookup<
+        ffi.NativeFunction<
+          ffi.Void Function(
+            ffi.Pointer<ffi.Void>,
+            ffi.Pointer<ffi.Void>,
+            ffi.Pointer<ffi.Size>,
+            ffi.Pointer<ffi.Pointer<ffi.Char>>,
+            ffi.Pointer<ffi.Size>,
+            ffi.Pointer<ffi.Pointer<ffi.Char>>,
+            ffi.Pointer<ffi.Size>,
+            ffi.Pointer<ffi.Pointer<ffi.Char>>,
+            ffi.Pointer<ffi.Size>,
+            ffi.Pointer<ffi.UnsignedShort>,
+            ffi.Pointer<ffi.UnsignedShort>,
+            ffi.Pointer<ffi.UnsignedShort>,
+            ffi.Pointer<ffi.UnsignedInt>,
+            ffi.Pointer<ffi.Pointer<ffi.Char>>,
+            ffi.Pointer<ffi.Size>,
+          )
+        >
+      >('get_index');
+  late final _get_index = _get_indexPtr
+      .asFunction<
+        void Function(
+          ffi.Pointer<ffi.Void>,
+          ffi.Pointer<ffi.Void>,
+          ffi.Pointer<ffi.Size>,
+          ffi.Pointer<ffi.Pointer<ffi.Char>>,
+          ffi.Pointer<ffi.Size>,
+          ffi.Pointer<ffi.Pointer<ffi.Char>>,
+          ffi.Pointer<ffi.Size>,
+          ffi.Pointer<ffi.Pointer<ffi.Char>>,
+          ffi.Pointer<ffi.Size>,
+          ffi.Pointer<ffi.UnsignedShort>,
+          ffi.Pointer<ffi.UnsignedShort>,
+          ffi.Pointer<ffi.UnsignedShort>,
+          ffi.Pointer<ffi.UnsignedInt>,
+          ffi.Pointer<ffi.Pointer<ffi.Char>>,
+          ffi.Pointer<ffi.Size>,
+        )
+      >();
+
+  void find_verse(
+    ffi.Pointer<ffi.Void> archived_indices,
+    ffi.Pointer<ffi.Char> book,
+    int book_len,
+    int chapter,
+    int verse,
+    ffi.Pointer<ffi.Size> out_page,
+    ffi.Pointer<ffi.Pointer<ffi.Char>> out_error,
+    ffi.Pointer<ffi.Size> out_error_len,
+  ) {
+    return _find_verse(
+      archived_indices,
+      book,
+      book_len,
+      chapter,
+      verse,
+      out_page,
+      out_error,
+      out_error_len,
+    );
+  }
+
+  late final _find_versePtr =
       _lookup<
         ffi.NativeFunction<
           ffi.Void Function(
             ffi.Pointer<ffi.Void>,
-            ffi.Pointer<ffi.Void>,
+            ffi.Pointer<ffi.Char>,
             ffi.Size,
-            ffi.Pointer<ffi.Pointer<Text>>,
+            ffi.UnsignedShort,
+            ffi.UnsignedShort,
             ffi.Pointer<ffi.Size>,
             ffi.Pointer<ffi.Pointer<ffi.Char>>,
             ffi.Pointer<ffi.Size>,
           )
         >
-      >('page');
-  late final _page = _pagePtr
+      >('find_verse');
+  late final _find_verse = _find_versePtr
       .asFunction<
         void Function(
           ffi.Pointer<ffi.Void>,
-          ffi.Pointer<ffi.Void>,
+          ffi.Pointer<ffi.Char>,
+          int,
+          int,
           int,
-          ffi.Pointer<ffi.Pointer<Text>>,
           ffi.Pointer<ffi.Size>,
           ffi.Pointer<ffi.Pointer<ffi.Char>>,
           ffi.Pointer<ffi.Size>,
         )
       >();
 
-  void serialize_indices(
+  void serialize_verses(
     ffi.Pointer<ffi.Void> painter,
     ffi.Pointer<ffi.Pointer<ffi.Char>> out,
     ffi.Pointer<ffi.Size> out_len,
     ffi.Pointer<ffi.Pointer<ffi.Char>> out_error,
     ffi.Pointer<ffi.Size> out_error_len,
   ) {
-    return _serialize_indices(painter, out, out_len, out_error, out_error_len);
+    return _serialize_verses(painter, out, out_len, out_error, out_error_len);
   }
 
-  late final _serialize_indicesPtr =
+  late final _serialize_versesPtr =
       _lookup<
         ffi.NativeFunction<
           ffi.Void Function(
@@ -397,8 +976,8 @@ class RustBindings {
             ffi.Pointer<ffi.Size>,
           )
         >
-      >('serialize_indices');
-  late final _serialize_indices = _serialize_indicesPtr
+      >('serialize_verses');
+  late final _serialize_verses = _serialize_versesPtr
       .asFunction<
         void Function(
           ffi.Pointer<ffi.Void>,
@@ -409,156 +988,178 @@ class RustBindings {
         )
       >();
 
-  ffi.Pointer<ffi.Void> archived_indices(
-    ffi.Pointer<ffi.Char> indices,
-    int indices_len,
-    ffi.Pointer<ffi.Pointer<ffi.Char>> out_error,
-    ffi.Pointer<ffi.Size> out_error_len,
+  void serialize_verse_ranges(
+    ffi.Pointer<ffi.Void> painter,
+    ffi.Pointer<ffi.Pointer<ffi.Char>> out,
+    ffi.Pointer<ffi.Size> out_len,
   ) {
-    return _archived_indices(indices, indices_len, out_error, out_error_len);
+    return _serialize_verse_ranges(painter, out, out_len);
   }
 
-  late final _archived_indicesPtr =
+  late final _serialize_verse_rangesPtr =
       _lookup<
         ffi.NativeFunction<
-          ffi.Pointer<ffi.Void> Function(
-            ffi.Pointer<ffi.Char>,
-            ffi.Size,
+          ffi.Void Function(
+            ffi.Pointer<ffi.Void>,
             ffi.Pointer<ffi.Pointer<ffi.Char>>,
             ffi.Pointer<ffi.Size>,
           )
         >
-      >('archived_indices');
-  late final _archived_indices = _archived_indicesPtr
+      >('serialize_verse_ranges');
+  late final _serialize_verse_ranges = _serialize_verse_rangesPtr
       .asFunction<
-        ffi.Pointer<ffi.Void> Function(
-          ffi.Pointer<ffi.Char>,
-          int,
+        void Function(
+          ffi.Pointer<ffi.Void>,
           ffi.Pointer<ffi.Pointer<ffi.Char>>,
           ffi.Pointer<ffi.Size>,
         )
       >();
 
-  void get_index(
-    ffi.Pointer<ffi.Void> archived_indices,
-    ffi.Pointer<ffi.Void> index,
-    ffi.Pointer<ffi.Size> out_page,
-    ffi.Pointer<ffi.Pointer<ffi.Char>> out_book,
-    ffi.Pointer<ffi.Size> out_book_len,
-    ffi.Pointer<ffi.Pointer<ffi.Char>> out_header,
-    ffi.Pointer<ffi.Size> out_header_len,
-    ffi.Pointer<ffi.UnsignedShort> out_chapter,
-    ffi.Pointer<ffi.UnsignedShort> out_verse,
+  void serialize_cross_refs(
+    ffi.Pointer<ffi.Void> painter,
+    ffi.Pointer<ffi.Pointer<ffi.Char>> out,
+    ffi.Pointer<ffi.Size> out_len,
     ffi.Pointer<ffi.Pointer<ffi.Char>> out_error,
     ffi.Pointer<ffi.Size> out_error_len,
   ) {
-    return _get_index(
-      archived_indices,
-      index,
-      out_page,
-      out_book,
-      out_book_len,
-      out_header,
-      out_header_len,
-      out_chapter,
-      out_verse,
+    return _serialize_cross_refs(
+      painter,
+      out,
+      out_len,
       out_error,
       out_error_len,
     );
   }
 
-  late final _get_indexPtr =
+  late final _serialize_cross_refsPtr =
       _lookup<
         ffi.NativeFunction<
           ffi.Void Function(
             ffi.Pointer<ffi.Void>,
-            ffi.Pointer<ffi.Void>,
-            ffi.Pointer<ffi.Size>,
-            ffi.Pointer<ffi.Pointer<ffi.Char>>,
-            ffi.Pointer<ffi.Size>,
             ffi.Pointer<ffi.Pointer<ffi.Char>>,
             ffi.Pointer<ffi.Size>,
-            ffi.Pointer<ffi.UnsignedShort>,
-            ffi.Pointer<ffi.UnsignedShort>,
             ffi.Pointer<ffi.Pointer<ffi.Char>>,
             ffi.Pointer<ffi.Size>,
           )
         >
-      >('get_index');
-  late final _get_index = _get_indexPtr
+      >('serialize_cross_refs');
+  late final _serialize_cross_refs = _serialize_cross_refsPtr
       .asFunction<
         void Function(
           ffi.Pointer<ffi.Void>,
-          ffi.Pointer<ffi.Void>,
-          ffi.Pointer<ffi.Size>,
           ffi.Pointer<ffi.Pointer<ffi.Char>>,
           ffi.Pointer<ffi.Size>,
           ffi.Pointer<ffi.Pointer<ffi.Char>>,
           ffi.Pointer<ffi.Size>,
-          ffi.Pointer<ffi.UnsignedShort>,
-          ffi.Pointer<ffi.UnsignedShort>,
+        )
+      >();
+
+  ffi.Pointer<ffi.Void> archived_cross_refs(
+    ffi.Pointer<ffi.Char> cross_refs,
+    int cross_refs_len,
+    ffi.Pointer<ffi.Pointer<ffi.Char>> out_error,
+    ffi.Pointer<ffi.Size> out_error_len,
+  ) {
+    return _archived_cross_refs(
+      cross_refs,
+      cross_refs_len,
+      out_error,
+      out_error_len,
+    );
+  }
+
+  late final _archived_cross_refsPtr =
+      _lookup<
+        ffi.NativeFunction<
+          ffi.Pointer<ffi.Void> Function(
+            ffi.Pointer<ffi.Char>,
+            ffi.Size,
+            ffi.Pointer<ffi.Pointer<ffi.Char>>,
+            ffi.Pointer<ffi.Size>,
+          )
+        >
+      >('archived_cross_refs');
+  late final _archived_cross_refs = _archived_cross_refsPtr
+      .asFunction<
+        ffi.Pointer<ffi.Void> Function(
+          ffi.Pointer<ffi.Char>,
+          int,
           ffi.Pointer<ffi.Pointer<ffi.Char>>,
           ffi.Pointer<ffi.Size>,
         )
       >();
 
-  void serialize_verses(
-    ffi.Pointer<ffi.Void> painter,
-    ffi.Pointer<ffi.Pointer<ffi.Char>> out,
+  void cross_ref_targets(
+    ffi.Pointer<ffi.Void> archived_cross_refs,
+    int link,
+    ffi.Pointer<ffi.Pointer<ffi.Pointer<ffi.Void>>> out_starts,
+    ffi.Pointer<ffi.Pointer<ffi.Pointer<ffi.Void>>> out_ends,
     ffi.Pointer<ffi.Size> out_len,
     ffi.Pointer<ffi.Pointer<ffi.Char>> out_error,
     ffi.Pointer<ffi.Size> out_error_len,
   ) {
-    return _serialize_verses(painter, out, out_len, out_error, out_error_len);
+    return _cross_ref_targets(
+      archived_cross_refs,
+      link,
+      out_starts,
+      out_ends,
+      out_len,
+      out_error,
+      out_error_len,
+    );
   }
 
-  late final _serialize_versesPtr =
+  late final _cross_ref_targetsPtr =
       _lookup<
         ffi.NativeFunction<
           ffi.Void Function(
             ffi.Pointer<ffi.Void>,
-            ffi.Pointer<ffi.Pointer<ffi.Char>>,
+            ffi.Size,
+            ffi.Pointer<ffi.Pointer<ffi.Pointer<ffi.Void>>>,
+            ffi.Pointer<ffi.Pointer<ffi.Pointer<ffi.Void>>>,
             ffi.Pointer<ffi.Size>,
             ffi.Pointer<ffi.Pointer<ffi.Char>>,
             ffi.Pointer<ffi.Size>,
           )
         >
-      >('serialize_verses');
-  late final _serialize_verses = _serialize_versesPtr
+      >('cross_ref_targets');
+  late final _cross_ref_targets = _cross_ref_targetsPtr
       .asFunction<
         void Function(
           ffi.Pointer<ffi.Void>,
-          ffi.Pointer<ffi.Pointer<ffi.Char>>,
+          int,
+          ffi.Pointer<ffi.Pointer<ffi.Pointer<ffi.Void>>>,
+          ffi.Pointer<ffi.Pointer<ffi.Pointer<ffi.Void>>>,
           ffi.Pointer<ffi.Size>,
           ffi.Pointer<ffi.Pointer<ffi.Char>>,
           ffi.Pointer<ffi.Size>,
         )
       >();
 
-  void serialize_verse_ranges(
-    ffi.Pointer<ffi.Void> painter,
-    ffi.Pointer<ffi.Pointer<ffi.Char>> out,
-    ffi.Pointer<ffi.Size> out_len,
+  void free_cross_ref_targets(
+    ffi.Pointer<ffi.Pointer<ffi.Void>> starts,
+    ffi.Pointer<ffi.Pointer<ffi.Void>> ends,
+    int len,
   ) {
-    return _serialize_verse_ranges(painter, out, out_len);
+    return _free_cross_ref_targets(starts, ends, len);
   }
 
-  late final _serialize_verse_rangesPtr =
+  late final _free_cross_ref_targetsPtr =
       _lookup<
         ffi.NativeFunction<
           ffi.Void Function(
-            ffi.Pointer<ffi.Void>,
-            ffi.Pointer<ffi.Pointer<ffi.Char>>,
-            ffi.Pointer<ffi.Size>,
+            ffi.Pointer<ffi.Pointer<ffi.Void>>,
+            ffi.Pointer<ffi.Pointer<ffi.Void>>,
+            ffi.Size,
           )
         >
-      >('serialize_verse_ranges');
-  late final _serialize_verse_ranges = _serialize_verse_rangesPtr
+      >('free_cross_ref_targets');
+  late final _free_cross_ref_targets = _free_cross_ref_targetsPtr
       .asFunction<
         void Function(
-          ffi.Pointer<ffi.Void>,
-          ffi.Pointer<ffi.Pointer<ffi.Char>>,
-          ffi.Pointer<ffi.Size>,
+          ffi.Pointer<ffi.Pointer<ffi.Void>>,
+          ffi.Pointer<ffi.Pointer<ffi.Void>>,
+          int,
         )
       >();
 
@@ -699,6 +1300,8 @@ class RustBindings {
     ffi.Pointer<ffi.Size> out_header_len,
     ffi.Pointer<ffi.UnsignedShort> out_chapter,
     ffi.Pointer<ffi.UnsignedShort> out_verse,
+    ffi.Pointer<ffi.UnsignedShort> out_verse_end,
+    ffi.Pointer<ffi.UnsignedInt> out_segment,
     ffi.Pointer<ffi.Pointer<ffi.Char>> out_error,
     ffi.Pointer<ffi.Size> out_error_len,
   ) {
@@ -713,6 +1316,8 @@ class RustBindings {
       out_header_len,
       out_chapter,
       out_verse,
+      out_verse_end,
+      out_segment,
       out_error,
       out_error_len,
     );
@@ -732,6 +1337,8 @@ class RustBindings {
             ffi.Pointer<ffi.Size>,
             ffi.Pointer<ffi.UnsignedShort>,
             ffi.Pointer<ffi.UnsignedShort>,
+            ffi.Pointer<ffi.UnsignedShort>,
+            ffi.Pointer<ffi.UnsignedInt>,
             ffi.Pointer<ffi.Pointer<ffi.Char>>,
             ffi.Pointer<ffi.Size>,
           )
@@ -750,6 +1357,8 @@ class RustBindings {
           ffi.Pointer<ffi.Size>,
           ffi.Pointer<ffi.UnsignedShort>,
           ffi.Pointer<ffi.UnsignedShort>,
+          ffi.Pointer<ffi.UnsignedShort>,
+          ffi.Pointer<ffi.UnsignedInt>,
           ffi.Pointer<ffi.Pointer<ffi.Char>>,
           ffi.Pointer<ffi.Size>,
         )
@@ -892,6 +1501,24 @@ class RustBindings {
       >();
 }
 
+enum Decoration {
+  DECORATION_NONE(0),
+  DECORATION_UNDERLINE(1),
+  DECORATION_DOTTED(2),
+  DECORATION_STRIKE(3);
+
+  final int value;
+  const Decoration(this.value);
+
+  static Decoration fromValue(int value) => switch (value) {
+    0 => DECORATION_NONE,
+    1 => DECORATION_UNDERLINE,
+    2 => DECORATION_DOTTED,
+    3 => DECORATION_STRIKE,
+    _ => throw ArgumentError("Unknown value for Decoration: $value"),
+  };
+}
+
 final class TextStyle extends ffi.Struct {
   external ffi.Pointer<ffi.Char> font_family;
 
@@ -910,8 +1537,25 @@ final class TextStyle extends ffi.Struct {
   @ffi.Float()
   external double word_spacing;
 
+  @ffi.UnsignedInt()
+  external int decorationAsInt;
+
+  Decoration get decoration => Decoration.fromValue(decorationAsInt);
+
   @ffi.Int32()
-  external int underline;
+  external int font_weight;
+
+  @ffi.Int32()
+  external int italic;
+
+  @ffi.Int32()
+  external int small_caps;
+
+  @ffi.UnsignedInt()
+  external int color;
+
+  @ffi.UnsignedInt()
+  external int background_color;
 }
 
 enum Style {
@@ -922,7 +1566,45 @@ enum Style {
   WORD(4),
   CALLER(9),
   FOOTNOTE(10),
-  CROSSREF(11);
+  CROSSREF(11),
+  MAJOR_SECTION(12),
+  MAJOR_REFERENCE(13),
+  SECTION(14),
+  SUB_SECTION(15),
+  MINOR_SECTION(16),
+  PARALLEL(17),
+  DESCRIPTIVE(18),
+  ACROSTIC_HEADING(19),
+  ADDITION(20),
+  DEITY(21),
+  JESUS(22),
+  BOLD(23),
+  ITALIC(24),
+  BOLD_ITALIC(25),
+  SMALL_CAP(26),
+  SELAH(27),
+  TRANSLITERATED(28),
+  PROPER(29),
+  TITLE(30),
+  SUB_TITLE(31),
+  INTRO_TITLE(32),
+  INTRO_SECTION(33),
+  INTRO(34),
+  INTRO_OUTLINE(35),
+  TABLE_HEADER(36),
+  CHAPTER_LABEL(37),
+  ALT_CHAPTER(38),
+  NOTE_REFERENCE(39),
+  NOTE_QUOTE(40),
+  NOTE_ALT_QUOTE(41),
+  NOTE_KEYWORD(42),
+  NOTE_LABEL(43),
+  NOTE_WITNESS(44),
+  NOTE_TEXT(45),
+  NOTE_DEUTERO(46),
+  NOTE_TARGET(47),
+  FIGURE_CAPTION(48),
+  SIDEBAR(49);
 
   final int value;
   const Style(this.value);
@@ -936,6 +1618,44 @@ enum Style {
     9 => CALLER,
     10 => FOOTNOTE,
     11 => CROSSREF,
+    12 => MAJOR_SECTION,
+    13 => MAJOR_REFERENCE,
+    14 => SECTION,
+    15 => SUB_SECTION,
+    16 => MINOR_SECTION,
+    17 => PARALLEL,
+    18 => DESCRIPTIVE,
+    19 => ACROSTIC_HEADING,
+    20 => ADDITION,
+    21 => DEITY,
+    22 => JESUS,
+    23 => BOLD,
+    24 => ITALIC,
+    25 => BOLD_ITALIC,
+    26 => SMALL_CAP,
+    27 => SELAH,
+    28 => TRANSLITERATED,
+    29 => PROPER,
+    30 => TITLE,
+    31 => SUB_TITLE,
+    32 => INTRO_TITLE,
+    33 => INTRO_SECTION,
+    34 => INTRO,
+    35 => INTRO_OUTLINE,
+    36 => TABLE_HEADER,
+    37 => CHAPTER_LABEL,
+    38 => ALT_CHAPTER,
+    39 => NOTE_REFERENCE,
+    40 => NOTE_QUOTE,
+    41 => NOTE_ALT_QUOTE,
+    42 => NOTE_KEYWORD,
+    43 => NOTE_LABEL,
+    44 => NOTE_WITNESS,
+    45 => NOTE_TEXT,
+    46 => NOTE_DEUTERO,
+    47 => NOTE_TARGET,
+    48 => FIGURE_CAPTION,
+    49 => SIDEBAR,
     _ => throw ArgumentError("Unknown value for Style: $value"),
   };
 }
@@ -963,6 +1683,58 @@ final class Text extends ffi.Struct {
   external Rectangle rect;
 
   external TextStyle style;
+
+  @ffi.Int32()
+  external int rtl;
+}
+
+final class Attribute extends ffi.Struct {
+  external ffi.Pointer<ffi.Char> key;
+
+  @ffi.Size()
+  external int key_len;
+
+  external ffi.Pointer<ffi.Char> value;
+
+  @ffi.Size()
+  external int value_len;
+}
+
+final class Image extends ffi.Struct {
+  external ffi.Pointer<ffi.Char> source;
+
+  @ffi.Size()
+  external int source_len;
+
+  external Rectangle rect;
+}
+
+final class BoxFrame extends ffi.Struct {
+  external Rectangle rect;
+
+  @ffi.Int32()
+  external int continued;
+
+  @ffi.Int32()
+  external int continues;
+}
+
+enum BookName {
+  BOOK_NAME_HEADER(0),
+  BOOK_NAME_LONG(1),
+  BOOK_NAME_SHORT(2),
+  BOOK_NAME_ABBREVIATION(3);
+
+  final int value;
+  const BookName(this.value);
+
+  static BookName fromValue(int value) => switch (value) {
+    0 => BOOK_NAME_HEADER,
+    1 => BOOK_NAME_LONG,
+    2 => BOOK_NAME_SHORT,
+    3 => BOOK_NAME_ABBREVIATION,
+    _ => throw ArgumentError("Unknown value for BookName: $value"),
+  };
 }
 
 final class Dimensions extends ffi.Struct {
@@ -978,3 +1750,143 @@ final class Dimensions extends ffi.Struct {
   @ffi.Float()
   external double drop_cap_padding;
 }
+
+enum CallerSequence {
+  CALLER_LETTERS(0),
+  CALLER_NUMERALS(1),
+  CALLER_SYMBOLS(2);
+
+  final int value;
+  const CallerSequence(this.value);
+
+  static CallerSequence fromValue(int value) => switch (value) {
+    0 => CALLER_LETTERS,
+    1 => CALLER_NUMERALS,
+    2 => CALLER_SYMBOLS,
+    _ => throw ArgumentError("Unknown value for CallerSequence: $value"),
+  };
+}
+
+enum CallerReset {
+  CALLER_RESET_PAGE(0),
+  CALLER_RESET_CHAPTER(1),
+  CALLER_RESET_BOOK(2);
+
+  final int value;
+  const CallerReset(this.value);
+
+  static CallerReset fromValue(int value) => switch (value) {
+    0 => CALLER_RESET_PAGE,
+    1 => CALLER_RESET_CHAPTER,
+    2 => CALLER_RESET_BOOK,
+    _ => throw ArgumentError("Unknown value for CallerReset: $value"),
+  };
+}
+
+enum NotePlacement {
+  NOTES_PAGE(0),
+  NOTES_CHAPTER(1),
+  NOTES_BOOK(2);
+
+  final int value;
+  const NotePlacement(this.value);
+
+  static NotePlacement fromValue(int value) => switch (value) {
+    0 => NOTES_PAGE,
+    1 => NOTES_CHAPTER,
+    2 => NOTES_BOOK,
+    _ => throw ArgumentError("Unknown value for NotePlacement: $value"),
+  };
+}
+
+enum TextDirection {
+  TEXT_DIRECTION_LTR(0),
+  TEXT_DIRECTION_RTL(1);
+
+  final int value;
+  const TextDirection(this.value);
+
+  static TextDirection fromValue(int value) => switch (value) {
+    0 => TEXT_DIRECTION_LTR,
+    1 => TEXT_DIRECTION_RTL,
+    _ => throw ArgumentError("Unknown value for TextDirection: $value"),
+  };
+}
+
+enum LineBreaking {
+  LINE_BREAKING_GREEDY(0),
+  LINE_BREAKING_OPTIMAL(1);
+
+  final int value;
+  const LineBreaking(this.value);
+
+  static LineBreaking fromValue(int value) => switch (value) {
+    0 => LINE_BREAKING_GREEDY,
+    1 => LINE_BREAKING_OPTIMAL,
+    _ => throw ArgumentError("Unknown value for LineBreaking: $value"),
+  };
+}
+
+final class LayoutOptions extends ffi.Struct {
+  @ffi.Int32()
+  external int include_introductions;
+
+  @ffi.Int32()
+  external int chapter_label_headings;
+
+  @ffi.UnsignedInt()
+  external int caller_sequenceAsInt;
+
+  CallerSequence get caller_sequence =>
+      CallerSequence.fromValue(caller_sequenceAsInt);
+
+  @ffi.UnsignedInt()
+  external int caller_resetAsInt;
+
+  CallerReset get caller_reset => CallerReset.fromValue(caller_resetAsInt);
+
+  @ffi.UnsignedInt()
+  external int note_placementAsInt;
+
+  NotePlacement get note_placement =>
+      NotePlacement.fromValue(note_placementAsInt);
+
+  external ffi.Pointer<ffi.Char> notes_title;
+
+  @ffi.Size()
+  external int notes_title_len;
+
+  @ffi.Int32()
+  external int show_verse_numbers;
+
+  @ffi.Int32()
+  external int show_footnotes;
+
+  @ffi.Int32()
+  external int show_cross_references;
+
+  @ffi.Int32()
+  external int show_headings;
+
+  @ffi.UnsignedInt()
+  external int line_breakingAsInt;
+
+  LineBreaking get line_breaking => LineBreaking.fromValue(line_breakingAsInt);
+
+  @ffi.UnsignedInt()
+  external int text_directionAsInt;
+
+  TextDirection get text_direction =>
+      TextDirection.fromValue(text_directionAsInt);
+
+  external ffi.Pointer<ffi.Char> language;
+
+  @ffi.Size()
+  external int language_len;
+
+  @ffi.Int32()
+  external int orphan_lines;
+
+  @ffi.Int32()
+  external int widow_lines;
+}
@@ -14,12 +14,36 @@ pub enum SolaError {
     #[error("Missing book identifier")]
     MissingIdentifier,
 
+    #[error("Missing book name")]
+    MissingBookName,
+
+    #[error("Invalid book name {0}")]
+    InvalidBookName(u32),
+
     #[error("Model loading failed: {0}")]
     ModelLoad(String),
 
     #[error("Search failed: {0}")]
     Search(String),
 
+    #[error("Invalid layout option {0}")]
+    InvalidOption(String),
+
     #[error("Missing index")]
     MissingIndex,
+
+    #[error("Unsupported index version {0}")]
+    IndexVersion(u32),
+
+    #[error("Missing fragment {1} on page {0}")]
+    MissingFragment(usize, usize),
+
+    #[error("Missing page {0}")]
+    MissingPage(usize),
+
+    #[error("No glossary entry for {0}")]
+    MissingGlossaryEntry(String),
+
+    #[error("{0} is not UTF-8")]
+    InvalidUtf8(&'static str),
 }
@@ -70,10 +70,43 @@ pub unsafe fn read_ref<'a, T>(ptr: *const std::ffi::c_void) -> &'a T {
     unsafe { &*(ptr as *const T) }
 }
 
+/// Hand a vector over as a pointer and length, for [`free_vec`] to take
+/// back once the caller has read it.
 pub unsafe fn write_vec<T>(vec: Vec<T>, out: *mut *const T, out_len: *mut usize) {
+    let slice: &mut [T] = Box::leak(vec.into_boxed_slice());
     unsafe {
-        *out = vec.as_ptr();
-        *out_len = vec.len();
+        *out = slice.as_ptr();
+        *out_len = slice.len();
+    }
+}
+
+/// Free a vector handed over by [`write_vec`]. A null pointer, as left
+/// when the call failed, is ignored.
+pub unsafe fn free_vec<T>(ptr: *const T, len: usize) {
+    if ptr.is_null() {
+        return;
+    }
+    unsafe {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+            ptr as *mut T,
+            len,
+        )));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hands_vectors_over_and_back() {
+        let mut vec = Vec::with_capacity(8);
+        vec.extend(["one".to_string(), "two".to_string()]);
+        let (mut ptr, mut len) = (std::ptr::null(), 0);
+        unsafe { write_vec(vec, &mut ptr, &mut len) };
+        assert_eq!(len, 2);
+        assert_eq!(unsafe { &*ptr }, "one");
+        unsafe { free_vec(ptr, len) };
+        unsafe { free_vec(std::ptr::null::<String>(), 0) };
     }
-    std::mem::forget(vec);
 }
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use usfm::{
+    ArchivedBook, ArchivedCharacterContents, ArchivedCharacterType, ArchivedParagraphContents,
+};
+
+/// The entries of a glossary (`GLO`) book, keyed by their `\k` keywords.
+#[derive(Debug, Default)]
+pub struct Glossary {
+    entries: HashMap<String, String>,
+}
+
+fn normalize(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase()
+}
+
+fn push_text(contents: &[ArchivedCharacterContents], text: &mut String) {
+    for content in contents {
+        match content {
+            ArchivedCharacterContents::Line(line) => text.push_str(line),
+            ArchivedCharacterContents::Character(character) => push_text(&character.contents, text),
+        }
+    }
+}
+
+impl Glossary {
+    /// Collect the entries of `book`. An entry starts at its `\k` keyword
+    /// and runs, across paragraphs, up to the next keyword.
+    pub fn new(book: &ArchivedBook) -> Self {
+        use usfm::ArchivedBookContents as Content;
+        let mut entries: Vec<(String, String)> = Vec::new();
+        for content in book.contents.iter() {
+            let contents = match content {
+                Content::Paragraph(paragraph) => &paragraph.contents,
+                Content::Poetry(poetry) => &poetry.contents,
+                _ => continue,
+            };
+            if let Some((_, text)) = entries.last_mut()
+                && !text.is_empty()
+            {
+                text.push('\n');
+            }
+            for content in contents.iter() {
+                match content {
+                    ArchivedParagraphContents::Character(character)
+                        if matches!(character.ty, ArchivedCharacterType::Keyword) =>
+                    {
+                        let mut keyword = String::new();
+                        push_text(&character.contents, &mut keyword);
+                        entries.push((normalize(&keyword), keyword));
+                    }
+                    ArchivedParagraphContents::Character(character) => {
+                        if let Some((_, text)) = entries.last_mut() {
+                            push_text(&character.contents, text);
+                        }
+                    }
+                    ArchivedParagraphContents::Line(line) => {
+                        if let Some((_, text)) = entries.last_mut() {
+                            text.push_str(line);
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        let mut glossary = Self::default();
+        for (keyword, text) in entries {
+            glossary
+                .entries
+                .entry(keyword)
+                .or_insert_with(|| text.trim().to_string());
+        }
+        glossary
+    }
+
+    /// The entry for a `\w` word, by its `lemma` attribute if it has one,
+    /// otherwise by the word itself.
+    pub fn lookup(&self, word: &str, lemma: Option<&str>) -> Option<&str> {
+        lemma
+            .and_then(|lemma| self.entries.get(&normalize(lemma)))
+            .or_else(|| self.entries.get(&normalize(word)))
+            .map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rkyv::rancor::Error;
+    use usfm::parse;
+
+    use super::*;
+
+    fn glossary(usfm: &str) -> Glossary {
+        let bytes = rkyv::to_bytes::<Error>(&parse(usfm)).unwrap();
+        Glossary::new(rkyv::access::<ArchivedBook, Error>(&bytes).unwrap())
+    }
+
+    const GLO: &str = "\\id GLO\n\
+        \\p \\k Grace\\k* Favour that is not earned.\n\
+        \\p It is given freely.\n\
+        \\p \\k Sabbath\\k* The seventh day.\n\
+        \\p \\k grace\\k* A second entry.\n";
+
+    #[test]
+    fn collects_entries_across_paragraphs() {
+        let glossary = glossary(GLO);
+        assert_eq!(
+            glossary.lookup("grace", None),
+            Some("Grace Favour that is not earned.\nIt is given freely.")
+        );
+        assert_eq!(
+            glossary.lookup("Sabbath", None),
+            Some("Sabbath The seventh day.")
+        );
+    }
+
+    #[test]
+    fn looks_up_by_lemma_then_word() {
+        let glossary = glossary(GLO);
+        let cases = [
+            ("gracious", Some("grace"), true),
+            ("Sabbath,", None, true),
+            ("“GRACE”", None, true),
+            ("sabbaths", Some("rest"), false),
+            ("mercy", None, false),
+        ];
+        for (word, lemma, found) in cases {
+            assert_eq!(
+                glossary.lookup(word, lemma).is_some(),
+                found,
+                "{word:?} {lemma:?}"
+            );
+        }
+    }
+}
@@ -1,13 +1,19 @@
 mod error;
 mod ffi;
+mod glossary;
+mod normalize;
 mod painter;
 mod search;
 
 use error::SolaError;
-use ffi::{read_bytes, read_ref, read_str, run_ffi};
+use ffi::{free_vec, read_bytes, read_ref, read_str, run_ffi, write_vec};
+use glossary::Glossary;
+use normalize::normalize;
+use painter::reference::BookNames;
 use painter::{
-    ArchivedIndex, ArchivedIndices, ArchivedPages, Dimensions, Index, Indices, Paint, Painter,
-    Renderer, Style, Text, TextStyle,
+    ArchivedCrossRefs, ArchivedIndex, ArchivedIndices, ArchivedPages, Attribute, BoxFrame,
+    CrossRefs, Dimensions, INDEX_VERSION, Image, Index, Indices, LayoutOptions, Paint, Painter,
+    Renderer, Style, Text, TextStyle, Verse, VerseIndex, verse_page,
 };
 use rkyv::deserialize;
 use rkyv::rancor::Error as RkyvError;
@@ -15,45 +21,57 @@ use skia_safe::FontMgr;
 use std::ffi::{c_char, c_void};
 use std::mem;
 use std::num::TryFromIntError;
+use std::str::from_utf8;
 use usfm::{ArchivedBook, parse};
 
 use crate::painter::layout::Page;
+use crate::painter::layout::state::WORD_TEXT;
 
 /// Holds the result of layout() for FFI access.
 struct LayoutResult {
     pages: Vec<Page>,
     indices: Indices,
     verses: Vec<Index>,
+    cross_refs: CrossRefs,
 }
 
 impl LayoutResult {
     fn compute_verse_ranges(&self) -> Vec<u8> {
         let num_pages = self.pages.len();
-        let mut page_verses: Vec<Vec<(u16, u16)>> = vec![Vec::new(); num_pages];
+        let mut page_verses: Vec<Vec<(u16, Verse)>> = vec![Vec::new(); num_pages];
         for (index, &page) in &self.indices {
-            if let (Some(chapter), Some(verse)) = (index.chapter, index.verse) {
-                if page < num_pages {
-                    page_verses[page].push((chapter, verse));
-                }
+            if let (Some(chapter), Some(verse)) = (index.chapter, index.verse)
+                && page < num_pages
+            {
+                page_verses[page].push((chapter, verse));
             }
         }
 
         let mut parts: Vec<String> = Vec::with_capacity(num_pages);
         for verses in &page_verses {
-            if verses.is_empty() {
+            // A bridge counts from its first verse to its last
+            let first = verses.iter().map(|(c, v)| (*c, v.start, v.segment)).min();
+            let last = verses.iter().map(|(c, v)| (*c, v.end, v.segment)).max();
+            let (Some((fc, fv, fs)), Some((lc, lv, ls))) = (first, last) else {
                 parts.push(String::new());
                 continue;
-            }
-            let mut sorted = verses.clone();
-            sorted.sort();
-            let (fc, fv) = sorted.first().unwrap();
-            let (lc, lv) = sorted.last().unwrap();
-            parts.push(format!("{}:{}\t{}:{}", fc, fv, lc, lv));
+            };
+            parts.push(format!(
+                "{}:{}\t{}:{}",
+                fc,
+                Verse::new(fv, fv, fs),
+                lc,
+                Verse::new(lv, lv, ls)
+            ));
         }
         parts.join("\n").into_bytes()
     }
 }
 
+// Pointers passed to the functions below must be valid for the lengths given
+// with them, and the `out` pointers valid for writes. Each function's
+// `# Safety` section says where its handles must come from.
+
 // ---------------------------------------------------------------------------
 // Renderer setup (infallible)
 // ---------------------------------------------------------------------------
@@ -63,8 +81,11 @@ pub extern "C" fn renderer() -> *mut c_void {
     Box::into_raw(Box::new(Renderer::new())) as *mut c_void
 }
 
+/// # Safety
+///
+/// `renderer` must come from [`renderer`], and `family` must be UTF-8.
 #[unsafe(no_mangle)]
-pub extern "C" fn register_font_family(
+pub unsafe extern "C" fn register_font_family(
     renderer: *mut c_void,
     family: *const c_char,
     family_len: usize,
@@ -92,28 +113,69 @@ pub extern "C" fn register_font_family(
     };
 }
 
+/// # Safety
+///
+/// `renderer` must come from [`renderer`] and `text_style` point to a [`TextStyle`].
 #[unsafe(no_mangle)]
-pub extern "C" fn register_style(renderer: *mut c_void, style: Style, text_style: *mut TextStyle) {
+pub unsafe extern "C" fn register_style(
+    renderer: *mut c_void,
+    style: Style,
+    text_style: *mut TextStyle,
+) {
     let renderer = unsafe { &mut *(renderer as *mut Renderer) };
     let text_style = unsafe { &*text_style };
-    renderer.insert_style(style, text_style.clone());
-    match style {
-        Style::Normal => {
-            let mut chapter_style = text_style.clone();
-            chapter_style.font_size *= 2.0 * chapter_style.height;
-            chapter_style.height = 1.0;
-            renderer.insert_style(Style::Chapter, chapter_style);
-        }
-        _ => (),
+    renderer.insert_style(style, *text_style);
+    if style == Style::Normal {
+        let mut chapter_style = *text_style;
+        chapter_style.font_size *= 2.0 * chapter_style.height;
+        chapter_style.height = 1.0;
+        renderer.insert_style(Style::Chapter, chapter_style);
     }
 }
 
+/// Register Liang hyphenation patterns (TeX format) for a language tag,
+/// used when `LayoutOptions.language` names it. Patterns that are not UTF-8,
+/// as may be read from a file, are reported rather than registered.
+///
+/// # Safety
+///
+/// `renderer` must come from [`renderer`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn register_hyphenation(
+    renderer: *mut c_void,
+    language: *const c_char,
+    language_len: usize,
+    patterns: *const c_char,
+    patterns_len: usize,
+    out_error: *mut *mut c_char,
+    out_error_len: *mut usize,
+) {
+    let Some(()) = run_ffi(
+        || {
+            let renderer = unsafe { &mut *(renderer as *mut Renderer) };
+            let language = from_utf8(unsafe { read_bytes(language as *const u8, language_len) })
+                .map_err(|_| SolaError::InvalidUtf8("language"))?;
+            let patterns = from_utf8(unsafe { read_bytes(patterns as *const u8, patterns_len) })
+                .map_err(|_| SolaError::InvalidUtf8("patterns"))?;
+            renderer.register_hyphenation(language, patterns);
+            Ok(())
+        },
+        out_error,
+        out_error_len,
+    ) else {
+        return;
+    };
+}
+
 // ---------------------------------------------------------------------------
 // USFM serialization
 // ---------------------------------------------------------------------------
 
+/// # Safety
+///
+/// `usfm` must be UTF-8.
 #[unsafe(no_mangle)]
-pub extern "C" fn serialize_usfm(
+pub unsafe extern "C" fn serialize_usfm(
     usfm: *const u8,
     usfm_len: usize,
     out: *mut *const u8,
@@ -125,7 +187,7 @@ pub extern "C" fn serialize_usfm(
     let Some(bytes) = run_ffi(
         || {
             let usfm = unsafe { read_str(usfm, usfm_len) };
-            let book = parse(&usfm);
+            let book = parse(&normalize(usfm));
             rkyv::to_bytes::<RkyvError>(&book).map_err(|e| SolaError::Serialization(e.to_string()))
         },
         out_error,
@@ -141,8 +203,11 @@ pub extern "C" fn serialize_usfm(
     mem::forget(bytes);
 }
 
+/// # Safety
+///
+/// `book` must hold the bytes [`serialize_usfm`] wrote.
 #[unsafe(no_mangle)]
-pub extern "C" fn archived_book(
+pub unsafe extern "C" fn archived_book(
     book: *const u8,
     book_len: usize,
     out_error: *mut *mut c_char,
@@ -161,8 +226,11 @@ pub extern "C" fn archived_book(
     .unwrap_or(std::ptr::null())
 }
 
+/// # Safety
+///
+/// `book` must come from [`archived_book`].
 #[unsafe(no_mangle)]
-pub extern "C" fn book_identifier(
+pub unsafe extern "C" fn book_identifier(
     book: *const c_void,
     out: *mut *const u8,
     out_len: *mut usize,
@@ -195,15 +263,217 @@ pub extern "C" fn book_identifier(
     }
 }
 
+/// Which of a book's names to read: `\h` or `\toc1`..`\toc3`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub enum BookName {
+    Header = 0,
+    Long = 1,
+    Short = 2,
+    Abbreviation = 3,
+}
+
+impl TryFrom<u32> for BookName {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, u32> {
+        match value {
+            0 => Ok(Self::Header),
+            1 => Ok(Self::Long),
+            2 => Ok(Self::Short),
+            3 => Ok(Self::Abbreviation),
+            _ => Err(value),
+        }
+    }
+}
+
+/// `name` is a [`BookName`], taken as a plain integer as any integer can
+/// arrive here over the FFI.
+///
+/// # Safety
+///
+/// `book` must come from [`archived_book`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn book_name(
+    book: *const c_void,
+    name: u32,
+    out: *mut *const u8,
+    out_len: *mut usize,
+    out_error: *mut *mut c_char,
+    out_error_len: *mut usize,
+) {
+    let Some((ptr, len)) = run_ffi(
+        || {
+            use usfm::ArchivedBookContents as Content;
+            use usfm::ArchivedElementContents;
+            use usfm::ArchivedElementType as Type;
+            let name = BookName::try_from(name).map_err(SolaError::InvalidBookName)?;
+            let book = unsafe { read_ref::<ArchivedBook>(book) };
+            book.contents
+                .iter()
+                .filter_map(|content| match content {
+                    Content::Element(element) => Some(element),
+                    _ => None,
+                })
+                .filter(|element| {
+                    matches!(
+                        (name, &element.ty),
+                        (BookName::Header, Type::Header)
+                            | (BookName::Long, Type::Contents(1))
+                            | (BookName::Short, Type::Contents(2))
+                            | (BookName::Abbreviation, Type::Contents(3))
+                    )
+                })
+                .flat_map(|element| element.contents.iter())
+                .find_map(|content| match content {
+                    ArchivedElementContents::Line(text) if !text.trim().is_empty() => {
+                        let text = text.trim();
+                        Some((text.as_ptr(), text.len()))
+                    }
+                    _ => None,
+                })
+                .ok_or(SolaError::MissingBookName)
+        },
+        out_error,
+        out_error_len,
+    ) else {
+        return;
+    };
+    unsafe {
+        *out = ptr;
+        *out_len = len;
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Book names
+// ---------------------------------------------------------------------------
+
+#[unsafe(no_mangle)]
+pub extern "C" fn book_names() -> *mut c_void {
+    Box::into_raw(Box::new(BookNames::new())) as *mut c_void
+}
+
+/// Add a book's `\h` and `\toc` names, so cross-references can name it.
+///
+/// # Safety
+///
+/// `book_names` must come from [`book_names`] and `book` from [`archived_book`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn add_book_names(book_names: *mut c_void, book: *const c_void) {
+    let book_names = unsafe { &mut *(book_names as *mut BookNames) };
+    let book = unsafe { read_ref::<ArchivedBook>(book) };
+    book_names.add(book);
+}
+
+/// Free the names [`book_names`] collected, once no layout needs them.
+///
+/// # Safety
+///
+/// `book_names` must come from [`book_names`] and is not to be used again.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn free_book_names(book_names: *mut c_void) {
+    if !book_names.is_null() {
+        drop(unsafe { Box::from_raw(book_names as *mut BookNames) });
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Glossary
+// ---------------------------------------------------------------------------
+
+/// Load the `\k` entries of a glossary (`GLO`) book.
+///
+/// # Safety
+///
+/// `book` must come from [`archived_book`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn glossary(book: *const c_void) -> *mut c_void {
+    let book = unsafe { read_ref::<ArchivedBook>(book) };
+    Box::into_raw(Box::new(Glossary::new(book))) as *mut c_void
+}
+
+/// Free a glossary [`glossary`] loaded.
+///
+/// # Safety
+///
+/// `glossary` must come from [`glossary`] and is not to be used again.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn free_glossary(glossary: *mut c_void) {
+    if !glossary.is_null() {
+        drop(unsafe { Box::from_raw(glossary as *mut Glossary) });
+    }
+}
+
+/// The glossary entry for a `\w` fragment on a page, found by its `lemma`
+/// attribute or else by the text of the whole word.
+///
+/// # Safety
+///
+/// `glossary` must come from [`glossary`] and `archived_pages` from
+/// [`archived_pages`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn glossary_entry(
+    glossary: *const c_void,
+    archived_pages: *const c_void,
+    page_index: usize,
+    fragment_index: usize,
+    out: *mut *const u8,
+    out_len: *mut usize,
+    out_error: *mut *mut c_char,
+    out_error_len: *mut usize,
+) {
+    let Some((ptr, len)) = run_ffi(
+        || {
+            let glossary = unsafe { read_ref::<Glossary>(glossary) };
+            let archived_pages = unsafe { read_ref::<ArchivedPages>(archived_pages) };
+            let fragment = archived_pages
+                .get(page_index)
+                .and_then(|page| {
+                    page.iter()
+                        .filter(|fragment| fragment.image.is_none())
+                        .nth(fragment_index)
+                })
+                .ok_or(SolaError::MissingFragment(page_index, fragment_index))?;
+            let attribute = |name: &str| {
+                fragment
+                    .attributes
+                    .iter()
+                    .find(|attribute| attribute.0 == name)
+                    .map(|attribute| attribute.1.as_str())
+            };
+            let word = attribute(WORD_TEXT).unwrap_or(&fragment.text);
+            let entry = glossary
+                .lookup(word, attribute("lemma"))
+                .ok_or_else(|| SolaError::MissingGlossaryEntry(word.to_string()))?;
+            Ok((entry.as_ptr(), entry.len()))
+        },
+        out_error,
+        out_error_len,
+    ) else {
+        return;
+    };
+    unsafe {
+        *out = ptr;
+        *out_len = len;
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Layout & pages
 // ---------------------------------------------------------------------------
 
+/// # Safety
+///
+/// `renderer` must come from [`renderer`], `book` from [`archived_book`] and
+/// `book_names`, unless it is null, from [`book_names`]. `dim` is freed here.
 #[unsafe(no_mangle)]
-pub extern "C" fn layout(
+pub unsafe extern "C" fn layout(
     renderer: *const c_void,
     book: *const c_void,
     dim: *mut Dimensions,
+    options: LayoutOptions,
+    book_names: *const c_void,
     out_error: *mut *mut c_char,
     out_error_len: *mut usize,
 ) -> *mut c_void {
@@ -213,11 +483,17 @@ pub extern "C" fn layout(
             let renderer = unsafe { read_ref::<Renderer>(renderer) };
             let book = unsafe { read_ref::<ArchivedBook>(book) };
             let dim = unsafe { Box::from_raw(dim) };
+            options.validate().map_err(SolaError::InvalidOption)?;
 
-            let mut painter = Painter::new(renderer, *dim.clone());
+            let mut painter = Painter::new(renderer, *dim.clone(), options);
+            if !book_names.is_null() {
+                let book_names = unsafe { read_ref::<BookNames>(book_names) };
+                painter.set_book_names(book_names.clone());
+            }
             book.paint(&mut painter);
 
             let (pages, indices) = painter.layout();
+            let cross_refs = painter.take_cross_refs();
 
             // Extract verses from indices (all entries with a verse field)
             let verses: Vec<Index> = indices
@@ -230,6 +506,7 @@ pub extern "C" fn layout(
                 pages,
                 indices,
                 verses,
+                cross_refs,
             };
             log!("[FFI] layout complete");
             Ok(Box::into_raw(Box::new(result)) as *mut c_void)
@@ -240,8 +517,11 @@ pub extern "C" fn layout(
     .unwrap_or(std::ptr::null_mut())
 }
 
+/// # Safety
+///
+/// `layout_result` must come from [`layout`].
 #[unsafe(no_mangle)]
-pub extern "C" fn serialize_pages(
+pub unsafe extern "C" fn serialize_pages(
     layout_result: *const c_void,
     out: *mut *const u8,
     out_len: *mut usize,
@@ -266,8 +546,11 @@ pub extern "C" fn serialize_pages(
     mem::forget(bytes);
 }
 
+/// # Safety
+///
+/// `pages` must hold the bytes [`serialize_pages`] wrote.
 #[unsafe(no_mangle)]
-pub extern "C" fn archived_pages(
+pub unsafe extern "C" fn archived_pages(
     pages: *const u8,
     pages_len: usize,
     out_error: *mut *mut c_char,
@@ -286,14 +569,20 @@ pub extern "C" fn archived_pages(
     .unwrap_or(std::ptr::null())
 }
 
+/// # Safety
+///
+/// `archived_pages` must come from [`archived_pages`].
 #[unsafe(no_mangle)]
-pub extern "C" fn num_pages(archived_pages: *const c_void) -> usize {
+pub unsafe extern "C" fn num_pages(archived_pages: *const c_void) -> usize {
     let archived_pages = unsafe { read_ref::<ArchivedPages>(archived_pages) };
     archived_pages.len()
 }
 
+/// # Safety
+///
+/// `renderer` must come from [`renderer`] and `archived_pages` from [`archived_pages`].
 #[unsafe(no_mangle)]
-pub extern "C" fn page(
+pub unsafe extern "C" fn page(
     renderer: *const c_void,
     archived_pages: *const c_void,
     page_index: usize,
@@ -320,12 +609,169 @@ pub extern "C" fn page(
     };
 }
 
+/// The `\w` attributes of a fragment on a page, in the order `page()` returns them.
+///
+/// # Safety
+///
+/// `archived_pages` must come from [`archived_pages`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fragment_attributes(
+    archived_pages: *const c_void,
+    page_index: usize,
+    fragment_index: usize,
+    out: *mut *const Attribute,
+    out_len: *mut usize,
+    out_error: *mut *mut c_char,
+    out_error_len: *mut usize,
+) {
+    let Some(attributes) = run_ffi(
+        || {
+            let archived_pages = unsafe { read_ref::<ArchivedPages>(archived_pages) };
+            let fragment = archived_pages
+                .get(page_index)
+                .and_then(|page| {
+                    page.iter()
+                        .filter(|fragment| fragment.image.is_none())
+                        .nth(fragment_index)
+                })
+                .ok_or(SolaError::MissingFragment(page_index, fragment_index))?;
+            Ok(fragment
+                .attributes
+                .iter()
+                .map(|attribute| Attribute::new(&attribute.0, &attribute.1))
+                .collect::<Vec<_>>())
+        },
+        out_error,
+        out_error_len,
+    ) else {
+        return;
+    };
+    unsafe { write_vec(attributes, out, out_len) };
+}
+
+/// Free the attributes [`fragment_attributes`] returned.
+///
+/// # Safety
+///
+/// `attributes` and `len` must be as [`fragment_attributes`] wrote them, and
+/// are not to be read again.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn free_attributes(attributes: *const Attribute, len: usize) {
+    unsafe { free_vec(attributes, len) };
+}
+
+/// The figures on a page, which `page()` leaves out.
+///
+/// # Safety
+///
+/// `archived_pages` must come from [`archived_pages`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn page_images(
+    archived_pages: *const c_void,
+    page_index: usize,
+    out: *mut *const Image,
+    out_len: *mut usize,
+    out_error: *mut *mut c_char,
+    out_error_len: *mut usize,
+) {
+    let Some(images) = run_ffi(
+        || {
+            let archived_pages = unsafe { read_ref::<ArchivedPages>(archived_pages) };
+            let page = archived_pages
+                .get(page_index)
+                .ok_or(SolaError::MissingPage(page_index))?;
+            Ok(page
+                .iter()
+                .filter_map(|fragment| {
+                    let source = fragment.image.as_ref()?;
+                    let rect = deserialize::<_, RkyvError>(&fragment.rect).ok()?;
+                    Some(Image::new(source, rect))
+                })
+                .collect::<Vec<_>>())
+        },
+        out_error,
+        out_error_len,
+    ) else {
+        return;
+    };
+    unsafe { write_vec(images, out, out_len) };
+}
+
+/// Free the images [`page_images`] returned.
+///
+/// # Safety
+///
+/// `images` and `len` must be as [`page_images`] wrote them, and are not to
+/// be read again.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn free_images(images: *const Image, len: usize) {
+    unsafe { free_vec(images, len) };
+}
+
+/// The frames of boxed blocks on a page, to draw behind the text. Their
+/// fragments come back from `page()` with no text, in `SIDEBAR` style.
+///
+/// # Safety
+///
+/// `archived_pages` must come from [`archived_pages`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn page_frames(
+    archived_pages: *const c_void,
+    page_index: usize,
+    out: *mut *const BoxFrame,
+    out_len: *mut usize,
+    out_error: *mut *mut c_char,
+    out_error_len: *mut usize,
+) {
+    let Some(frames) = run_ffi(
+        || {
+            let archived_pages = unsafe { read_ref::<ArchivedPages>(archived_pages) };
+            let page = archived_pages
+                .get(page_index)
+                .ok_or(SolaError::MissingPage(page_index))?;
+            Ok(page
+                .iter()
+                .filter_map(|fragment| {
+                    let frame = fragment.frame.as_ref()?;
+                    let rect = deserialize::<_, RkyvError>(&fragment.rect).ok()?;
+                    Some(BoxFrame::new(rect, frame.continued, frame.continues))
+                })
+                .collect::<Vec<_>>())
+        },
+        out_error,
+        out_error_len,
+    ) else {
+        return;
+    };
+    unsafe { write_vec(frames, out, out_len) };
+}
+
+/// Free the frames [`page_frames`] returned.
+///
+/// # Safety
+///
+/// `frames` and `len` must be as [`page_frames`] wrote them, and are not to
+/// be read again.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn free_frames(frames: *const BoxFrame, len: usize) {
+    unsafe { free_vec(frames, len) };
+}
+
 // ---------------------------------------------------------------------------
 // Indices & verses
 // ---------------------------------------------------------------------------
 
+/// Layout of the archived indices and verses, for keying caches of them.
 #[unsafe(no_mangle)]
-pub extern "C" fn serialize_indices(
+pub extern "C" fn index_version() -> u32 {
+    INDEX_VERSION
+}
+
+/// # Safety
+///
+/// `layout_result` must come from [`layout`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn serialize_indices(
     layout_result: *const c_void,
     out: *mut *const u8,
     out_len: *mut usize,
@@ -350,8 +796,11 @@ pub extern "C" fn serialize_indices(
     mem::forget(bytes);
 }
 
+/// # Safety
+///
+/// `indices` must hold the bytes [`serialize_indices`] wrote.
 #[unsafe(no_mangle)]
-pub extern "C" fn archived_indices(
+pub unsafe extern "C" fn archived_indices(
     indices: *const u8,
     indices_len: usize,
     out_error: *mut *mut c_char,
@@ -370,8 +819,12 @@ pub extern "C" fn archived_indices(
     .unwrap_or(std::ptr::null())
 }
 
+/// # Safety
+///
+/// `page_map` must come from [`archived_indices`], and `index` must point to an
+/// archived [`Index`], such as a key of it or a [`cross_ref_targets`] target.
 #[unsafe(no_mangle)]
-pub extern "C" fn get_index(
+pub unsafe extern "C" fn get_index(
     page_map: *const c_void,
     index: *const c_void,
     out_page: *mut usize,
@@ -379,8 +832,12 @@ pub extern "C" fn get_index(
     out_book_len: *mut usize,
     out_header: *mut *const u8,
     out_header_len: *mut usize,
+    out_heading: *mut *const u8,
+    out_heading_len: *mut usize,
     out_chapter: *mut u16,
     out_verse: *mut u16,
+    out_verse_end: *mut u16,
+    out_segment: *mut u32,
     out_error: *mut *mut c_char,
     out_error_len: *mut usize,
 ) {
@@ -417,23 +874,65 @@ pub extern "C" fn get_index(
     ) else {
         return;
     };
+    // Read in place, as the archive outlives the call
+    let index = unsafe { read_ref::<ArchivedIndex>(index) };
+    let heading = index.heading.as_ref().map(|heading| heading.as_str());
     unsafe {
         *out_page = page_val;
         *out_book = book_ptr;
         *out_book_len = book_len;
         *out_header = header_ptr;
         *out_header_len = header_len;
+        *out_heading = heading.map_or(std::ptr::null(), str::as_ptr);
+        *out_heading_len = heading.map_or(0, str::len);
         if let Some(chapter) = chapter {
             *out_chapter = chapter;
         }
         if let Some(verse) = verse {
-            *out_verse = verse;
+            *out_verse = verse.start;
+            *out_verse_end = verse.end;
+            *out_segment = verse.segment.map_or(0, u32::from);
         }
     }
 }
 
+/// The page a verse starts on, including verses inside a bridge such as `3-4`.
+///
+/// # Safety
+///
+/// `page_map` must come from [`archived_indices`], and `book` must be UTF-8.
 #[unsafe(no_mangle)]
-pub extern "C" fn serialize_verses(
+pub unsafe extern "C" fn find_verse(
+    page_map: *const c_void,
+    book: *const u8,
+    book_len: usize,
+    chapter: u16,
+    verse: u16,
+    out_page: *mut usize,
+    out_error: *mut *mut c_char,
+    out_error_len: *mut usize,
+) {
+    let Some(page_val) = run_ffi(
+        || {
+            let page_map = unsafe { read_ref::<ArchivedIndices>(page_map) };
+            let book = unsafe { read_str(book, book_len) };
+            verse_page(page_map, book, chapter, verse).ok_or(SolaError::MissingIndex)
+        },
+        out_error,
+        out_error_len,
+    ) else {
+        return;
+    };
+    unsafe {
+        *out_page = page_val;
+    }
+}
+
+/// # Safety
+///
+/// `layout_result` must come from [`layout`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn serialize_verses(
     layout_result: *const c_void,
     out: *mut *const u8,
     out_len: *mut usize,
@@ -443,7 +942,7 @@ pub extern "C" fn serialize_verses(
     let Some(bytes) = run_ffi(
         || {
             let result = unsafe { read_ref::<LayoutResult>(layout_result) };
-            rkyv::to_bytes::<RkyvError>(&result.verses)
+            rkyv::to_bytes::<RkyvError>(&VerseIndex::new(result.verses.clone()))
                 .map_err(|e| SolaError::Serialization(e.to_string()))
         },
         out_error,
@@ -458,8 +957,11 @@ pub extern "C" fn serialize_verses(
     mem::forget(bytes);
 }
 
+/// # Safety
+///
+/// `layout_result` must come from [`layout`].
 #[unsafe(no_mangle)]
-pub extern "C" fn serialize_verse_ranges(
+pub unsafe extern "C" fn serialize_verse_ranges(
     layout_result: *const c_void,
     out: *mut *const u8,
     out_len: *mut usize,
@@ -473,6 +975,122 @@ pub extern "C" fn serialize_verse_ranges(
     mem::forget(bytes);
 }
 
+// ---------------------------------------------------------------------------
+// Cross-references
+// ---------------------------------------------------------------------------
+
+/// # Safety
+///
+/// `layout_result` must come from [`layout`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn serialize_cross_refs(
+    layout_result: *const c_void,
+    out: *mut *const u8,
+    out_len: *mut usize,
+    out_error: *mut *mut c_char,
+    out_error_len: *mut usize,
+) {
+    let Some(bytes) = run_ffi(
+        || {
+            let result = unsafe { read_ref::<LayoutResult>(layout_result) };
+            rkyv::to_bytes::<RkyvError>(&result.cross_refs)
+                .map_err(|e| SolaError::Serialization(e.to_string()))
+        },
+        out_error,
+        out_error_len,
+    ) else {
+        return;
+    };
+    unsafe {
+        *out = bytes.as_ptr();
+        *out_len = bytes.len();
+    }
+    mem::forget(bytes);
+}
+
+/// # Safety
+///
+/// `cross_refs` must hold the bytes [`serialize_cross_refs`] wrote.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn archived_cross_refs(
+    cross_refs: *const u8,
+    cross_refs_len: usize,
+    out_error: *mut *mut c_char,
+    out_error_len: *mut usize,
+) -> *const c_void {
+    run_ffi(
+        || {
+            let bytes = unsafe { read_bytes(cross_refs, cross_refs_len) };
+            let archived = rkyv::access::<ArchivedCrossRefs, RkyvError>(bytes)
+                .map_err(|e| SolaError::Deserialization(e.to_string()))?;
+            Ok(archived as *const ArchivedCrossRefs as *const c_void)
+        },
+        out_error,
+        out_error_len,
+    )
+    .unwrap_or(std::ptr::null())
+}
+
+/// The first and last index of each target of a linked cross-reference, for
+/// `get_index`. `link` is the fragment's `x-link` attribute.
+///
+/// # Safety
+///
+/// `archived_cross_refs` must come from [`archived_cross_refs`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cross_ref_targets(
+    archived_cross_refs: *const c_void,
+    link: usize,
+    out_starts: *mut *const *const c_void,
+    out_ends: *mut *const *const c_void,
+    out_len: *mut usize,
+    out_error: *mut *mut c_char,
+    out_error_len: *mut usize,
+) {
+    let Some((starts, ends)) = run_ffi(
+        || {
+            let cross_refs = unsafe { read_ref::<ArchivedCrossRefs>(archived_cross_refs) };
+            let targets = cross_refs.get(link).ok_or(SolaError::MissingIndex)?;
+            Ok(targets
+                .iter()
+                .map(|range| {
+                    (
+                        &range.start as *const ArchivedIndex as *const c_void,
+                        &range.end as *const ArchivedIndex as *const c_void,
+                    )
+                })
+                .unzip::<_, _, Vec<_>, Vec<_>>())
+        },
+        out_error,
+        out_error_len,
+    ) else {
+        return;
+    };
+    unsafe {
+        write_vec(starts, out_starts, out_len);
+        write_vec(ends, out_ends, out_len);
+    }
+}
+
+/// Free the targets [`cross_ref_targets`] returned. The indices they point to
+/// stay in the archived cross-references.
+///
+/// # Safety
+///
+/// `starts`, `ends` and `len` must be as [`cross_ref_targets`] wrote them,
+/// and are not to be read again.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn free_cross_ref_targets(
+    starts: *const *const c_void,
+    ends: *const *const c_void,
+    len: usize,
+) {
+    unsafe {
+        free_vec(starts, len);
+        free_vec(ends, len);
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Android logging
 // ---------------------------------------------------------------------------
@@ -509,3 +1127,71 @@ macro_rules! log {
         println!($($arg)*);
     }}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_raw_book_names() {
+        use BookName::*;
+        for name in [Header, Long, Short, Abbreviation] {
+            assert_eq!(BookName::try_from(name as u32), Ok(name));
+        }
+        assert_eq!(BookName::try_from(4), Err(4));
+    }
+
+    #[test]
+    fn rejects_unknown_book_names() {
+        let book = parse("\\id GEN\n\\h Genesis\n\\toc1 The Book of Genesis\n");
+        let bytes = rkyv::to_bytes::<RkyvError>(&book).unwrap();
+        let book = rkyv::access::<ArchivedBook, RkyvError>(&bytes).unwrap();
+        let book = book as *const ArchivedBook as *const c_void;
+        let read = |name: u32| {
+            let (mut text, mut len) = (std::ptr::null(), 0);
+            let (mut error, mut error_len) = (std::ptr::null_mut(), 0);
+            unsafe { book_name(book, name, &mut text, &mut len, &mut error, &mut error_len) };
+            if error_len > 0 {
+                let message = unsafe { read_str(error as *const u8, error_len) }.to_string();
+                ffi::free_error(error, error_len);
+                return Err(message);
+            }
+            Ok(unsafe { read_str(text, len) }.to_string())
+        };
+        let long = BookName::Long as u32;
+        assert_eq!(read(long).as_deref(), Ok("The Book of Genesis"));
+        assert_eq!(read(9), Err("Invalid book name 9".to_string()));
+    }
+
+    #[test]
+    fn rejects_patterns_that_are_not_utf8() {
+        let renderer = renderer();
+        let register = |patterns: &[u8]| {
+            let language = "en";
+            let (mut error, mut error_len) = (std::ptr::null_mut(), 0);
+            unsafe {
+                register_hyphenation(
+                    renderer,
+                    language.as_ptr() as *const c_char,
+                    language.len(),
+                    patterns.as_ptr() as *const c_char,
+                    patterns.len(),
+                    &mut error,
+                    &mut error_len,
+                )
+            };
+            if error_len == 0 {
+                return None;
+            }
+            let message = unsafe { read_str(error as *const u8, error_len) }.to_string();
+            ffi::free_error(error, error_len);
+            Some(message)
+        };
+        assert_eq!(register(b"\\patterns{a1b}"), None);
+        assert_eq!(
+            register(b"\\patterns{a1\xFFb}").as_deref(),
+            Some("patterns is not UTF-8")
+        );
+        drop(unsafe { Box::from_raw(renderer as *mut Renderer) });
+    }
+}
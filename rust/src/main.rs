@@ -1,7 +1,13 @@
+// This preview shares the library's modules; the parts of them only the FFI
+// reaches go unused here.
+#[allow(dead_code)]
 mod error;
+mod normalize;
+#[allow(dead_code, unused_imports)]
 mod painter;
 
 use error::SolaError;
+use normalize::normalize;
 use painter::Style;
 use rkyv::rancor::Error;
 use skia_safe::FontMgr;
@@ -10,7 +16,7 @@ use std::ffi::c_char;
 use std::fs;
 use usfm::{ArchivedBook, parse};
 
-use crate::painter::{Dimensions, Paint, Painter, Renderer, TextStyle};
+use crate::painter::{Decoration, Dimensions, LayoutOptions, Paint, Painter, Renderer, TextStyle};
 
 fn main() {
     // Get the file path from command line arguments
@@ -25,9 +31,9 @@ fn main() {
 
     // Read the file into a string
     let contents = fs::read_to_string(file_path).expect("Failed to read file");
-    let book = parse(&contents);
+    let book = parse(&normalize(&contents));
     let bytes = rkyv::to_bytes::<Error>(&book).unwrap();
-    let archived = rkyv::access::<ArchivedBook, Error>(&*bytes).unwrap();
+    let archived = rkyv::access::<ArchivedBook, Error>(&bytes).unwrap();
 
     let file_path = &args[2];
 
@@ -60,7 +66,12 @@ fn main() {
             height: 1.5,
             letter_spacing: 0.0,
             word_spacing: 0.0,
-            underline: 0,
+            decoration: Decoration::None,
+            font_weight: 0,
+            italic: 0,
+            small_caps: 0,
+            color: 0,
+            background_color: 0,
         },
     );
     renderer.insert_style(
@@ -72,7 +83,12 @@ fn main() {
             height: 1.0,
             letter_spacing: 0.0,
             word_spacing: 0.0,
-            underline: 0,
+            decoration: Decoration::None,
+            font_weight: 0,
+            italic: 0,
+            small_caps: 0,
+            color: 0,
+            background_color: 0,
         },
     );
     renderer.insert_style(
@@ -84,7 +100,12 @@ fn main() {
             height: 1.0,
             letter_spacing: 0.0,
             word_spacing: 0.0,
-            underline: 0,
+            decoration: Decoration::None,
+            font_weight: 0,
+            italic: 0,
+            small_caps: 0,
+            color: 0,
+            background_color: 0,
         },
     );
     renderer.insert_style(
@@ -96,7 +117,12 @@ fn main() {
             height: 1.0,
             letter_spacing: 0.0,
             word_spacing: 0.0,
-            underline: 0,
+            decoration: Decoration::None,
+            font_weight: 0,
+            italic: 0,
+            small_caps: 0,
+            color: 0,
+            background_color: 0,
         },
     );
     renderer.insert_style(
@@ -108,7 +134,12 @@ fn main() {
             height: 1.5,
             letter_spacing: 0.0,
             word_spacing: 0.0,
-            underline: 1,
+            decoration: Decoration::Underline,
+            font_weight: 0,
+            italic: 0,
+            small_caps: 0,
+            color: 0,
+            background_color: 0,
         },
     );
     renderer.insert_style(
@@ -120,7 +151,12 @@ fn main() {
             height: 1.0,
             letter_spacing: 0.0,
             word_spacing: 0.0,
-            underline: 0,
+            decoration: Decoration::None,
+            font_weight: 0,
+            italic: 0,
+            small_caps: 0,
+            color: 0,
+            background_color: 0,
         },
     );
     renderer.insert_style(
@@ -132,7 +168,12 @@ fn main() {
             height: 1.5,
             letter_spacing: 0.0,
             word_spacing: 0.0,
-            underline: 0,
+            decoration: Decoration::None,
+            font_weight: 0,
+            italic: 0,
+            small_caps: 0,
+            color: 0,
+            background_color: 0,
         },
     );
     renderer.insert_style(
@@ -144,7 +185,12 @@ fn main() {
             height: 1.5,
             letter_spacing: 0.0,
             word_spacing: 0.0,
-            underline: 0,
+            decoration: Decoration::None,
+            font_weight: 0,
+            italic: 0,
+            small_caps: 0,
+            color: 0,
+            background_color: 0,
         },
     );
     let dim = Dimensions {
@@ -153,7 +199,7 @@ fn main() {
         header_height: 702.0 / 5.0,
         drop_cap_padding: 20.0,
     };
-    let mut painter = Painter::new(&renderer, dim);
+    let mut painter = Painter::new(&renderer, dim, LayoutOptions::default());
 
     archived.paint(&mut painter);
 
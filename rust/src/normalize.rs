@@ -0,0 +1,553 @@
+use std::borrow::Cow;
+
+use crate::log;
+
+/// Rewrite markers that the usfm parser has no rule for into equivalents it
+/// does parse. Anything rewritten is logged, so a book that reads differently
+/// from its source can be traced back here.
+///
+/// - `\li#` list items become `\ph#`, which has the same hanging indent.
+/// - Verse bridges and segments such as `\v 3-4` or `\v 12a` keep their first
+///   number, and the full text moves into a `\vp` published verse, which the
+///   painter indexes as the range it names.
+/// - `\fig` figures become `\jmp` links carrying [`FIGURE_SOURCE`] and
+///   [`FIGURE_SIZE`] attributes, which the painter places as figures.
+/// - `\tr` table rows become `\m` paragraphs, and each cell marker such as
+///   `\th1` or `\tcr2` becomes an empty `\jmp` link naming it in a
+///   [`TABLE_CELL`] attribute, which the painter lays out as a table.
+/// - `\esb` and `\esbe`, which open and close a sidebar, become `\m`
+///   paragraphs holding only an empty `\jmp` link that names the marker in a
+///   [`SIDEBAR`] attribute, which the painter lays out as a box around the
+///   blocks between them.
+/// - Notes with an explicit caller such as `\f *` take the `?` caller, and
+///   the character moves into their first element as an empty `\+jmp` link
+///   carrying a [`NOTE_CALLER`] attribute, which the painter shows instead.
+pub fn normalize(usfm: &str) -> Cow<'_, str> {
+    let mut text = Cow::Borrowed(usfm);
+    if let Some(rewritten) = figures(&text) {
+        text = Cow::Owned(rewritten);
+    }
+    if let Some(rewritten) = tables(&text) {
+        text = Cow::Owned(rewritten);
+    }
+    if let Some(rewritten) = sidebars(&text) {
+        text = Cow::Owned(rewritten);
+    }
+    if let Some(rewritten) = note_callers(&text) {
+        text = Cow::Owned(rewritten);
+    }
+    if let Some(rewritten) = list_items(&text) {
+        text = Cow::Owned(rewritten);
+    }
+    if let Some(rewritten) = verse_ranges(&text) {
+        text = Cow::Owned(rewritten);
+    }
+    text
+}
+
+fn list_items(usfm: &str) -> Option<String> {
+    if !usfm.contains("\\li") {
+        return None;
+    }
+    let mut out = String::with_capacity(usfm.len());
+    let mut rest = usfm;
+    let mut list_items = 0;
+    while let Some(at) = rest.find("\\li") {
+        out.push_str(&rest[..at]);
+        let after = &rest[at + 3..];
+        let digits = after.len() - after.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let ends = after[digits..]
+            .chars()
+            .next()
+            .is_none_or(|c| c.is_whitespace());
+        if ends {
+            // \li on its own is the first level
+            let level = if digits == 0 { "1" } else { &after[..digits] };
+            out.push_str("\\ph");
+            out.push_str(level);
+            list_items += 1;
+        } else {
+            // \lit and friends are other markers
+            out.push_str(&rest[at..at + 3 + digits]);
+        }
+        rest = &after[digits..];
+    }
+    out.push_str(rest);
+    if list_items == 0 {
+        return None;
+    }
+    log!("[USFM] {} \\li list items laid out as \\ph", list_items);
+    Some(out)
+}
+
+fn verse_ranges(usfm: &str) -> Option<String> {
+    let mut out = String::with_capacity(usfm.len());
+    let mut rest = usfm;
+    let mut ranges = 0;
+    while let Some(at) = rest.find("\\v ") {
+        out.push_str(&rest[..at + 3]);
+        let after = &rest[at + 3..];
+        let digits = after.len() - after.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let suffix = after[digits..]
+            .find(' ')
+            .filter(|&len| {
+                after[digits..digits + len]
+                    .chars()
+                    .all(|c| c == '-' || c.is_ascii_digit() || c.is_alphabetic())
+            })
+            .unwrap_or(0);
+        if digits == 0 || suffix == 0 {
+            rest = after;
+            continue;
+        }
+        let number = &after[..digits + suffix];
+        out.push_str(&after[..digits]);
+        out.push(' ');
+        rest = &after[digits + suffix + 1..];
+        // A published number given in the source wins over the range
+        if !rest.trim_start().starts_with("\\vp ") {
+            out.push_str("\\vp ");
+            out.push_str(number);
+            out.push_str("\\vp* ");
+        }
+        ranges += 1;
+    }
+    if ranges == 0 {
+        return None;
+    }
+    out.push_str(rest);
+    log!("[USFM] {} verse ranges published with \\vp", ranges);
+    Some(out)
+}
+
+/// Attribute holding the image path of a link rewritten from a `\fig`.
+pub const FIGURE_SOURCE: &str = "fig-src";
+/// Attribute holding the `size` of a link rewritten from a `\fig`: `col` or `span`.
+pub const FIGURE_SIZE: &str = "fig-size";
+/// Stands in for a missing caption or a table cell's text, as the parser
+/// needs some text in a link.
+pub const EMPTY_LINK: char = '\u{200B}';
+
+fn figures(usfm: &str) -> Option<String> {
+    if !usfm.contains("\\fig ") {
+        return None;
+    }
+    let mut out = String::with_capacity(usfm.len());
+    let mut rest = usfm;
+    let mut figures = 0;
+    while let Some(at) = rest.find("\\fig ") {
+        out.push_str(&rest[..at]);
+        let after = &rest[at + 5..];
+        let fields = after
+            .find("\\fig*")
+            .and_then(|end| Some((figure_fields(&after[..end])?, end)));
+        let Some(((caption, source, size), end)) = fields else {
+            // Left for the parser to report
+            out.push_str(&rest[at..at + 5]);
+            rest = after;
+            continue;
+        };
+        let caption = caption.trim();
+        out.push_str("\\jmp ");
+        if caption.is_empty() {
+            out.push(EMPTY_LINK);
+        } else {
+            out.push_str(caption);
+        }
+        // The grammar takes attributes back to back
+        out.push_str(&format!(
+            "|{FIGURE_SOURCE}=\"{source}\"{FIGURE_SIZE}=\"{size}\"\\jmp*"
+        ));
+        rest = &after[end + 5..];
+        figures += 1;
+    }
+    out.push_str(rest);
+    if figures == 0 {
+        return None;
+    }
+    log!("[USFM] {} \\fig figures laid out as \\jmp", figures);
+    Some(out)
+}
+
+/// Attribute naming the marker of a link rewritten from a table cell, such
+/// as `th1` or `tcr2`.
+pub const TABLE_CELL: &str = "table-cell";
+
+fn tables(usfm: &str) -> Option<String> {
+    if !usfm.contains("\\tr") {
+        return None;
+    }
+    let mut out = String::with_capacity(usfm.len());
+    let mut rows = 0;
+    for line in usfm.split_inclusive('\n') {
+        let indent = line.len() - line.trim_start().len();
+        let row = line[indent..]
+            .strip_prefix("\\tr")
+            .filter(|row| row.chars().next().is_none_or(char::is_whitespace));
+        let Some(row) = row else {
+            out.push_str(line);
+            continue;
+        };
+        out.push_str(&line[..indent]);
+        out.push_str("\\m");
+        let mut rest = row;
+        while let Some(at) = rest.find('\\') {
+            out.push_str(&rest[..at]);
+            let after = &rest[at + 1..];
+            let end = after
+                .find(|c: char| c.is_whitespace() || c == '\\')
+                .unwrap_or(after.len());
+            let marker = &after[..end];
+            if is_table_cell(marker) {
+                out.push_str(&format!(
+                    "\\jmp {EMPTY_LINK}|{TABLE_CELL}=\"{marker}\"\\jmp*"
+                ));
+            } else {
+                // The parser only takes a verse at the start of a line
+                if marker == "v" {
+                    out.push('\n');
+                }
+                out.push_str(&rest[at..at + 1 + end]);
+            }
+            rest = &after[end..];
+        }
+        out.push_str(rest);
+        rows += 1;
+    }
+    if rows == 0 {
+        return None;
+    }
+    log!("[USFM] {} \\tr table rows laid out as \\m", rows);
+    Some(out)
+}
+
+/// Whether a marker opens a table cell: `th`, `tc`, optionally `r` or `c` for
+/// its alignment, and its column or span of columns, as in `tcr2` or `th1-2`.
+fn is_table_cell(marker: &str) -> bool {
+    let Some(column) = marker
+        .strip_prefix("th")
+        .or_else(|| marker.strip_prefix("tc"))
+    else {
+        return false;
+    };
+    let column = column.strip_prefix(['r', 'c']).unwrap_or(column);
+    let (first, last) = column.split_once('-').unwrap_or((column, "1"));
+    [first, last]
+        .iter()
+        .all(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Attribute naming the `\esb` or `\esbe` marker a paragraph was rewritten from.
+pub const SIDEBAR: &str = "sidebar";
+
+fn sidebars(usfm: &str) -> Option<String> {
+    if !usfm.contains("\\esb") {
+        return None;
+    }
+    let mut out = String::with_capacity(usfm.len());
+    let mut markers = 0;
+    for line in usfm.split_inclusive('\n') {
+        let indent = line.len() - line.trim_start().len();
+        let marker = ["esbe", "esb"].into_iter().find(|marker| {
+            line[indent..]
+                .strip_prefix('\\')
+                .and_then(|after| after.strip_prefix(marker))
+                .is_some_and(|after| after.chars().next().is_none_or(char::is_whitespace))
+        });
+        let Some(marker) = marker else {
+            out.push_str(line);
+            continue;
+        };
+        out.push_str(&line[..indent]);
+        out.push_str(&format!(
+            "\\m \\jmp {EMPTY_LINK}|{SIDEBAR}=\"{marker}\"\\jmp*\n"
+        ));
+        // A \cat category is not shown
+        let mut rest = line[indent + 1 + marker.len()..].trim();
+        if let Some(category) = rest.strip_prefix("\\cat ") {
+            rest = category
+                .split_once("\\cat*")
+                .map_or("", |(_, after)| after.trim());
+        }
+        if !rest.is_empty() {
+            out.push_str(rest);
+            out.push('\n');
+        }
+        markers += 1;
+    }
+    if markers == 0 {
+        return None;
+    }
+    log!("[USFM] {} \\esb sidebar markers laid out as \\m", markers);
+    Some(out)
+}
+
+/// Attribute holding the explicit caller of a note, such as `*`.
+pub const NOTE_CALLER: &str = "note-caller";
+
+fn note_callers(usfm: &str) -> Option<String> {
+    let mut out = String::with_capacity(usfm.len());
+    let mut rest = usfm;
+    let mut callers = 0;
+    while let Some(at) = rest.find('\\') {
+        out.push_str(&rest[..at + 1]);
+        let after = &rest[at + 1..];
+        rest = after;
+        let Some(marker) = ["f ", "fe ", "x "]
+            .into_iter()
+            .find(|m| after.starts_with(m))
+        else {
+            continue;
+        };
+        let note = &after[marker.len()..];
+        let mut chars = note.chars();
+        let caller = match (chars.next(), chars.next()) {
+            (Some(caller), Some(' ')) if !"+-?".contains(caller) => caller,
+            _ => continue,
+        };
+        let note = &note[caller.len_utf8() + 1..];
+        // Left for the parser to report
+        let Some(element) = first_element(note) else {
+            continue;
+        };
+        out.push_str(marker);
+        out.push_str("? ");
+        out.push_str(&note[..element]);
+        out.push_str(&format!(
+            "\\+jmp {EMPTY_LINK}|{NOTE_CALLER}=\"{caller}\"\\+jmp*"
+        ));
+        rest = &note[element..];
+        callers += 1;
+    }
+    if callers == 0 {
+        return None;
+    }
+    out.push_str(rest);
+    log!(
+        "[USFM] {} explicit note callers moved into their notes",
+        callers
+    );
+    Some(out)
+}
+
+/// Where the text of a note's first element starts, past the `\fr` or `\xo`
+/// reference that may come before it.
+fn first_element(note: &str) -> Option<usize> {
+    let mut at = note.find('\\')?;
+    if note[at..].starts_with("\\fr ") || note[at..].starts_with("\\xo ") {
+        at += 1 + note[at + 1..].find('\\')?;
+    }
+    Some(at + note[at..].find(' ')? + 1)
+}
+
+/// The caption, image path and size of a figure, written either as
+/// `caption|src="..." size="..."` or in the USFM 2 form
+/// `desc|file|size|loc|copy|caption|ref`.
+fn figure_fields(figure: &str) -> Option<(&str, &str, &str)> {
+    let (caption, attributes) = figure.split_once('|')?;
+    if !attributes.contains("=\"") {
+        let fields: Vec<&str> = figure.split('|').collect();
+        let source = fields.get(1).map(|source| source.trim())?;
+        let size = fields.get(2).map_or("", |size| size.trim());
+        let caption = fields.get(5).copied().unwrap_or("");
+        return (!source.is_empty()).then_some((caption, source, size));
+    }
+    let (mut source, mut size) = (None, "");
+    let mut rest = attributes;
+    while let Some((name, value)) = rest.split_once("=\"") {
+        let (value, after) = value.split_once('"')?;
+        match name.trim() {
+            "src" => source = Some(value),
+            "size" => size = value,
+            _ => {}
+        }
+        rest = after;
+    }
+    Some((caption, source.filter(|source| !source.is_empty())?, size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves_text_without_list_items() {
+        let usfm = "\\p \\v 1 In the beginning\n";
+        assert!(matches!(normalize(usfm), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn list_items_become_hanging_paragraphs() {
+        let cases = [
+            ("\\li1 one\n", "\\ph1 one\n"),
+            ("\\li2 two\n", "\\ph2 two\n"),
+            ("\\li first\n", "\\ph1 first\n"),
+            ("\\li1\n\\v 3 text\n", "\\ph1\n\\v 3 text\n"),
+            ("\\li4", "\\ph4"),
+        ];
+        for (usfm, expected) in cases {
+            assert_eq!(normalize(usfm), expected, "{usfm:?}");
+        }
+    }
+
+    #[test]
+    fn leaves_other_markers_starting_with_li() {
+        let usfm = "\\lit Glory to the Father\n\\lik key\\lik*\n";
+        assert_eq!(normalize(usfm), usfm);
+    }
+
+    #[test]
+    fn verse_ranges_become_published_verses() {
+        let cases = [
+            ("\\v 3-4 text", "\\v 3 \\vp 3-4\\vp* text"),
+            ("\\v 12a text", "\\v 12 \\vp 12a\\vp* text"),
+            ("\\v 3-4 \\vp 3b\\vp* text", "\\v 3 \\vp 3b\\vp* text"),
+            ("\\v 1 one \\v 2-3 two", "\\v 1 one \\v 2 \\vp 2-3\\vp* two"),
+        ];
+        for (usfm, expected) in cases {
+            assert_eq!(normalize(usfm), expected, "{usfm:?}");
+        }
+    }
+
+    #[test]
+    fn leaves_single_verses() {
+        let usfm = "\\p\n\\v 1 In the beginning \\vp 1\\vp* text\n";
+        assert!(matches!(normalize(usfm), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn figures_become_links() {
+        let cases = [
+            (
+                "\\fig Map of Canaan|src=\"map.jpg\" size=\"span\" ref=\"1.2\"\\fig*",
+                "\\jmp Map of Canaan|fig-src=\"map.jpg\"fig-size=\"span\"\\jmp*",
+            ),
+            (
+                "\\fig |src=\"a.png\"\\fig*",
+                "\\jmp \u{200B}|fig-src=\"a.png\"fig-size=\"\"\\jmp*",
+            ),
+            (
+                "\\fig Canaan|map.jpg|col|||The land|1.2\\fig*",
+                "\\jmp The land|fig-src=\"map.jpg\"fig-size=\"col\"\\jmp*",
+            ),
+        ];
+        for (usfm, expected) in cases {
+            assert_eq!(normalize(usfm), expected, "{usfm:?}");
+        }
+    }
+
+    #[test]
+    fn leaves_figures_without_a_source() {
+        for usfm in ["\\fig caption\\fig*", "\\fig caption|size=\"col\"\\fig*"] {
+            assert!(matches!(normalize(usfm), Cow::Borrowed(_)), "{usfm:?}");
+        }
+    }
+
+    #[test]
+    fn parses_rewritten_figures() {
+        let usfm =
+            "\\id GEN\n\\c 1\n\\p\n\\v 1 text\n\\fig The land|src=\"map.jpg\" size=\"col\"\\fig*\n";
+        let book = usfm::parse(&normalize(usfm));
+        assert!(format!("{book:?}").contains("fig-src"));
+    }
+
+    #[test]
+    fn table_rows_become_paragraphs_of_cells() {
+        let cell = |marker: &str| format!("\\jmp \u{200B}|table-cell=\"{marker}\"\\jmp*");
+        let cases = [
+            (
+                "\\tr \\th1 Tribe \\thr2 Number\n".to_string(),
+                format!("\\m {} Tribe {} Number\n", cell("th1"), cell("thr2")),
+            ),
+            (
+                "\\tr \\tc1 Reuben\\tcr2 46,500\n".to_string(),
+                format!("\\m {} Reuben{} 46,500\n", cell("tc1"), cell("tcr2")),
+            ),
+            (
+                "\\tr \\tc1-2 \\v 3 Total\n".to_string(),
+                format!("\\m {} \n\\v 3 Total\n", cell("tc1-2")),
+            ),
+        ];
+        for (usfm, expected) in cases {
+            assert_eq!(normalize(&usfm), expected, "{usfm:?}");
+        }
+    }
+
+    #[test]
+    fn leaves_other_markers_starting_with_t() {
+        let usfm = "\\toc1 Numbers\n\\tr \\tc1 \\tl shalom\\tl* \\th\n\\trs x\n";
+        assert_eq!(
+            normalize(usfm),
+            "\\toc1 Numbers\n\\m \\jmp \u{200B}|table-cell=\"tc1\"\\jmp* \\tl shalom\\tl* \\th\n\\trs x\n"
+        );
+    }
+
+    #[test]
+    fn parses_rewritten_tables() {
+        let usfm = "\\id NUM\n\\c 1\n\\tr \\th1 Tribe \\thr2 Number\n\\tr \\tc1 \\v 20 Reuben \\tcr2 46,500\n";
+        let book = usfm::parse(&normalize(usfm));
+        let rows = format!("{book:?}");
+        assert_eq!(rows.matches("table-cell").count(), 4, "{rows}");
+        assert!(rows.contains("Verse(20)"), "{rows}");
+    }
+
+    #[test]
+    fn sidebar_markers_become_paragraphs() {
+        let marker = |m: &str| format!("\\m \\jmp \u{200B}|sidebar=\"{m}\"\\jmp*\n");
+        let cases = [
+            (
+                "\\esb\n\\p Text\n\\esbe\n".to_string(),
+                format!("{}\\p Text\n{}", marker("esb"), marker("esbe")),
+            ),
+            (
+                "\\esb \\cat History\\cat*\n\\p Text\n\\esbe".to_string(),
+                format!("{}\\p Text\n{}", marker("esb"), marker("esbe")),
+            ),
+            (
+                "\\esb \\s1 Heading\n\\esbe\n".to_string(),
+                format!("{}\\s1 Heading\n{}", marker("esb"), marker("esbe")),
+            ),
+        ];
+        for (usfm, expected) in cases {
+            assert_eq!(normalize(&usfm), expected, "{usfm:?}");
+        }
+    }
+
+    #[test]
+    fn parses_rewritten_sidebars() {
+        let usfm = "\\id GEN\n\\c 1\n\\p\n\\v 1 Text\n\\esb\n\\s1 Note\n\\p Aside\n\\esbe\n\\p\n\\v 2 More\n";
+        let book = usfm::parse(&normalize(usfm));
+        let contents = format!("{book:?}");
+        assert_eq!(contents.matches("sidebar").count(), 2, "{contents}");
+        assert!(contents.contains("Verse(2)"), "{contents}");
+    }
+
+    #[test]
+    fn explicit_callers_move_into_their_notes() {
+        let caller = |c: &str| format!("\\+jmp \u{200B}|note-caller=\"{c}\"\\+jmp*");
+        let cases = [
+            (
+                "text\\f * \\ft note\\f* more".to_string(),
+                format!("text\\f ? \\ft {}note\\f* more", caller("*")),
+            ),
+            (
+                "\\f † \\fr 1:2 \\fq word \\ft note\\f*".to_string(),
+                format!("\\f ? \\fr 1:2 \\fq {}word \\ft note\\f*", caller("†")),
+            ),
+            (
+                "\\x a \\xo 1:2 \\xt Mk 1:1\\x*".to_string(),
+                format!("\\x ? \\xo 1:2 \\xt {}Mk 1:1\\x*", caller("a")),
+            ),
+        ];
+        for (usfm, expected) in cases {
+            assert_eq!(normalize(&usfm), expected, "{usfm:?}");
+        }
+    }
+
+    #[test]
+    fn leaves_automatic_callers() {
+        for usfm in ["\\f + \\ft a\\f*", "\\f - \\ft b\\f*", "\\fe ? \\ft c\\fe*"] {
+            assert!(matches!(normalize(usfm), Cow::Borrowed(_)), "{usfm:?}");
+        }
+    }
+}
@@ -1,26 +1,47 @@
+mod hyphenation;
 #[allow(dead_code)]
 pub mod layout;
 mod paint;
+pub mod reference;
 mod renderer;
 
-use std::{ffi::c_char, mem};
+use std::{
+    collections::HashMap,
+    ffi::c_char,
+    mem,
+    rc::Rc,
+    slice::from_raw_parts,
+    str::{Utf8Error, from_utf8},
+};
 
-pub use layout::{Alignment, ArchivedIndex, ArchivedIndices, ArchivedPages, Index, Indices};
+pub use layout::{
+    Alignment, ArchivedCrossRefs, ArchivedIndex, ArchivedIndices, ArchivedPages,
+    ArchivedVerseIndex, CrossRefs, INDEX_VERSION, Index, IndexV1, Indices, TextDirection, Verse,
+    VerseIndex, verse_page,
+};
 pub use paint::Paint;
 pub use renderer::{Renderer, TextStyle};
 use rkyv::{Archive, Deserialize, Serialize, rancor::Error};
-use usfm::{ArchivedBookIdentifier, BookIdentifier};
+use usfm::{ArchivedBookIdentifier, BookIdentifier, Caller};
 
+use hyphenation::Hyphenator;
 use layout::{
     Page, Section, TextFragment,
     artefact::{Artefact, ArtefactAnchor, ArtefactPadding},
     container::{BufferEntry, StackDirection},
-    inline::{ItemKind, StreamItem},
+    figure::{self, CAPTION_GAP, FIGURE_GAP, FLOAT_WIDTH, Figure, FigurePlacement},
+    inline::{InlineItem, ItemKind, StreamItem},
+    knuth_plass::{self, BreakItem},
+    line_breaker::LineBreaking,
     scaffold::Scaffold,
-    state::LayoutState,
-    template::{ContainerFill, Template},
+    state::{
+        CallerReset, CallerSequence, Endnote, LayoutState, NOTE_LINK, NotePlacement, NoteSegment,
+    },
+    table::{self, ShapedCell, TableRow},
+    template::{BOX_PADDING, BoxMark, ContainerFill, Template},
 };
-use renderer::shape_segments;
+use reference::{BookNames, BookTarget, parse_references};
+use renderer::{letter_breaks, shape_segments};
 
 // ---------------------------------------------------------------------------
 // Style enum
@@ -38,11 +59,164 @@ pub enum Style {
     Caller = 9,
     Footnote = 10,
     CrossRef = 11,
+
+    MajorSection = 12,
+    MajorReference = 13,
+    Section = 14,
+    SubSection = 15,
+    MinorSection = 16,
+    Parallel = 17,
+    Descriptive = 18,
+    AcrosticHeading = 19,
+
+    Addition = 20,
+    Deity = 21,
+    Jesus = 22,
+    Bold = 23,
+    Italic = 24,
+    BoldItalic = 25,
+    SmallCap = 26,
+    Selah = 27,
+    Transliterated = 28,
+    Proper = 29,
+
+    Title = 30,
+    SubTitle = 31,
+
+    IntroTitle = 32,
+    IntroSection = 33,
+    Intro = 34,
+    IntroOutline = 35,
+
+    TableHeader = 36,
+
+    ChapterLabel = 37,
+    AltChapter = 38,
+
+    NoteReference = 39,
+    NoteQuote = 40,
+    NoteAltQuote = 41,
+    NoteKeyword = 42,
+    NoteLabel = 43,
+    NoteWitness = 44,
+    NoteText = 45,
+    NoteDeutero = 46,
+    NoteTarget = 47,
+
+    FigureCaption = 48,
+    Sidebar = 49,
+}
+
+impl Style {
+    /// The style to fall back on when this one has not been registered,
+    /// so that older apps keep working as new styles are introduced.
+    pub fn fallback(&self) -> Option<Style> {
+        match self {
+            Style::Normal => None,
+            Style::Title | Style::MajorSection | Style::Section => Some(Style::Header),
+            Style::SubSection | Style::IntroSection | Style::ChapterLabel => Some(Style::Section),
+            Style::AltChapter => Some(Style::Verse),
+            Style::IntroTitle => Some(Style::Title),
+            Style::MinorSection => Some(Style::SubSection),
+            Style::Addition
+            | Style::Selah
+            | Style::Transliterated
+            | Style::NoteQuote
+            | Style::NoteAltQuote
+            | Style::NoteWitness
+            | Style::FigureCaption => Some(Style::Italic),
+            Style::NoteKeyword | Style::NoteLabel => Some(Style::Bold),
+            Style::BoldItalic | Style::TableHeader => Some(Style::Bold),
+            Style::Deity => Some(Style::SmallCap),
+            _ => Some(Style::Normal),
+        }
+    }
+
+    /// Character styles compose over the styles they are nested in, rather
+    /// than replacing them.
+    pub fn is_character(&self) -> bool {
+        matches!(
+            self,
+            Style::Word
+                | Style::Addition
+                | Style::Deity
+                | Style::Jesus
+                | Style::Bold
+                | Style::Italic
+                | Style::BoldItalic
+                | Style::SmallCap
+                | Style::Selah
+                | Style::Transliterated
+                | Style::Proper
+                | Style::IntroOutline
+                | Style::NoteReference
+                | Style::NoteQuote
+                | Style::NoteAltQuote
+                | Style::NoteKeyword
+                | Style::NoteLabel
+                | Style::NoteWitness
+                | Style::NoteText
+                | Style::NoteDeutero
+                | Style::NoteTarget
+        )
+    }
+}
+
+/// A fragment of a page as drawn by the app; the last field is non-zero for
+/// right-to-left text.
+#[derive(Debug)]
+#[repr(C)]
+pub struct Text(*const c_char, usize, Rectangle, TextStyle, i32);
+
+/// A boxed block's frame on a page. The two flags say whether the block is
+/// continued from the previous page and whether it continues on the next,
+/// for the app to mark.
+#[derive(Debug)]
+#[repr(C)]
+pub struct BoxFrame(Rectangle, i32, i32);
+
+impl BoxFrame {
+    pub fn new(rect: Rectangle, continued: bool, continues: bool) -> Self {
+        Self(rect, continued as i32, continues as i32)
+    }
+}
+
+/// A figure's image source path and where it goes on the page.
+#[derive(Debug)]
+#[repr(C)]
+pub struct Image(*const c_char, usize, Rectangle);
+
+impl Image {
+    pub fn new(source: &str, rect: Rectangle) -> Self {
+        Self(source.as_ptr() as *const c_char, source.len(), rect)
+    }
+}
+
+/// A line drawn with the text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub enum Decoration {
+    None = 0,
+    Underline = 1,
+    Dotted = 2,
+    Strike = 3,
 }
 
+/// A `\w` attribute as key and value, borrowed from the archived page.
 #[derive(Debug)]
 #[repr(C)]
-pub struct Text(*const c_char, usize, Rectangle, TextStyle);
+pub struct Attribute(*const c_char, usize, *const c_char, usize);
+
+impl Attribute {
+    pub fn new(key: &str, value: &str) -> Self {
+        Self(
+            key.as_ptr() as *const c_char,
+            key.len(),
+            value.as_ptr() as *const c_char,
+            value.len(),
+        )
+    }
+}
 
 #[derive(Archive, Serialize, Deserialize, Debug, Clone, Copy)]
 #[repr(C)]
@@ -62,6 +236,192 @@ pub struct Dimensions {
     pub drop_cap_padding: f32,
 }
 
+/// Reader choices about what goes into the layout.
+#[derive(Debug, Clone)]
+#[repr(C)]
+pub struct LayoutOptions {
+    pub include_introductions: i32,
+    /// Draw a chapter's own `\cl` label as a heading rather than in the drop
+    /// cap. A book-wide `\cl` is always a heading.
+    pub chapter_label_headings: i32,
+    /// A [`CallerSequence`], checked by [`LayoutOptions::validate`] like the
+    /// other enums, as any integer can arrive here over the FFI.
+    pub caller_sequence: u32,
+    /// A [`CallerReset`].
+    pub caller_reset: u32,
+    /// A [`NotePlacement`].
+    pub note_placement: u32,
+    /// Heading of the notes block when notes are collected; empty leaves
+    /// the block without one.
+    pub notes_title: *const c_char,
+    pub notes_title_len: usize,
+    // Reading edition switches: zero leaves the item out. Verses are
+    // still indexed where their numbers would have been.
+    pub show_verse_numbers: i32,
+    pub show_footnotes: i32,
+    pub show_cross_references: i32,
+    pub show_headings: i32,
+    /// A [`LineBreaking`].
+    pub line_breaking: u32,
+    /// A [`TextDirection`], the translation's `text_direction`.
+    pub text_direction: u32,
+    /// BCP 47 tag of the translation's language, choosing the hyphenation
+    /// patterns registered for it. Empty turns hyphenation off.
+    pub language: *const c_char,
+    pub language_len: usize,
+    /// Fewest lines of a paragraph left at the foot of a page (orphans) and
+    /// carried over to the top of the next (widows); 1 or less allows any.
+    pub orphan_lines: i32,
+    pub widow_lines: i32,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        Self {
+            include_introductions: 1,
+            chapter_label_headings: 0,
+            caller_sequence: CallerSequence::Letters as u32,
+            caller_reset: CallerReset::Page as u32,
+            note_placement: NotePlacement::Page as u32,
+            notes_title: std::ptr::null(),
+            notes_title_len: 0,
+            show_verse_numbers: 1,
+            show_footnotes: 1,
+            show_cross_references: 1,
+            show_headings: 1,
+            line_breaking: LineBreaking::Greedy as u32,
+            text_direction: TextDirection::Ltr as u32,
+            language: std::ptr::null(),
+            language_len: 0,
+            orphan_lines: 2,
+            widow_lines: 2,
+        }
+    }
+}
+
+/// A string passed in the options as a pointer and length; null is empty.
+fn raw_str<'a>(ptr: *const c_char, len: usize) -> Result<&'a str, Utf8Error> {
+    if ptr.is_null() {
+        return Ok("");
+    }
+    from_utf8(unsafe { from_raw_parts(ptr as *const u8, len) })
+}
+
+impl LayoutOptions {
+    /// Check the enum fields, which arrive over the FFI as plain integers, and
+    /// the strings.
+    pub fn validate(&self) -> Result<(), String> {
+        CallerSequence::try_from(self.caller_sequence)
+            .map_err(|value| format!("caller_sequence {}", value))?;
+        CallerReset::try_from(self.caller_reset)
+            .map_err(|value| format!("caller_reset {}", value))?;
+        NotePlacement::try_from(self.note_placement)
+            .map_err(|value| format!("note_placement {}", value))?;
+        LineBreaking::try_from(self.line_breaking)
+            .map_err(|value| format!("line_breaking {}", value))?;
+        TextDirection::try_from(self.text_direction)
+            .map_err(|value| format!("text_direction {}", value))?;
+        raw_str(self.notes_title, self.notes_title_len)
+            .map_err(|_| "notes_title is not UTF-8".to_string())?;
+        raw_str(self.language, self.language_len)
+            .map_err(|_| "language is not UTF-8".to_string())?;
+        Ok(())
+    }
+
+    pub fn caller_sequence(&self) -> CallerSequence {
+        CallerSequence::try_from(self.caller_sequence).unwrap_or(CallerSequence::Letters)
+    }
+
+    pub fn caller_reset(&self) -> CallerReset {
+        CallerReset::try_from(self.caller_reset).unwrap_or(CallerReset::Page)
+    }
+
+    pub fn note_placement(&self) -> NotePlacement {
+        NotePlacement::try_from(self.note_placement).unwrap_or(NotePlacement::Page)
+    }
+
+    pub fn line_breaking(&self) -> LineBreaking {
+        LineBreaking::try_from(self.line_breaking).unwrap_or(LineBreaking::Greedy)
+    }
+
+    pub fn text_direction(&self) -> TextDirection {
+        TextDirection::try_from(self.text_direction).unwrap_or(TextDirection::Ltr)
+    }
+
+    pub fn notes_title(&self) -> &str {
+        raw_str(self.notes_title, self.notes_title_len).unwrap_or("")
+    }
+
+    pub fn language(&self) -> &str {
+        raw_str(self.language, self.language_len).unwrap_or("")
+    }
+
+    /// When callers start again. With a notes block they follow the block,
+    /// so that callers in the text and in the notes agree.
+    pub fn effective_caller_reset(&self) -> CallerReset {
+        match self.note_placement() {
+            NotePlacement::Page => self.caller_reset(),
+            NotePlacement::Chapter => CallerReset::Chapter,
+            NotePlacement::Book => CallerReset::Book,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Block format
+// ---------------------------------------------------------------------------
+
+/// How a block of buffered text is laid out in the body container.
+#[derive(Debug, Clone, Copy)]
+pub struct BlockFormat {
+    pub line_style: Style,
+    pub alignment: Alignment,
+    pub indent: (f32, f32),
+    /// Space above and below the block, in body lines.
+    pub spacing: (f32, f32),
+    /// Move the whole block to the next page rather than split it.
+    pub keep_together: bool,
+    /// Move the block to the next page along with the block after it, when
+    /// that one starts the next page.
+    pub keep_with_next: bool,
+    /// Leave floating artefacts, such as a drop cap, for the block below.
+    pub heading: bool,
+}
+
+impl BlockFormat {
+    pub const fn new(line_style: Style, alignment: Alignment, indent: (f32, f32)) -> Self {
+        Self {
+            line_style,
+            alignment,
+            indent,
+            spacing: (0.0, 0.0),
+            keep_together: false,
+            keep_with_next: false,
+            heading: false,
+        }
+    }
+
+    pub const fn with_spacing(mut self, before: f32, after: f32) -> Self {
+        self.spacing = (before, after);
+        self
+    }
+
+    pub const fn kept_together(mut self) -> Self {
+        self.keep_together = true;
+        self
+    }
+
+    pub const fn kept_with_next(mut self) -> Self {
+        self.keep_with_next = true;
+        self
+    }
+
+    pub const fn as_heading(mut self) -> Self {
+        self.heading = true;
+        self
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Location tracking
 // ---------------------------------------------------------------------------
@@ -73,8 +433,22 @@ struct LocationState {
     chapter: Option<u16>,
 }
 
+/// Blocks of a sidebar being painted, each with its artefacts and format.
+/// They are laid out together once the sidebar ends.
+type SidebarBlocks = Vec<(Vec<BufferEntry>, Vec<(Section, Artefact)>, BlockFormat)>;
+
+/// The last block laid out, kept with the next: what it was filled from, the
+/// layout state it began in, and how many templates it placed on the page.
+struct KeptBlock {
+    buffer: Vec<BufferEntry>,
+    artefacts: Vec<(Section, Artefact)>,
+    format: BlockFormat,
+    state: LayoutState,
+    templates: usize,
+}
+
 // ---------------------------------------------------------------------------
-// Container config for paint_paragraph
+// Container config for paint_block
 // ---------------------------------------------------------------------------
 
 struct ContainerConfig {
@@ -93,6 +467,7 @@ struct ContainerConfig {
 pub struct Painter {
     renderer: Renderer,
     dim: Dimensions,
+    options: LayoutOptions,
 
     // Buffer: cross-container flat stream
     buffer: Vec<BufferEntry>,
@@ -100,11 +475,17 @@ pub struct Painter {
 
     // Style stack (per-section would be complex; keep simple: one stack)
     style_stack: Vec<Style>,
+    // Attributes of the \w word being painted, if any
+    word_attributes: Vec<(String, String)>,
 
     // Index registry
     index_registry: Vec<Index>,
     location: LocationState,
 
+    // Cross-reference targets, resolved against the translation's book names
+    book_names: BookNames,
+    cross_refs: CrossRefs,
+
     // Scaffold + pages
     scaffold: Scaffold,
     pages: Vec<Page>,
@@ -113,26 +494,60 @@ pub struct Painter {
     // Pending artefact for next template
     pending_artefacts: Vec<(Section, Artefact)>,
 
+    // Endnote mode: the note being painted and the buffer it interrupted,
+    // and the notes waiting for the next notes block
+    pending_endnote: Option<(Endnote, Vec<BufferEntry>)>,
+    endnotes: Vec<Endnote>,
+    endnote_links: usize,
+
+    // Sidebars: blocks gathered until the sidebar ends, then the box being
+    // laid out and how far its text is inset
+    sidebar: Option<SidebarBlocks>,
+    sidebar_count: usize,
+    boxed: Option<BoxMark>,
+    inset: f32,
+
+    // The block just laid out, if it goes over with the next one
+    kept: Option<KeptBlock>,
+
+    // Patterns for the translation's language, if registered
+    hyphenator: Option<Rc<Hyphenator>>,
+
     // Layout state (resets on page break)
     state: LayoutState,
 }
 
 impl Painter {
-    pub fn new(renderer: &Renderer, dim: Dimensions) -> Self {
-        let scaffold = Scaffold::new(dim.width, dim.height);
+    pub fn new(renderer: &Renderer, dim: Dimensions, options: LayoutOptions) -> Self {
+        let scaffold = Scaffold::new(dim.width, dim.height, options.text_direction());
+        let state = LayoutState::new(options.caller_sequence());
+        let hyphenator = renderer.hyphenator(options.language());
         Self {
             renderer: renderer.clone(),
             dim,
+            options,
             buffer: Vec::new(),
             active_section: Section::Body,
             style_stack: Vec::new(),
+            word_attributes: Vec::new(),
             index_registry: Vec::new(),
             location: LocationState::default(),
+            book_names: BookNames::new(),
+            cross_refs: Vec::new(),
             scaffold,
             pages: Vec::new(),
             indices: layout::Indices::new(),
             pending_artefacts: Vec::new(),
-            state: LayoutState::new(),
+            pending_endnote: None,
+            endnotes: Vec::new(),
+            endnote_links: 0,
+            sidebar: None,
+            sidebar_count: 0,
+            boxed: None,
+            inset: 0.0,
+            kept: None,
+            hyphenator,
+            state,
         }
     }
 
@@ -140,12 +555,38 @@ impl Painter {
         &self.dim
     }
 
+    pub fn get_options(&self) -> &LayoutOptions {
+        &self.options
+    }
+
+    pub fn set_book_names(&mut self, book_names: BookNames) -> &mut Self {
+        self.book_names = book_names;
+        self
+    }
+
     // --- Style management ---
 
     fn current_style(&self) -> Style {
         self.style_stack.last().copied().unwrap_or(Style::Normal)
     }
 
+    /// Styles the current one is nested in, outermost first: every style from
+    /// the enclosing block style up, when the current style is a character style.
+    fn outer_styles(&self) -> Vec<Style> {
+        let Some((current, outer)) = self.style_stack.split_last() else {
+            return Vec::new();
+        };
+        if !current.is_character() {
+            return Vec::new();
+        }
+        let block = outer.iter().rposition(|s| !s.is_character()).unwrap_or(0);
+        outer[block..]
+            .iter()
+            .copied()
+            .filter(|s| *s != Style::Normal)
+            .collect()
+    }
+
     pub fn push_properties(&mut self, style: Style, _section: Section) -> &mut Self {
         self.style_stack.push(style);
         self
@@ -156,6 +597,12 @@ impl Painter {
         self
     }
 
+    /// Attach `\w` attributes to the text added until they are cleared.
+    pub fn set_word_attributes(&mut self, attributes: Vec<(String, String)>) -> &mut Self {
+        self.word_attributes = attributes;
+        self
+    }
+
     // --- Container management ---
 
     pub fn set_container(&mut self, section: Section) -> &mut Self {
@@ -171,21 +618,23 @@ impl Painter {
             return self;
         }
         let style = self.current_style();
+        let outer_styles = self.outer_styles();
         let section = self.active_section;
         self.buffer.push(BufferEntry::Segment {
             text: text.to_string(),
             style,
+            outer_styles,
+            attributes: self.word_attributes.clone(),
             section,
         });
         self
     }
 
-    pub fn add_state_dependent(
-        &mut self,
-        f: Box<dyn Fn(&mut LayoutState) -> (String, Style)>,
-    ) -> &mut Self {
+    /// Push the rest of the current line flush right.
+    pub fn add_fill(&mut self) -> &mut Self {
+        let style = self.current_style();
         let section = self.active_section;
-        self.buffer.push(BufferEntry::StateDep(f, section));
+        self.buffer.push(BufferEntry::Fill { style, section });
         self
     }
 
@@ -222,37 +671,131 @@ impl Painter {
 
     // --- Footnote convenience (group pattern) ---
 
-    pub fn begin_footnote(&mut self) {
+    pub fn begin_footnote(&mut self, caller: &Caller) {
+        if self.options.note_placement() != NotePlacement::Page {
+            self.begin_endnote(caller);
+            return;
+        }
         self.begin_group();
         // Insert caller in body
         self.set_container(Section::Body);
-        let section = self.active_section;
-        self.buffer.push(BufferEntry::StateDep(
-            Box::new(|state: &mut LayoutState| state.get_next_caller()),
-            section,
-        ));
+        self.add_caller(caller, |state| state.get_next_caller());
         // Begin expanded for footer content
         self.begin_expanded();
         self.set_container(Section::Footer);
         // Insert caller in footer
-        let section = self.active_section;
-        self.buffer.push(BufferEntry::StateDep(
-            Box::new(|state: &mut LayoutState| state.get_current_caller()),
-            section,
-        ));
+        self.add_caller(caller, |state| state.get_current_caller());
+    }
+
+    /// `+` takes the next caller in sequence and `-` has none. Any other
+    /// caller, such as the `*` of `\f *`, is shown as written.
+    fn add_caller(&mut self, caller: &Caller, auto: fn(&mut LayoutState) -> (String, Style)) {
+        match caller {
+            Caller::Auto => {
+                let section = self.active_section;
+                self.buffer
+                    .push(BufferEntry::StateDep(Box::new(auto), section));
+            }
+            Caller::None => (),
+            Caller::Some(c) => {
+                let section = self.active_section;
+                self.push_properties(Style::Caller, section)
+                    .add_text(c.to_string())
+                    .pop_properties();
+            }
+        }
     }
 
     pub fn end_footnote(&mut self) {
+        if let Some((mut note, buffer)) = self.pending_endnote.take() {
+            note.segments = mem::replace(&mut self.buffer, buffer)
+                .into_iter()
+                .filter_map(|entry| match entry {
+                    BufferEntry::Segment {
+                        text,
+                        style,
+                        outer_styles,
+                        attributes,
+                        ..
+                    } => Some(NoteSegment {
+                        text,
+                        style,
+                        outer_styles,
+                        attributes,
+                    }),
+                    _ => None,
+                })
+                .collect();
+            self.endnotes.push(note);
+            return;
+        }
         self.end_expanded();
         self.set_container(Section::Body);
         self.end_group();
     }
 
+    // --- Endnotes ---
+
+    /// The caller is settled now and linked to the note; the note's text is
+    /// set aside by `end_footnote` for the notes block.
+    fn begin_endnote(&mut self, caller: &Caller) {
+        let caller = match caller {
+            Caller::Auto => self.state.get_next_caller().0,
+            Caller::None => String::new(),
+            Caller::Some(c) => c.to_string(),
+        };
+        let link = self.endnote_links;
+        self.endnote_links += 1;
+        self.add_note_caller(&caller, link);
+
+        let note = Endnote {
+            caller,
+            link,
+            segments: Vec::new(),
+        };
+        self.pending_endnote = Some((note, mem::take(&mut self.buffer)));
+    }
+
+    fn add_note_caller(&mut self, caller: &str, link: usize) {
+        let attributes = mem::replace(
+            &mut self.word_attributes,
+            vec![(NOTE_LINK.to_string(), link.to_string())],
+        );
+        self.push_properties(Style::Caller, Section::Body)
+            .add_text(caller)
+            .pop_properties();
+        self.word_attributes = attributes;
+    }
+
+    /// Notes gathered since the last notes block.
+    pub fn take_endnotes(&mut self) -> Vec<Endnote> {
+        mem::take(&mut self.endnotes)
+    }
+
+    /// Buffer a note for the notes block, led by its linked caller.
+    pub fn add_endnote(&mut self, note: &Endnote) -> &mut Self {
+        self.set_container(Section::Body);
+        if !note.caller.is_empty() {
+            self.add_note_caller(&note.caller, note.link);
+            self.add_text(" ");
+        }
+        for segment in note.segments.iter() {
+            self.buffer.push(BufferEntry::Segment {
+                text: segment.text.clone(),
+                style: segment.style,
+                outer_styles: segment.outer_styles.clone(),
+                attributes: segment.attributes.clone(),
+                section: Section::Body,
+            });
+        }
+        self
+    }
+
     // --- Shape a single text+style for raw fragment creation ---
 
     pub fn raw(&self, text: &str, style: Style) -> TextFragment {
         let segments = vec![(text.to_string(), style)];
-        let items = shape_segments(&self.renderer, &segments, Section::Body);
+        let items = shape_segments(&self.renderer, &segments, &[], Section::Body, None, &[]);
         let width: f32 = items.iter().map(|i| i.width).sum();
         let line_height = self.renderer.line_height(&style);
         TextFragment::new(
@@ -264,10 +807,19 @@ impl Painter {
                 height: line_height,
             },
             style,
+            Vec::new(),
+            Vec::new(),
             0.0,
         )
     }
 
+    /// The lines of body text an artefact of `height` stands beside.
+    pub fn line_span(&self, height: f32) -> usize {
+        let lines = height / self.renderer.line_height(&Style::Normal);
+        // A height of whole lines may come out a hair over them
+        (lines - 1e-3).ceil().max(0.0) as usize
+    }
+
     // --- Index methods ---
 
     pub fn index_book(&mut self, book: &ArchivedBookIdentifier) -> &mut Self {
@@ -277,7 +829,8 @@ impl Painter {
     }
 
     pub fn index_header(&mut self, header: &rkyv::string::ArchivedString) -> &mut Self {
-        let header: String = rkyv::deserialize::<_, Error>(header).unwrap();
+        // Trimmed, as cross-reference targets name the book by it
+        let header = header.trim().to_string();
         self.location.header = Some(header.clone());
         let index = Index::new(self.location.book.clone().unwrap(), header, None, None);
         let id = self.index_registry.len();
@@ -286,8 +839,28 @@ impl Painter {
         self
     }
 
+    /// Index a section heading under its own text, alongside the book
+    /// header. Unlike `index_header`, this leaves the location alone so verse
+    /// indices keep their keys.
+    pub fn index_heading(&mut self, heading: impl AsRef<str>) -> &mut Self {
+        let index = Index::new(
+            self.location.book.clone().unwrap(),
+            self.location.header.clone().unwrap_or_default(),
+            self.location.chapter,
+            None,
+        )
+        .with_heading(heading.as_ref().to_string());
+        let id = self.index_registry.len();
+        self.index_registry.push(index);
+        self.add_index_marker(id);
+        self
+    }
+
     pub fn index_chapter(&mut self, chapter: u16) -> &mut Self {
         self.location.chapter = Some(chapter);
+        if self.options.effective_caller_reset() == CallerReset::Chapter {
+            self.state.reset();
+        }
         let index = Index::new(
             self.location.book.clone().unwrap(),
             self.location.header.clone().unwrap(),
@@ -300,12 +873,12 @@ impl Painter {
         self
     }
 
-    pub fn index_verse(&mut self, verse: u16) -> &mut Self {
+    pub fn index_verse(&mut self, verse: impl Into<Verse>) -> &mut Self {
         let index = Index::new(
             self.location.book.clone().unwrap(),
             self.location.header.clone().unwrap(),
             self.location.chapter,
-            Some(verse),
+            Some(verse.into()),
         );
         let id = self.index_registry.len();
         self.index_registry.push(index);
@@ -313,14 +886,169 @@ impl Painter {
         self
     }
 
+    // --- Cross-reference links ---
+
+    /// Resolve cross-reference target text and keep the targets. Returns the
+    /// link id to tag the target's text with, or `None` if nothing resolved.
+    pub fn link_references(&mut self, text: &str) -> Option<usize> {
+        let book = self.location.book.clone().map(|book| BookTarget {
+            book,
+            header: self.location.header.clone().unwrap_or_default(),
+        });
+        let targets =
+            parse_references(text, &self.book_names, book.as_ref(), self.location.chapter);
+        if targets.is_empty() {
+            return None;
+        }
+        self.cross_refs.push(targets);
+        Some(self.cross_refs.len() - 1)
+    }
+
+    pub fn take_cross_refs(&mut self) -> CrossRefs {
+        mem::take(&mut self.cross_refs)
+    }
+
     // --- Paint paragraph variants ---
 
-    pub fn paint_paragraph(&mut self) {
-        self.do_paint_paragraph(Alignment::Justified, (20.0, 0.0));
+    pub fn paint_block(&mut self, format: BlockFormat) {
+        if let Some(blocks) = self.sidebar.as_mut() {
+            let buffer = mem::take(&mut self.buffer);
+            let artefacts = mem::take(&mut self.pending_artefacts);
+            blocks.push((buffer, artefacts, format));
+            return;
+        }
+        self.do_paint_paragraph(format);
+    }
+
+    // --- Sidebars ---
+
+    /// Start a boxed block. Blocks painted until `end_sidebar` go inside it;
+    /// sidebars do not nest, so one already open carries on.
+    pub fn begin_sidebar(&mut self) {
+        self.sidebar.get_or_insert_with(Vec::new);
+    }
+
+    /// Lay out the sidebar's blocks inside a frame. A sidebar that does not
+    /// fit on the rest of this page but fits on a page of its own moves to
+    /// the next page whole; a longer one breaks across pages.
+    pub fn end_sidebar(&mut self) {
+        let Some(blocks) = self.sidebar.take() else {
+            return;
+        };
+        let id = self.sidebar_count;
+        self.sidebar_count += 1;
+        self.inset = BOX_PADDING;
+        self.kept = None;
+
+        let height: f32 = blocks
+            .iter()
+            .map(|(buffer, artefacts, format)| self.measure_block(buffer, artefacts, *format))
+            .sum::<f32>()
+            + 2.0 * BOX_PADDING;
+        if height > self.scaffold.remaining()
+            && height <= self.dim.height
+            && !self.scaffold.templates.is_empty()
+        {
+            self.break_page();
+        }
+
+        let last = blocks.len().saturating_sub(1);
+        for (i, (buffer, artefacts, format)) in blocks.iter().enumerate() {
+            self.boxed = Some(BoxMark {
+                id,
+                opens: i == 0,
+                closes: i == last,
+            });
+            self.fill_paragraph(buffer, artefacts, *format, 0, true);
+        }
+        self.boxed = None;
+        self.inset = 0.0;
+    }
+
+    /// Height a block would take in the body, leaving the layout state as it was.
+    fn measure_block(
+        &mut self,
+        buffer: &[BufferEntry],
+        artefacts: &[(Section, Artefact)],
+        format: BlockFormat,
+    ) -> f32 {
+        let state = self.state.clone();
+        let (stream, _, _) = self.resolve_and_shape(buffer);
+        self.state = state;
+
+        let mut fill = ContainerFill::new(
+            usize::MAX,
+            self.dim.width - self.inset,
+            StackDirection::TopDown,
+            self.renderer.line_height(&format.line_style),
+            format.alignment,
+            (format.indent.0 + self.inset, format.indent.1 + self.inset),
+        );
+        let spacing_unit = self.renderer.line_height(&Style::Normal);
+        fill.spacing = (
+            format.spacing.0 * spacing_unit,
+            format.spacing.1 * spacing_unit,
+        );
+        fill.artefacts = artefacts
+            .iter()
+            .filter(|(section, _)| *section == Section::Body)
+            .map(|(_, artefact)| artefact.clone())
+            .collect();
+
+        // Footnote content goes to the footer, not into the box
+        let mut in_expanded = false;
+        let mut items = Vec::new();
+        for item in stream {
+            match item {
+                StreamItem::BeginExpanded => in_expanded = true,
+                StreamItem::EndExpanded => in_expanded = false,
+                StreamItem::Inline(item) if !in_expanded && item.section == Section::Body => {
+                    items.push(item)
+                }
+                _ => (),
+            }
+        }
+        fill.extend(items);
+        fill.total_height()
     }
 
-    pub fn paint_paragraph_with_indent(&mut self, first: f32, cont: f32) {
-        self.do_paint_paragraph(Alignment::Left, (first, cont));
+    /// Tag a body container as part of the box being laid out, padding it
+    /// where the box opens or closes.
+    fn mark_box(&self, fill: &mut ContainerFill, opens: bool, closes: bool) {
+        let Some(mark) = self.boxed else {
+            return;
+        };
+        let mut placed = fill.boxed.unwrap_or(BoxMark {
+            id: mark.id,
+            opens: false,
+            closes: false,
+        });
+        if mark.opens && opens && !placed.opens {
+            placed.opens = true;
+            fill.spacing.0 += BOX_PADDING;
+        }
+        if mark.closes && closes && !placed.closes {
+            placed.closes = true;
+            fill.spacing.1 += BOX_PADDING;
+        }
+        fill.boxed = Some(placed);
+    }
+
+    /// Close the current page and start a new one.
+    fn break_page(&mut self) {
+        self.kept = None;
+        let page =
+            self.scaffold
+                .finalize(&self.index_registry, self.pages.len(), &mut self.indices);
+        self.pages.push(page);
+        self.scaffold = Scaffold::new(
+            self.dim.width,
+            self.dim.height,
+            self.options.text_direction(),
+        );
+        if self.options.effective_caller_reset() == CallerReset::Page {
+            self.state.reset();
+        }
     }
 
     pub fn paint_heading(&mut self, text: impl AsRef<str>) {
@@ -351,63 +1079,284 @@ impl Painter {
         );
         self.pending_artefacts.push((Section::Body, artefact));
 
-        self.do_paint_paragraph(Alignment::Center, (0.0, 0.0));
+        self.do_paint_paragraph(
+            BlockFormat::new(Style::Normal, Alignment::Center, (0.0, 0.0)).as_heading(),
+        );
+    }
+
+    /// Lay out a block of title lines, one per line and centred, as a
+    /// non-wrapping artefact. Any buffered text is discarded.
+    pub fn paint_title(&mut self, lines: &[(String, Style)]) {
+        self.buffer
+            .retain(|e| matches!(e, BufferEntry::IndexMarker(_)));
+
+        let mut top = 0.0;
+        let mut fragments = Vec::with_capacity(lines.len());
+        for (text, style) in lines {
+            let mut fragment = self.raw(text.trim(), *style);
+            fragment.rect.top = top;
+            fragment.rect.left = (self.dim.width - fragment.rect.width) / 2.0;
+            top += fragment.rect.height;
+            fragments.push(fragment);
+        }
+        if fragments.is_empty() {
+            return;
+        }
+
+        let padding = self.dim.header_height / 2.0;
+        let artefact = Artefact::new(
+            ArtefactPadding {
+                top: padding,
+                bottom: padding,
+                left: 0.0,
+                right: 0.0,
+            },
+            self.dim.width,
+            top,
+            ArtefactAnchor::Left,
+            false,
+            0,
+            fragments,
+        );
+        self.pending_artefacts.push((Section::Body, artefact));
+
+        self.do_paint_paragraph(
+            BlockFormat::new(Style::Normal, Alignment::Center, (0.0, 0.0)).as_heading(),
+        );
+    }
+
+    /// Lay out a table, one row per template so that it splits across
+    /// pages at row boundaries. Any buffered text is discarded.
+    pub fn paint_table(&mut self, rows: &[TableRow]) {
+        self.buffer
+            .retain(|e| matches!(e, BufferEntry::IndexMarker(_)));
+
+        let shaped: Vec<Vec<ShapedCell>> = rows
+            .iter()
+            .map(|row| {
+                row.cells
+                    .iter()
+                    .map(|cell| {
+                        let style = if cell.header {
+                            Style::TableHeader
+                        } else {
+                            Style::Normal
+                        };
+                        let text: String = cell.segments.iter().map(|(t, _)| t.as_str()).collect();
+                        ShapedCell {
+                            items: shape_segments(
+                                &self.renderer,
+                                &cell.segments,
+                                &[],
+                                Section::Body,
+                                self.hyphenator.as_deref(),
+                                &letter_breaks(&text),
+                            ),
+                            alignment: cell.alignment,
+                            line_height: self.renderer.line_height(&style),
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let widths = table::column_widths(&shaped, self.dim.width);
+
+        // Nothing wraps beside a table, so a drop cap or float waiting for
+        // text takes lines of its own above it
+        if !self.pending_artefacts.is_empty() {
+            for (_, artefact) in self.pending_artefacts.iter_mut() {
+                artefact.wrap = false;
+            }
+            self.do_paint_paragraph(BlockFormat::new(Style::Normal, Alignment::Left, (0.0, 0.0)));
+        }
+
+        for (row, cells) in rows.iter().zip(&shaped) {
+            for &verse in &row.verses {
+                self.index_verse(verse);
+            }
+            let (fragments, height) =
+                table::layout_row(cells, &widths, self.options.text_direction());
+            let artefact = Artefact::new(
+                ArtefactPadding {
+                    top: 0.0,
+                    bottom: 0.0,
+                    left: 0.0,
+                    right: 0.0,
+                },
+                self.dim.width,
+                height,
+                ArtefactAnchor::Left,
+                false,
+                0,
+                fragments,
+            );
+            self.pending_artefacts.push((Section::Body, artefact));
+            self.do_paint_paragraph(BlockFormat::new(Style::Normal, Alignment::Left, (0.0, 0.0)));
+        }
     }
 
-    pub fn clean(&mut self) {
-        self.buffer.clear();
-        self.pending_artefacts.clear();
+    /// Place a figure with its caption beneath. Floating figures go beside
+    /// the current block's first lines; full-width ones get a block of their
+    /// own, leaving any buffered text for the block that follows.
+    pub fn paint_figure(&mut self, figure: &Figure) {
+        let width = match figure.placement {
+            FigurePlacement::Full => self.dim.width,
+            FigurePlacement::Left | FigurePlacement::Right => self.dim.width * FLOAT_WIDTH,
+        };
+        let image_height = width * figure.aspect_ratio;
+
+        let caption = figure.caption.trim();
+        let segments = vec![(caption.to_string(), Style::FigureCaption)];
+        let caption = shape_segments(
+            &self.renderer,
+            &segments,
+            &[],
+            Section::Body,
+            None,
+            &letter_breaks(caption),
+        );
+        let (mut fragments, caption_height) = figure::layout_caption(
+            &caption,
+            self.renderer.line_height(&Style::FigureCaption),
+            width,
+            self.options.text_direction(),
+        );
+        for fragment in fragments.iter_mut() {
+            fragment.rect.top += image_height + CAPTION_GAP;
+        }
+        fragments.insert(
+            0,
+            TextFragment::image(
+                figure.source.clone(),
+                Rectangle {
+                    top: 0.0,
+                    left: 0.0,
+                    width,
+                    height: image_height,
+                },
+            ),
+        );
+        let height = if caption_height > 0.0 {
+            image_height + CAPTION_GAP + caption_height
+        } else {
+            image_height
+        };
+
+        let (anchor, padding, offset) = match figure.placement {
+            FigurePlacement::Left => (ArtefactAnchor::Left, (0.0, FIGURE_GAP), 0.0),
+            FigurePlacement::Right => (
+                ArtefactAnchor::Right,
+                (FIGURE_GAP, 0.0),
+                self.dim.width - width,
+            ),
+            FigurePlacement::Full => (ArtefactAnchor::Left, (0.0, 0.0), 0.0),
+        };
+        for fragment in fragments.iter_mut() {
+            fragment.rect.left += offset;
+        }
+        let wrap = figure.placement != FigurePlacement::Full;
+        let line_span = if wrap { self.line_span(height) } else { 0 };
+        let gap = if wrap { 0.0 } else { FIGURE_GAP };
+        let artefact = Artefact::new(
+            ArtefactPadding {
+                top: gap,
+                bottom: gap,
+                left: padding.0,
+                right: padding.1,
+            },
+            width,
+            height,
+            anchor,
+            wrap,
+            line_span,
+            fragments,
+        );
+        self.pending_artefacts.push((Section::Body, artefact));
+
+        if !wrap {
+            let buffer = mem::take(&mut self.buffer);
+            self.do_paint_paragraph(BlockFormat::new(Style::Normal, Alignment::Left, (0.0, 0.0)));
+            self.buffer = buffer;
+        }
     }
 
     // --- The core: paint_paragraph ---
 
-    fn do_paint_paragraph(&mut self, alignment: Alignment, indent: (f32, f32)) {
+    fn do_paint_paragraph(&mut self, format: BlockFormat) {
         let buffer = mem::take(&mut self.buffer);
-        let artefacts = mem::take(&mut self.pending_artefacts);
+        let mut artefacts = mem::take(&mut self.pending_artefacts);
+        if format.heading {
+            // A drop cap or float waits for the first block of text
+            let (floating, own) = artefacts.into_iter().partition(|(_, a)| a.wrap);
+            self.pending_artefacts = floating;
+            artefacts = own;
+        }
 
-        self.fill_paragraph(&buffer, &artefacts, alignment, indent, 0);
+        let kept = format
+            .keep_with_next
+            .then(|| (self.state.clone(), self.pages.len()));
+        let placed = self.scaffold.templates.len();
+        self.fill_paragraph(&buffer, &artefacts, format, 0, true);
+
+        // Only a block all on this page can go over with the next
+        self.kept = kept
+            .filter(|(_, page)| *page == self.pages.len())
+            .map(|(state, _)| KeptBlock {
+                templates: self.scaffold.templates.len() - placed,
+                buffer,
+                artefacts,
+                format,
+                state,
+            });
     }
 
+    /// Lay out a paragraph's lines from `stream_offset` into the stream of
+    /// `buffer`. `first_line` is whether this is where the paragraph begins,
+    /// rather than where it carries on after a page break: a carried-on
+    /// paragraph may resume at the start of a buffer entry, so the offset
+    /// alone cannot tell.
     fn fill_paragraph(
         &mut self,
         buffer: &[BufferEntry],
         artefacts: &[(Section, Artefact)],
-        alignment: Alignment,
-        indent: (f32, f32),
+        format: BlockFormat,
         stream_offset: usize,
+        first_line: bool,
     ) {
+        let BlockFormat {
+            line_style,
+            alignment,
+            indent,
+            spacing,
+            keep_together,
+            ..
+        } = format;
+
         // 1. Resolve and shape: walk buffer entries, resolve state-deps, shape text
-        let (stream, buf_map) = self.resolve_and_shape(buffer);
+        let (stream, buf_map, states) = self.resolve_and_shape(buffer);
 
         if stream.is_empty() && artefacts.is_empty() {
             return;
         }
 
+        let spacing_unit = self.renderer.line_height(&Style::Normal);
+        let spacing = (spacing.0 * spacing_unit, spacing.1 * spacing_unit);
+
         // Handle artefact-only templates (e.g., headers with no text in stream)
         if stream.is_empty() {
-            // Record any index markers directly
-            for entry in buffer {
-                if let BufferEntry::IndexMarker(id) = entry {
-                    if *id < self.index_registry.len() {
-                        self.indices
-                            .insert(self.index_registry[*id].clone(), self.pages.len());
-                    }
-                }
-            }
-
-            let body_line_height = self.renderer.line_height(&Style::Normal);
+            let line_height = self.renderer.line_height(&line_style);
             let mut template = Template::new();
-            template.ensure_container(
-                Section::Body,
-                ContainerFill::new(
-                    1,
-                    self.dim.width,
-                    StackDirection::TopDown,
-                    body_line_height,
-                    alignment,
-                    indent,
-                ),
+            let mut fill = ContainerFill::new(
+                1,
+                self.dim.width - self.inset,
+                StackDirection::TopDown,
+                line_height,
+                alignment,
+                (indent.0 + self.inset, indent.1 + self.inset),
             );
+            fill.spacing = spacing;
+            self.mark_box(&mut fill, true, true);
+            template.ensure_container(Section::Body, fill);
             for (section, artefact) in artefacts.iter() {
                 template.add_artefact(*section, artefact.clone());
             }
@@ -417,22 +1366,25 @@ impl Painter {
             match self.scaffold.push(template) {
                 Ok(()) => {}
                 Err(rejected) => {
-                    let page = self.scaffold.finalize(
-                        &self.index_registry,
-                        self.pages.len(),
-                        &mut self.indices,
-                    );
-                    self.pages.push(page);
-                    self.scaffold = Scaffold::new(self.dim.width, self.dim.height);
-                    self.state.reset();
+                    self.break_page();
                     let _ = self.scaffold.push(rejected);
                 }
             }
+
+            // Record any index markers directly, once we know which page took the template
+            for entry in buffer {
+                if let BufferEntry::IndexMarker(id) = entry
+                    && *id < self.index_registry.len()
+                {
+                    self.indices
+                        .insert(self.index_registry[*id].clone(), self.pages.len());
+                }
+            }
             return;
         }
 
         // 2. Build template and fill
-        let body_line_height = self.renderer.line_height(&Style::Normal);
+        let body_line_height = self.renderer.line_height(&line_style);
         let footer_line_height = self.renderer.line_height(&Style::Footnote);
 
         let footer_config = ContainerConfig {
@@ -444,9 +1396,23 @@ impl Painter {
             indent: (0.0, 0.0),
         };
 
+        // Justified text may have its breaks planned for the whole paragraph
+        let breaks = if self.options.line_breaking() == LineBreaking::Optimal
+            && alignment == Alignment::Justified
+        {
+            let first_artefacts = if first_line { artefacts } else { &[] };
+            self.plan_breaks(&stream, stream_offset, indent, first_artefacts)
+        } else {
+            Vec::new()
+        };
+
         // 3. Walk stream, fill templates, push to scaffold
         let mut cursor = stream_offset;
-        let mut template_idx = if stream_offset > 0 { 1 } else { 0 };
+        let mut template_idx = if first_line { 0 } else { 1 };
+        // Templates placed on this page as (stream cursor, template index),
+        // so that a page break can move back to an earlier line
+        let mut page_templates: Vec<(usize, usize)> = Vec::new();
+        let mut lines_placed = 0usize;
         while cursor < stream.len() {
             let mut template = Template::new();
 
@@ -459,17 +1425,12 @@ impl Painter {
             };
 
             // Set up containers in the template
-            template.ensure_container(
-                Section::Body,
-                ContainerFill::new(
-                    1, // max_lines: one line per template for body
-                    self.dim.width,
-                    StackDirection::TopDown,
-                    body_line_height,
-                    alignment,
-                    template_indent,
-                ),
-            );
+            let mut body_fill = self.line_fill(body_line_height, alignment, template_indent);
+            if template_idx == 0 {
+                body_fill.spacing.0 = spacing.0;
+            }
+            self.mark_box(&mut body_fill, template_idx == 0, false);
+            template.ensure_container(Section::Body, body_fill);
 
             // Add pending artefacts only to first template
             if template_idx == 0 {
@@ -480,7 +1441,7 @@ impl Painter {
 
             // Fill the template using next_template algorithm
             let cursor_before = cursor;
-            match self.next_template(&mut template, &stream, &mut cursor, &footer_config) {
+            match self.next_template(&mut template, &stream, &mut cursor, &footer_config, &breaks) {
                 Ok(()) => {}
                 Err(rollback_cursor) => {
                     cursor = rollback_cursor;
@@ -493,16 +1454,52 @@ impl Painter {
 
             // Mark the last template of this paragraph so justification works correctly
             let reached_end = cursor >= stream.len();
-            if reached_end {
-                if let Some(fill) = template.containers.get_mut(&Section::Body) {
-                    fill.is_paragraph_end = true;
-                }
+            if reached_end && let Some(fill) = template.containers.get_mut(&Section::Body) {
+                fill.is_paragraph_end = true;
+                fill.spacing.1 = spacing.1;
+                self.mark_box(fill, false, true);
+            }
+
+            // The page must not end on the paragraph's opening lines, nor
+            // inside a block kept together
+            lines_placed += template
+                .containers
+                .get(&Section::Body)
+                .map_or(0, |fill| fill.num_lines);
+            let orphaned = first_line && lines_placed < self.options.orphan_lines.max(0) as usize;
+            if !reached_end && (orphaned || keep_together) {
+                template.mark_hot();
             }
 
             // Push template to scaffold
             match self.scaffold.push(template) {
-                Ok(()) => {}
+                Ok(()) => page_templates.push((cursor_before, template_idx)),
                 Err(_rejected) => {
+                    // Carry enough lines over to start the next page
+                    let widow_lines = self.options.widow_lines.max(0) as usize;
+                    let carried = if widow_lines > 1 {
+                        let fill =
+                            self.line_fill(body_line_height, alignment, (indent.1, indent.1));
+                        self.count_lines_from(
+                            &stream,
+                            cursor_before,
+                            &fill,
+                            &footer_config,
+                            &breaks,
+                            widow_lines,
+                        )
+                    } else {
+                        widow_lines
+                    };
+                    let (restart, restart_idx) = self
+                        .roll_back(&page_templates, widow_lines.saturating_sub(carried))
+                        .unwrap_or((cursor_before, template_idx));
+                    // The block kept with this one goes over with it
+                    let kept = match self.kept.take() {
+                        Some(kept) if first_line && restart_idx == 0 => self.take_back(kept),
+                        _ => None,
+                    };
+
                     // Page break: finalize current scaffold
                     let page = self.scaffold.finalize(
                         &self.index_registry,
@@ -510,24 +1507,44 @@ impl Painter {
                         &mut self.indices,
                     );
                     self.pages.push(page);
-                    self.scaffold = Scaffold::new(self.dim.width, self.dim.height);
-                    self.state.reset();
+                    self.scaffold = Scaffold::new(
+                        self.dim.width,
+                        self.dim.height,
+                        self.options.text_direction(),
+                    );
+
+                    if let Some(kept) = kept {
+                        self.state = kept.state;
+                        if self.options.effective_caller_reset() == CallerReset::Page {
+                            self.state.reset();
+                        }
+                        self.fill_paragraph(&kept.buffer, &kept.artefacts, kept.format, 0, true);
+                        self.fill_paragraph(buffer, artefacts, format, 0, true);
+                        return;
+                    }
 
                     // Find remaining buffer entries and recurse
-                    let buf_start = buf_map[cursor_before];
+                    let buf_start = buf_map[restart];
+
+                    // The remaining entries are resolved again, so callers either
+                    // restart or carry on from where those entries began
+                    self.state = states[buf_start].clone();
+                    if self.options.effective_caller_reset() == CallerReset::Page {
+                        self.state.reset();
+                    }
                     let entry_first_stream =
                         buf_map.iter().position(|&b| b == buf_start).unwrap_or(0);
-                    let items_to_skip = cursor_before - entry_first_stream;
+                    let items_to_skip = restart - entry_first_stream;
 
                     // Recursive re-fill with remaining buffer + continuation indent
-                    // Carry artefacts forward if the page break happened before they were placed
-                    let carry_artefacts = if template_idx == 0 { artefacts } else { &[] };
+                    let (continuation, carry_artefacts) =
+                        Self::continuation(format, artefacts, restart_idx);
                     self.fill_paragraph(
                         &buffer[buf_start..],
                         carry_artefacts,
-                        alignment,
-                        (indent.1, indent.1), // continuation indent
+                        continuation,
                         items_to_skip,
+                        restart_idx == 0,
                     );
                     return;
                 }
@@ -536,15 +1553,120 @@ impl Painter {
         }
     }
 
+    /// The body container of a one-line paragraph template.
+    fn line_fill(
+        &self,
+        line_height: f32,
+        alignment: Alignment,
+        indent: (f32, f32),
+    ) -> ContainerFill {
+        ContainerFill::new(
+            1, // max_lines: one line per template for body
+            self.dim.width - self.inset,
+            StackDirection::TopDown,
+            line_height,
+            alignment,
+            (indent.0 + self.inset, indent.1 + self.inset),
+        )
+    }
+
+    /// Count the lines the paragraph takes from `cursor` on, up to `limit`,
+    /// each in a template with the body container `fill`.
+    fn count_lines_from(
+        &self,
+        stream: &[StreamItem],
+        mut cursor: usize,
+        fill: &ContainerFill,
+        footer_config: &ContainerConfig,
+        breaks: &[usize],
+        limit: usize,
+    ) -> usize {
+        let mut lines = 0;
+        while cursor < stream.len() && lines < limit {
+            let mut template = Template::new();
+            template.ensure_container(Section::Body, fill.clone());
+            let before = cursor;
+            if let Err(rollback) =
+                self.next_template(&mut template, stream, &mut cursor, footer_config, breaks)
+            {
+                cursor = rollback;
+            }
+            if template.is_empty() || cursor == before {
+                break;
+            }
+            lines += 1;
+        }
+        lines
+    }
+
+    /// Take lines of the paragraph back off the page before it breaks: at
+    /// least `shortfall`, so that enough lines start the next page, then
+    /// any the page must not end on. Returns where the first line taken back
+    /// starts, of `page_templates`; none are taken when the page would be
+    /// left empty.
+    fn roll_back(
+        &mut self,
+        page_templates: &[(usize, usize)],
+        shortfall: usize,
+    ) -> Option<(usize, usize)> {
+        let placed = page_templates.len();
+        let templates = &self.scaffold.templates;
+        let mut moved = shortfall.min(placed);
+        while moved < placed && templates[templates.len() - 1 - moved].is_hot() {
+            moved += 1;
+        }
+        if moved == 0 || moved == templates.len() {
+            return None;
+        }
+        for _ in 0..moved {
+            self.scaffold.pop();
+        }
+        Some(page_templates[placed - moved])
+    }
+
+    /// Take the templates of a block kept with the next one back off the
+    /// page, unless that would leave the page empty.
+    fn take_back(&mut self, kept: KeptBlock) -> Option<KeptBlock> {
+        if kept.templates == 0 || kept.templates >= self.scaffold.templates.len() {
+            return None;
+        }
+        for _ in 0..kept.templates {
+            self.scaffold.pop();
+        }
+        Some(kept)
+    }
+
+    /// The format and artefacts a paragraph carries on with at the top of the
+    /// next page, from its template `restart_idx`. Artefacts are carried
+    /// forward if the page broke before they were placed: the paragraph then
+    /// starts again as it began, drop cap and all.
+    fn continuation(
+        format: BlockFormat,
+        artefacts: &[(Section, Artefact)],
+        restart_idx: usize,
+    ) -> (BlockFormat, &[(Section, Artefact)]) {
+        if restart_idx == 0 {
+            return (format, artefacts);
+        }
+        let continuation = BlockFormat {
+            indent: (format.indent.1, format.indent.1),
+            ..format
+        };
+        (continuation, &[])
+    }
+
     /// Walk the stream from `cursor`, filling `template`.
     /// Returns Ok(()) when template is full (one line in active container filled).
     /// Returns Err(rollback_cursor) if we need to back up.
+    /// `breaks` are stream positions of glue and discretionaries planned as
+    /// line breaks, if any.
     fn next_template(
         &self,
         template: &mut Template,
         stream: &[StreamItem],
         cursor: &mut usize,
         footer_config: &ContainerConfig,
+        breaks: &[usize],
     ) -> Result<(), usize> {
         let mut index = *cursor; // last break point
         let mut committed = 0usize; // items in template at last break point
@@ -592,9 +1714,24 @@ impl Painter {
                         );
                         template.force_push(inline_item, &default_fill);
                         *cursor += 1;
+                    } else if matches!(inline_item.kind, ItemKind::Discretionary) {
+                        // A break inside a word, kept only where its hyphen
+                        // fits and never to start a template; one the line
+                        // never breaks at takes no room
+                        if !in_group
+                            && template.item_count(active_section) > 0
+                            && template.push(inline_item).is_ok()
+                        {
+                            if breaks.binary_search(cursor).is_ok() {
+                                template.mark_break(active_section);
+                            }
+                            index = *cursor;
+                            committed = template.item_count(active_section);
+                        }
+                        *cursor += 1;
                     } else {
                         // Normal fill
-                        if matches!(inline_item.kind, ItemKind::Glue) && !in_group {
+                        if inline_item.is_glue() && !in_group {
                             // Glue outside group: update break point
                             index = *cursor;
                             committed = template.item_count(active_section);
@@ -602,6 +1739,9 @@ impl Painter {
 
                         match template.push(inline_item) {
                             Ok(()) => {
+                                if breaks.binary_search(cursor).is_ok() {
+                                    template.mark_break(active_section);
+                                }
                                 *cursor += 1;
                             }
                             Err(()) => {
@@ -614,7 +1754,7 @@ impl Painter {
                                 }
                                 // Not in group: truncate to last break point
                                 template.truncate(active_section, committed);
-                                *cursor = index + 1; // skip the glue at break point
+                                *cursor = index + 1; // skip the glue or hyphen at break point
                                 return Ok(());
                             }
                         }
@@ -627,24 +1767,102 @@ impl Painter {
         Ok(())
     }
 
+    /// Plan the line breaks of the body text in `stream` from `stream_offset`
+    /// with the optimal breaker, on the same line widths the templates will
+    /// have. Returns the stream positions of the glue and discretionaries to
+    /// break at.
+    fn plan_breaks(
+        &self,
+        stream: &[StreamItem],
+        stream_offset: usize,
+        indent: (f32, f32),
+        artefacts: &[(Section, Artefact)],
+    ) -> Vec<usize> {
+        let mut items = Vec::new();
+        let mut positions = Vec::new();
+        let mut in_group = false;
+        let mut in_expanded = false;
+        for (position, item) in stream.iter().enumerate().skip(stream_offset) {
+            match item {
+                StreamItem::BeginGrouped => in_group = true,
+                StreamItem::EndGrouped => in_group = false,
+                StreamItem::BeginExpanded => in_expanded = true,
+                StreamItem::EndExpanded => in_expanded = false,
+                StreamItem::Inline(item) if !in_expanded => {
+                    items.push(BreakItem {
+                        width: item.width,
+                        kind: item.kind,
+                        breakable: matches!(
+                            item.kind,
+                            ItemKind::Glue | ItemKind::LetterBreak | ItemKind::Discretionary
+                        ) && !in_group,
+                    });
+                    positions.push(position);
+                }
+                StreamItem::Inline(_) => (),
+            }
+        }
+
+        let artefacts: Vec<&Artefact> = artefacts
+            .iter()
+            .filter(|(section, _)| *section == Section::Body)
+            .map(|(_, artefact)| artefact)
+            .collect();
+        let available_width = self.dim.width - self.inset;
+        let inset = self.inset;
+        let width_fn = |line: usize| {
+            let ind = inset + if line == 0 { indent.0 } else { indent.1 };
+            let left_artefact: f32 = artefacts
+                .iter()
+                .filter(|a| line < a.line_span && a.anchor == ArtefactAnchor::Left)
+                .map(|a| a.total_width())
+                .sum();
+            let left_offset = ind.max(left_artefact);
+            let right_artefact: f32 = artefacts
+                .iter()
+                .filter(|a| line < a.line_span && a.anchor == ArtefactAnchor::Right)
+                .map(|a| a.total_width())
+                .sum();
+            (left_offset, available_width - left_offset - right_artefact)
+        };
+        let distinct_lines = artefacts
+            .iter()
+            .map(|a| a.line_span)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        knuth_plass::optimal_breaks(&items, &width_fn, distinct_lines)
+            .into_iter()
+            .map(|i| positions[i])
+            .collect()
+    }
+
     /// Resolve buffer entries: evaluate state-deps, shape each entry independently.
-    /// Returns (stream, buf_map) where buf_map[i] is the buffer entry index that
-    /// produced stream[i].
-    fn resolve_and_shape(&mut self, buffer: &[BufferEntry]) -> (Vec<StreamItem>, Vec<usize>) {
+    /// Returns (stream, buf_map, states) where buf_map[i] is the buffer entry index that
+    /// produced stream[i], and states[j] is the layout state before buffer entry j.
+    fn resolve_and_shape(
+        &mut self,
+        buffer: &[BufferEntry],
+    ) -> (Vec<StreamItem>, Vec<usize>, Vec<LayoutState>) {
         let mut stream: Vec<StreamItem> = Vec::new();
         let mut buf_map: Vec<usize> = Vec::new();
+        let mut states: Vec<LayoutState> = Vec::with_capacity(buffer.len());
         let mut pending_index_id: Option<usize> = None;
 
         // First pass: resolve all entries into (text, style, section) segments + markers
         struct ResolvedSegment {
             text: String,
             style: Style,
+            outer_styles: Vec<Style>,
+            attributes: Vec<(String, String)>,
             section: Section,
             index_id: Option<usize>,
             buf_idx: usize,
         }
         enum ResolvedEntry {
             Segment(ResolvedSegment),
+            Fill(Style, Section, usize),
             BeginGrouped(usize),
             EndGrouped(usize),
             BeginExpanded(usize),
@@ -654,21 +1872,29 @@ impl Painter {
         let mut resolved: Vec<ResolvedEntry> = Vec::new();
 
         for (buf_idx, entry) in buffer.iter().enumerate() {
+            states.push(self.state.clone());
             match entry {
                 BufferEntry::Segment {
                     text,
                     style,
+                    outer_styles,
+                    attributes,
                     section,
                 } => {
                     let idx = pending_index_id.take();
                     resolved.push(ResolvedEntry::Segment(ResolvedSegment {
                         text: text.clone(),
                         style: *style,
+                        outer_styles: outer_styles.clone(),
+                        attributes: attributes.clone(),
                         section: *section,
                         index_id: idx,
                         buf_idx,
                     }));
                 }
+                BufferEntry::Fill { style, section } => {
+                    resolved.push(ResolvedEntry::Fill(*style, *section, buf_idx));
+                }
                 BufferEntry::StateDep(f, section) => {
                     let (text, style) = f(&mut self.state);
                     if !text.is_empty() {
@@ -676,6 +1902,8 @@ impl Painter {
                         resolved.push(ResolvedEntry::Segment(ResolvedSegment {
                             text,
                             style,
+                            outer_styles: Vec::new(),
+                            attributes: Vec::new(),
                             section: *section,
                             index_id: idx,
                             buf_idx,
@@ -700,6 +1928,23 @@ impl Painter {
             }
         }
 
+        // Letter breaks are found in each section's text as a whole, so that
+        // a dictionary sees words running across styles, and a break may fall
+        // where one segment meets the next
+        let mut section_text: HashMap<Section, String> = HashMap::new();
+        let mut segment_starts = vec![0; resolved.len()];
+        for (i, entry) in resolved.iter().enumerate() {
+            if let ResolvedEntry::Segment(seg) = entry {
+                let text = section_text.entry(seg.section).or_default();
+                segment_starts[i] = text.len();
+                text.push_str(&seg.text);
+            }
+        }
+        let section_breaks: HashMap<Section, Vec<usize>> = section_text
+            .iter()
+            .map(|(section, text)| (*section, letter_breaks(text)))
+            .collect();
+
         // Second pass: shape each segment independently (no batching)
         let mut i = 0;
         while i < resolved.len() {
@@ -724,13 +1969,45 @@ impl Painter {
                     stream.push(StreamItem::EndExpanded);
                     i += 1;
                 }
+                ResolvedEntry::Fill(style, section, bi) => {
+                    buf_map.push(*bi);
+                    stream.push(StreamItem::Inline(InlineItem {
+                        text: String::new(),
+                        style: *style,
+                        outer_styles: Vec::new(),
+                        attributes: Vec::new(),
+                        width: 0.0,
+                        kind: ItemKind::Fill,
+                        section: *section,
+                        index_id: None,
+                    }));
+                    i += 1;
+                }
                 ResolvedEntry::Segment(seg) => {
                     let segments = vec![(seg.text.clone(), seg.style)];
-                    let mut items = shape_segments(&self.renderer, &segments, seg.section);
+                    let (start, end) = (segment_starts[i], segment_starts[i] + seg.text.len());
+                    let breaks = &section_breaks[&seg.section];
+                    let from = breaks.partition_point(|&b| b < start);
+                    let to = breaks.partition_point(|&b| b < end);
+                    let letter_breaks: Vec<usize> =
+                        breaks[from..to].iter().map(|b| b - start).collect();
+                    let mut items = shape_segments(
+                        &self.renderer,
+                        &segments,
+                        &seg.outer_styles,
+                        seg.section,
+                        self.hyphenator.as_deref(),
+                        &letter_breaks,
+                    );
                     // Assign index_id to first item if present
-                    if let Some(idx_id) = seg.index_id {
-                        if let Some(first) = items.first_mut() {
-                            first.index_id = Some(idx_id);
+                    if let Some(idx_id) = seg.index_id
+                        && let Some(first) = items.first_mut()
+                    {
+                        first.index_id = Some(idx_id);
+                    }
+                    if !seg.attributes.is_empty() {
+                        for item in items.iter_mut() {
+                            item.attributes = seg.attributes.clone();
                         }
                     }
                     for item in items {
@@ -742,12 +2019,15 @@ impl Painter {
             }
         }
 
-        (stream, buf_map)
+        (stream, buf_map, states)
     }
 
     // --- Final layout ---
 
     pub fn layout(&mut self) -> (Vec<Page>, Indices) {
+        // A sidebar left open runs to the end of the book
+        self.end_sidebar();
+
         // Finalize last scaffold
         if !self.scaffold.templates.is_empty() {
             let page =
@@ -759,3 +2039,436 @@ impl Painter {
         (mem::take(&mut self.pages), mem::take(&mut self.indices))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::normalize::normalize;
+    use usfm::ArchivedBook;
+
+    const LINE_HEIGHT: f32 = 10.0;
+    const FONT_FAMILY: &str = "serif";
+
+    fn painter(lines_per_page: usize) -> Painter {
+        let dim = Dimensions {
+            width: 100.0,
+            height: LINE_HEIGHT * lines_per_page as f32,
+            header_height: 0.0,
+            drop_cap_padding: 0.0,
+        };
+        let mut renderer = Renderer::new();
+        renderer.insert_style(
+            Style::Normal,
+            TextStyle {
+                font_family: FONT_FAMILY.as_ptr() as *const c_char,
+                font_family_len: FONT_FAMILY.len(),
+                font_size: LINE_HEIGHT,
+                height: 1.0,
+                letter_spacing: 0.0,
+                word_spacing: 0.0,
+                decoration: Decoration::None,
+                font_weight: 0,
+                italic: 0,
+                small_caps: 0,
+                color: 0,
+                background_color: 0,
+            },
+        );
+        // As registered along with the normal style over the FFI
+        let mut chapter = renderer.text_style(&Style::Normal);
+        chapter.font_size *= 2.0;
+        renderer.insert_style(Style::Chapter, chapter);
+        Painter::new(&renderer, dim, LayoutOptions::default())
+    }
+
+    /// Lay out a book from its source on pages of `lines_per_page` lines.
+    fn lay_out(usfm: &str, lines_per_page: usize) -> (Vec<Page>, Indices) {
+        let book = usfm::parse(&normalize(usfm));
+        let bytes = rkyv::to_bytes::<Error>(&book).unwrap();
+        let book = rkyv::access::<ArchivedBook, Error>(&bytes).unwrap();
+        let mut painter = painter(lines_per_page);
+        book.paint(&mut painter);
+        painter.layout()
+    }
+
+    fn item(kind: ItemKind, width: f32) -> InlineItem {
+        InlineItem {
+            text: String::new(),
+            style: Style::Normal,
+            outer_styles: Vec::new(),
+            attributes: Vec::new(),
+            width,
+            kind,
+            section: Section::Body,
+            index_id: None,
+        }
+    }
+
+    /// `words` words of width 40 with glue between them, two to a line.
+    fn stream(words: usize) -> Vec<StreamItem> {
+        let mut stream = Vec::new();
+        for n in 0..words {
+            if n > 0 {
+                stream.push(StreamItem::Inline(item(ItemKind::Glue, 5.0)));
+            }
+            stream.push(StreamItem::Inline(item(ItemKind::Word, 40.0)));
+        }
+        stream
+    }
+
+    fn line(painter: &Painter, hot: bool) -> Template {
+        let mut template = Template::new();
+        template.ensure_container(
+            Section::Body,
+            painter.line_fill(LINE_HEIGHT, Alignment::Left, (0.0, 0.0)),
+        );
+        template.push(&item(ItemKind::Word, 40.0)).unwrap();
+        if hot {
+            template.mark_hot();
+        }
+        template
+    }
+
+    fn footer_config() -> ContainerConfig {
+        ContainerConfig {
+            max_lines: usize::MAX,
+            available_width: 100.0,
+            direction: StackDirection::BottomUp,
+            line_height: LINE_HEIGHT,
+            alignment: Alignment::Left,
+            indent: (0.0, 0.0),
+        }
+    }
+
+    #[test]
+    fn counts_lines_up_to_the_limit() {
+        let painter = painter(10);
+        let fill = painter.line_fill(LINE_HEIGHT, Alignment::Left, (0.0, 0.0));
+        // Five words break as 0..3, 4..7 and 8
+        let stream = stream(5);
+        let cases = [(0, 5, 3), (0, 2, 2), (4, 5, 2), (8, 5, 1), (9, 5, 0)];
+        for (cursor, limit, expected) in cases {
+            let lines =
+                painter.count_lines_from(&stream, cursor, &fill, &footer_config(), &[], limit);
+            assert_eq!(lines, expected, "{cursor:?} {limit:?}");
+        }
+    }
+
+    #[test]
+    fn rolls_back_lines_to_the_next_page() {
+        // Whether each line already on the page and each of the paragraph's
+        // lines is hot; the shortfall; and the paragraph's line taken back
+        // first, with how many lines stay on the page
+        type Case = (
+            &'static [bool],
+            &'static [bool],
+            usize,
+            Option<(usize, usize)>,
+        );
+        let cases: [Case; 6] = [
+            // Enough lines start the next page already
+            (&[], &[true, false], 0, None),
+            (&[], &[true, false, false], 1, Some((2, 2))),
+            // The page does not end on the paragraph's opening lines
+            (&[false], &[true, false], 2, Some((0, 1))),
+            (&[false], &[true, true, false], 1, Some((0, 1))),
+            // Nor is it emptied
+            (&[], &[true, false], 2, None),
+            (&[], &[true, true, false], 1, None),
+        ];
+        for (before, paragraph, shortfall, expected) in cases {
+            let mut painter = painter(10);
+            let mut page_templates = Vec::new();
+            for &hot in before.iter().chain(paragraph) {
+                let template = line(&painter, hot);
+                assert!(painter.scaffold.push(template).is_ok());
+            }
+            // Each line starts three items on in the stream
+            for template_idx in 0..paragraph.len() {
+                page_templates.push((template_idx * 3, template_idx));
+            }
+
+            let placed = painter.scaffold.templates.len();
+            let restart = painter.roll_back(&page_templates, shortfall);
+            let kept = painter.scaffold.templates.len();
+            let message = format!("{before:?} {paragraph:?} {shortfall:?}");
+            let expected_restart = expected.map(|(idx, _)| (idx * 3, idx));
+            assert_eq!(restart, expected_restart, "{message}");
+            assert_eq!(kept, expected.map_or(placed, |(_, kept)| kept), "{message}");
+            let height = kept as f32 * LINE_HEIGHT;
+            assert_eq!(painter.scaffold.top_cursor, height, "{message}");
+        }
+    }
+
+    #[test]
+    fn restarts_the_paragraph_from_its_first_line() {
+        let format = BlockFormat::new(Style::Normal, Alignment::Justified, (30.0, 10.0));
+        let drop_cap = Artefact::new(
+            ArtefactPadding {
+                top: 0.0,
+                bottom: 0.0,
+                left: 0.0,
+                right: 4.0,
+            },
+            20.0,
+            20.0,
+            ArtefactAnchor::Left,
+            true,
+            2,
+            Vec::new(),
+        );
+        let artefacts = [(Section::Body, drop_cap)];
+
+        // Rolled back to its first line, the paragraph begins again with its
+        // first-line indent and drop cap
+        let (restarted, carried) = Painter::continuation(format, &artefacts, 0);
+        assert_eq!(restarted.indent, (30.0, 10.0));
+        assert_eq!(carried.len(), 1);
+
+        let (continued, carried) = Painter::continuation(format, &artefacts, 2);
+        assert_eq!(continued.indent, (10.0, 10.0));
+        assert!(carried.is_empty());
+    }
+
+    /// Lay out blocks of words on a page `lines_per_page` lines tall, one word
+    /// to a line, as no word fits a line. Returns the words and tops of each page.
+    fn fill(lines_per_page: usize, blocks: &[(&[&str], BlockFormat)]) -> Vec<Vec<(String, f32)>> {
+        let mut painter = painter(lines_per_page);
+        painter.dim.width = -1.0;
+        for (words, format) in blocks {
+            for word in words.iter() {
+                painter.add_text(format!("{word} "));
+            }
+            painter.paint_block(*format);
+        }
+        let (pages, _) = painter.layout();
+        pages
+            .iter()
+            .map(|page| page.iter().map(|f| (f.text.clone(), f.rect.top)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn moves_a_kept_together_block_to_the_next_page() {
+        let format = BlockFormat::new(Style::Normal, Alignment::Left, (0.0, 0.0));
+        let pages = fill(
+            4,
+            &[
+                (&["one", "two"], format),
+                (&["three", "four", "five"], format.kept_together()),
+            ],
+        );
+        let words = |page: &Vec<(String, f32)>| page.iter().map(|(w, _)| w.clone()).collect();
+        let words: Vec<Vec<String>> = pages.iter().map(words).collect();
+        assert_eq!(words, [vec!["one", "two"], vec!["three", "four", "five"]]);
+        assert_eq!(pages[1][0].1, 0.0);
+    }
+
+    #[test]
+    fn carries_a_paragraph_on_without_its_opening_space() {
+        // The page breaks before "four", at the start of a buffer entry, and
+        // the paragraph carries on from there without its space above
+        let format = BlockFormat::new(Style::Normal, Alignment::Left, (0.0, 0.0));
+        let spaced = format.with_spacing(1.0, 0.0);
+        let pages = fill(
+            4,
+            &[
+                (&["one"], format),
+                (&["two", "three", "four", "five"], spaced),
+            ],
+        );
+        let expected = [
+            vec![("one", 0.0), ("two", 20.0), ("three", 30.0)],
+            vec![("four", 0.0), ("five", 10.0)],
+        ];
+        for (page, expected) in pages.iter().zip(expected) {
+            let page: Vec<(&str, f32)> = page.iter().map(|(w, top)| (w.as_str(), *top)).collect();
+            assert_eq!(page, expected);
+        }
+        assert_eq!(pages.len(), 2);
+    }
+
+    #[test]
+    fn moves_a_block_over_with_the_next() {
+        let format = BlockFormat::new(Style::Normal, Alignment::Left, (0.0, 0.0));
+        let line = format.kept_together();
+        let words = |pages: Vec<Vec<(String, f32)>>| -> Vec<Vec<String>> {
+            pages
+                .into_iter()
+                .map(|page| page.into_iter().map(|(word, _)| word).collect())
+                .collect()
+        };
+        // The second line of a couplet starts the next page, and takes the
+        // first along, unless that would leave the page empty
+        let pages = fill(
+            4,
+            &[
+                (&["one", "two"], format),
+                (&["three"], line.kept_with_next()),
+                (&["four", "five"], line),
+            ],
+        );
+        assert_eq!(
+            words(pages),
+            [vec!["one", "two"], vec!["three", "four", "five"]]
+        );
+        let pages = fill(
+            4,
+            &[
+                (&["one", "two", "three"], line.kept_with_next()),
+                (&["four", "five"], line),
+            ],
+        );
+        assert_eq!(
+            words(pages),
+            [vec!["one", "two", "three"], vec!["four", "five"]]
+        );
+        // A block not kept with the next stays
+        let pages = fill(
+            4,
+            &[(&["one", "two", "three"], line), (&["four", "five"], line)],
+        );
+        assert_eq!(
+            words(pages),
+            [vec!["one", "two", "three"], vec!["four", "five"]]
+        );
+    }
+
+    #[test]
+    fn keeps_couplets_together() {
+        let usfm = "\\id PSA\n\\h Psalms\n\\c 1\n\\q1\n\\v 1 Blessed\n\\q2 who walks\n\\q1 nor stands\n\\q2 nor sits\n\\q1\n\\v 2 But his\n\\q2 and on\n";
+        let (pages, _) = lay_out(usfm, 5);
+        let first_lines: Vec<&str> = pages.iter().map(|page| page[0].text.as_str()).collect();
+        assert_eq!(first_lines, ["Psalms", "nor stands"]);
+    }
+
+    #[test]
+    fn indexes_headings_apart_from_the_book_header() {
+        let usfm = "\\id GEN\n\\h Genesis\n\\c 1\n\\s1 The Creation\n\\p\n\\v 1 In the beginning\n";
+        let (_, indices) = lay_out(usfm, 20);
+        let heading = indices
+            .keys()
+            .find(|index| index.heading.is_some())
+            .expect("heading index");
+        assert_eq!(heading.header, "Genesis");
+        assert_eq!(heading.heading.as_deref(), Some("The Creation"));
+        assert_eq!(heading.chapter, Some(1));
+        // Verses keep the book header in their keys
+        let verse = Index::new(
+            BookIdentifier::Genesis,
+            "Genesis".into(),
+            Some(1),
+            Some(1.into()),
+        );
+        assert!(indices.contains_key(&verse));
+    }
+
+    #[test]
+    fn lays_tables_out_a_row_at_a_time() {
+        let usfm = "\\id NUM\n\\h Numbers\n\\c 1\n\\tr \\th1 Tribe \\thr2 Number\n\\tr \\tc1 \\v 20 Reuben \\tcr2 46,500\n\\tr \\tc1 \\v 22 Simeon \\tcr2 59,300\n";
+        let (pages, indices) = lay_out(usfm, 5);
+        let texts: Vec<Vec<(&str, Style)>> = pages
+            .iter()
+            .map(|page| page.iter().map(|f| (f.text.as_str(), f.style)).collect())
+            .collect();
+        // The drop cap takes its own line, and the last row goes over whole
+        assert_eq!(
+            texts,
+            [
+                vec![
+                    ("Numbers", Style::Header),
+                    ("1", Style::Chapter),
+                    ("Tribe", Style::TableHeader),
+                    ("Number", Style::TableHeader),
+                    ("20", Style::Verse),
+                    ("Reuben", Style::Normal),
+                    ("46,500", Style::Normal),
+                ],
+                vec![
+                    ("22", Style::Verse),
+                    ("Simeon", Style::Normal),
+                    ("59,300", Style::Normal),
+                ],
+            ]
+        );
+        let verse = |verse: u16| {
+            Index::new(
+                BookIdentifier::Numbers,
+                "Numbers".into(),
+                Some(1),
+                Some(verse.into()),
+            )
+        };
+        assert_eq!(indices.get(&verse(20)), Some(&0));
+        assert_eq!(indices.get(&verse(22)), Some(&1));
+    }
+
+    #[test]
+    fn boxes_sidebars() {
+        let usfm = "\\id GEN\n\\h G\n\\c 1\n\\p\n\\v 1 Text\n\\esb\n\\s1 Aside\n\\p More\n\\esbe\n\\p\n\\v 2 After\n";
+        let (pages, _) = lay_out(usfm, 20);
+        let page = &pages[0];
+        let frame = &page[0];
+        assert_eq!(frame.style, Style::Sidebar);
+        assert!(
+            frame
+                .frame
+                .as_ref()
+                .is_some_and(|frame| !frame.continued && !frame.continues)
+        );
+        let top = |text: &str| page.iter().find(|f| f.text == text).unwrap().rect.top;
+        let bottom = frame.rect.top + frame.rect.height;
+        // The heading and paragraph between \esb and \esbe are boxed, inset
+        // by the padding, and the text around them is not
+        for text in ["Aside", "More"] {
+            assert!(top(text) > frame.rect.top && top(text) < bottom, "{text}");
+        }
+        assert!(top("Text") < frame.rect.top);
+        assert!(top("After") >= bottom);
+        let more = page.iter().find(|f| f.text == "More").unwrap();
+        let after = page.iter().find(|f| f.text == "After").unwrap();
+        assert_eq!(more.rect.left, after.rect.left + BOX_PADDING);
+    }
+
+    #[test]
+    fn keeps_text_clear_of_a_tall_drop_cap() {
+        let usfm = "\\id GEN\n\\h G\n\\c 1\n\\ca 2\\ca*\n\\p\n\\v 1 a\n\\p\n\\v 2 b\n";
+        let (pages, _) = lay_out(usfm, 20);
+        let top = |text: &str| {
+            let fragment = pages[0].iter().find(|f| f.text == text).unwrap();
+            (fragment.rect.top, fragment.rect.top + fragment.rect.height)
+        };
+        // The alternate number under the drop cap takes a third line
+        assert_eq!(top("1").1, top("(2)").0);
+        assert_eq!(top("(2)").1, top("b").0);
+    }
+
+    #[test]
+    fn publishes_chapter_numbers_in_book_wide_labels() {
+        let usfm = "\\id PSA\n\\h Psalms\n\\cl Psalm\n\\c 1\n\\cp A\n\\q1\n\\v 1 Blessed\n";
+        let (pages, _) = lay_out(usfm, 20);
+        let label = pages[0].iter().find(|f| f.style == Style::ChapterLabel);
+        assert_eq!(label.map(|f| f.text.as_str()), Some("Psalm A"));
+    }
+
+    #[test]
+    fn shows_explicit_note_callers() {
+        let usfm =
+            "\\id GEN\n\\h G\n\\c 1\n\\p\n\\v 1 In the beginning\\f * \\ft Or when\\f* God\n";
+        let (pages, _) = lay_out(usfm, 20);
+        let texts: Vec<(&str, Style)> = pages[0]
+            .iter()
+            .filter(|f| matches!(f.style, Style::Caller | Style::NoteText))
+            .map(|f| (f.text.as_str(), f.style))
+            .collect();
+        // In the text and again before the note
+        assert_eq!(
+            texts,
+            [
+                ("*", Style::Caller),
+                ("*", Style::Caller),
+                ("Or when", Style::NoteText)
+            ]
+        );
+    }
+}
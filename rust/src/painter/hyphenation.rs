@@ -0,0 +1,214 @@
+//! Liang's hyphenation, as in TeX: words are split where the patterns of
+//! their language leave an odd value between two letters. Patterns are read
+//! in the TeX format (`\patterns{...}` / `\hyphenation{...}`, or the plain
+//! lists of hyph-utf8) and registered on the renderer per language tag.
+
+use std::collections::HashMap;
+
+/// Letters kept together at the start and end of a word.
+const LEFT_MIN: usize = 2;
+const RIGHT_MIN: usize = 3;
+
+#[derive(Debug, Clone, Default)]
+pub struct Hyphenator {
+    /// Pattern letters to their values, one more value than letters.
+    patterns: HashMap<String, Vec<u8>>,
+    /// Whole words with their own breaks, as char positions.
+    exceptions: HashMap<String, Vec<usize>>,
+    max_pattern: usize,
+}
+
+impl Hyphenator {
+    /// Read patterns, skipping `%` comments and TeX commands. Entries with a
+    /// hyphen in them (such as `ta-ble`) are exceptions.
+    pub fn new(patterns: &str) -> Self {
+        let mut hyphenator = Self::default();
+        for line in patterns.lines() {
+            let line = line.split('%').next().unwrap_or_default();
+            for token in line.split(|c: char| c.is_whitespace() || c == '{' || c == '}') {
+                if token.is_empty() || token.starts_with('\\') {
+                    continue;
+                }
+                if token.contains('-') {
+                    hyphenator.add_exception(token);
+                } else {
+                    hyphenator.add_pattern(token);
+                }
+            }
+        }
+        hyphenator
+    }
+
+    fn add_pattern(&mut self, pattern: &str) {
+        let mut letters = String::new();
+        let mut values = vec![0u8];
+        for c in pattern.chars() {
+            match c.to_digit(10) {
+                Some(value) => *values.last_mut().unwrap() = value as u8,
+                None => {
+                    letters.extend(c.to_lowercase());
+                    values.push(0);
+                }
+            }
+        }
+        self.max_pattern = self.max_pattern.max(values.len() - 1);
+        self.patterns.insert(letters, values);
+    }
+
+    fn add_exception(&mut self, exception: &str) {
+        let mut word = String::new();
+        let mut breaks = Vec::new();
+        for c in exception.chars() {
+            if c == '-' {
+                breaks.push(word.chars().count());
+            } else {
+                word.extend(c.to_lowercase());
+            }
+        }
+        self.exceptions.insert(word, breaks);
+    }
+
+    /// Byte offsets in `word` where it may be hyphenated. Punctuation around
+    /// the word is left alone, and words with anything but letters inside
+    /// are not hyphenated.
+    pub fn hyphenate(&self, word: &str) -> Vec<usize> {
+        let core_start = word
+            .char_indices()
+            .find(|(_, c)| c.is_alphabetic())
+            .map_or(word.len(), |(i, _)| i);
+        let core_end = word
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_alphabetic())
+            .map_or(core_start, |(i, c)| i + c.len_utf8());
+        if core_start >= core_end {
+            return Vec::new();
+        }
+        let core = &word[core_start..core_end];
+        if !core.chars().all(char::is_alphabetic) {
+            return Vec::new();
+        }
+
+        // One lowercase char per char of the word, so positions line up
+        let letters: Vec<char> = core
+            .chars()
+            .map(|c| c.to_lowercase().next().unwrap_or(c))
+            .collect();
+        let len = letters.len();
+        if len < LEFT_MIN + RIGHT_MIN {
+            return Vec::new();
+        }
+
+        let lower: String = letters.iter().collect();
+        let breaks: Vec<usize> = match self.exceptions.get(&lower) {
+            Some(breaks) => breaks.clone(),
+            None => {
+                let dotted: Vec<char> = ['.']
+                    .into_iter()
+                    .chain(letters.iter().copied())
+                    .chain(['.'])
+                    .collect();
+                let mut values = vec![0u8; dotted.len() + 1];
+                for start in 0..dotted.len() {
+                    let longest = self.max_pattern.min(dotted.len() - start);
+                    for end in start + 1..=start + longest {
+                        let key: String = dotted[start..end].iter().collect();
+                        if let Some(pattern) = self.patterns.get(&key) {
+                            for (k, &value) in pattern.iter().enumerate() {
+                                values[start + k] = values[start + k].max(value);
+                            }
+                        }
+                    }
+                }
+                // The value between letters i - 1 and i sits after the dot
+                (1..len).filter(|&i| values[i + 1] % 2 == 1).collect()
+            }
+        };
+
+        let offsets: Vec<usize> = core.char_indices().map(|(i, _)| i).collect();
+        breaks
+            .into_iter()
+            .filter(|&i| i >= LEFT_MIN && len - i >= RIGHT_MIN && i < len)
+            .map(|i| core_start + offsets[i])
+            .collect()
+    }
+}
+
+/// Lowercased language tag, with `_` as `-`.
+pub fn normalize_language(language: &str) -> String {
+    language.trim().replace('_', "-").to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Liang's example patterns, with a couple of exceptions.
+    const PATTERNS: &str = "% Liang's example\n\
+        \\patterns{hy3ph he2n hena4 hen5at 1na n2at 1tio 2io o2n}\n\
+        \\hyphenation{ta-ble a-b-c-d-e-f Über-set-zung}\n";
+
+    /// The word with a hyphen at each break.
+    fn hyphenated(hyphenator: &Hyphenator, word: &str) -> String {
+        let mut out = String::new();
+        let mut last = 0;
+        for at in hyphenator.hyphenate(word) {
+            out.push_str(&word[last..at]);
+            out.push('-');
+            last = at;
+        }
+        out.push_str(&word[last..]);
+        out
+    }
+
+    #[test]
+    fn hyphenates_by_pattern_and_exception() {
+        let hyphenator = Hyphenator::new(PATTERNS);
+        let cases = [
+            ("hyphenation", "hy-phen-ation"),
+            ("Hyphenation", "Hy-phen-ation"),
+            ("table", "ta-ble"),
+            ("TABLE", "TA-BLE"),
+            ("nation", "na-tion"),
+        ];
+        for (word, expected) in cases {
+            assert_eq!(hyphenated(&hyphenator, word), expected, "{word:?}");
+        }
+    }
+
+    #[test]
+    fn keeps_letters_together_at_the_ends() {
+        let hyphenator = Hyphenator::new(PATTERNS);
+        assert_eq!(hyphenated(&hyphenator, "abcdef"), "ab-c-def");
+        // Too short for LEFT_MIN + RIGHT_MIN
+        assert_eq!(hyphenated(&hyphenator, "hyph"), "hyph");
+    }
+
+    #[test]
+    fn leaves_punctuation_alone() {
+        let hyphenator = Hyphenator::new(PATTERNS);
+        let cases = [
+            ("(table),", "(ta-ble),"),
+            ("“hyphenation”", "“hy-phen-ation”"),
+            ("ta-ble", "ta-ble"),
+            ("table's", "table's"),
+            ("...", "..."),
+        ];
+        for (word, expected) in cases {
+            assert_eq!(hyphenated(&hyphenator, word), expected, "{word:?}");
+        }
+    }
+
+    #[test]
+    fn lowercases_beyond_ascii() {
+        let hyphenator = Hyphenator::new(PATTERNS);
+        assert_eq!(hyphenated(&hyphenator, "ÜBERSETZUNG"), "ÜBER-SET-ZUNG");
+        assert_eq!(hyphenated(&hyphenator, "übersetzung"), "über-set-zung");
+    }
+
+    #[test]
+    fn normalizes_language_tags() {
+        assert_eq!(normalize_language(" en_GB "), "en-gb");
+        assert_eq!(normalize_language("sr-Latn"), "sr-latn");
+    }
+}
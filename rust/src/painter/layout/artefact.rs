@@ -1,5 +1,6 @@
 use super::fragment::TextFragment;
 
+/// The side an artefact sits on; on right-to-left pages the sides swap.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[allow(dead_code)]
 pub enum ArtefactAnchor {
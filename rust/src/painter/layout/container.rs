@@ -9,11 +9,25 @@ pub enum StackDirection {
     BottomUp,
 }
 
+/// Text worked out from the layout state when it is laid out, such as an
+/// automatic note caller.
+pub type StateText = Box<dyn Fn(&mut LayoutState) -> (String, Style)>;
+
 /// What lives in the painter's buffer before paint_paragraph() processes it.
 /// This is a cross-container flat stream.
 pub enum BufferEntry {
-    Segment { text: String, style: Style, section: Section },
-    StateDep(Box<dyn Fn(&mut LayoutState) -> (String, Style)>, Section),
+    Segment {
+        text: String,
+        style: Style,
+        outer_styles: Vec<Style>,
+        attributes: Vec<(String, String)>,
+        section: Section,
+    },
+    Fill {
+        style: Style,
+        section: Section,
+    },
+    StateDep(StateText, Section),
     BeginGrouped,
     EndGrouped,
     BeginExpanded,
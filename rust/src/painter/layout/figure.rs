@@ -0,0 +1,73 @@
+use super::fragment::{TextFragment, extract_fragments};
+use super::inline::InlineItem;
+use super::line_breaker::LineBreaker;
+use super::{Alignment, TextDirection};
+
+/// Space between a floating figure and the text beside it.
+pub const FIGURE_GAP: f32 = 10.0;
+/// Space between a figure's image and its caption.
+pub const CAPTION_GAP: f32 = 4.0;
+/// Share of the page width taken by a floating figure.
+pub const FLOAT_WIDTH: f32 = 0.5;
+/// Height over width of the space kept for an image, whose own size is only
+/// known where it is drawn.
+pub const DEFAULT_ASPECT_RATIO: f32 = 0.75;
+
+/// Where a figure sits: floating at one side with text wrapping beside it,
+/// or across the full width with text above and below.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FigurePlacement {
+    Left,
+    Right,
+    Full,
+}
+
+impl FigurePlacement {
+    /// Where a figure of a `\fig` `size` goes: `span` across the page, and
+    /// `col` or anything else floating right.
+    pub fn from_size(size: &str) -> Self {
+        match size.trim() {
+            "span" => Self::Full,
+            _ => Self::Right,
+        }
+    }
+}
+
+/// A `\fig` map or illustration.
+#[derive(Debug, Clone)]
+pub struct Figure {
+    /// Path of the image file, as written in the source.
+    pub source: String,
+    pub caption: String,
+    /// Height of the image over its width.
+    pub aspect_ratio: f32,
+    pub placement: FigurePlacement,
+}
+
+/// Lay out a caption centred within the figure's `width`. Fragments are
+/// positioned relative to the top of the caption. Returns the fragments
+/// and the caption's height.
+pub fn layout_caption(
+    items: &[InlineItem],
+    line_height: f32,
+    width: f32,
+    direction: TextDirection,
+) -> (Vec<TextFragment>, f32) {
+    let breaker = LineBreaker::new(items, Box::new(move |_| (0.0, width)));
+    let lines: Vec<_> = breaker.collect();
+    let mut fragments = Vec::new();
+    for (line_idx, line) in lines.iter().enumerate() {
+        fragments.extend(extract_fragments(
+            items,
+            line,
+            line_idx as f32 * line_height,
+            line_height,
+            0.0,
+            width,
+            line_idx == lines.len() - 1,
+            &Alignment::Center,
+            direction,
+        ));
+    }
+    (fragments, lines.len() as f32 * line_height)
+}
@@ -1,31 +1,154 @@
 use std::mem;
 
 use rkyv::{Archive, Serialize};
+use unicode_bidi::{BidiClass, BidiInfo, Level, bidi_class};
 
 use crate::painter::{Rectangle, Style};
 
-use super::Alignment;
 use super::inline::{BrokenLine, InlineItem, ItemKind};
+use super::{Alignment, TextDirection};
+
+/// The frame drawn behind a boxed block on one page, and whether the block
+/// is continued from the previous page or continues on the next.
+#[derive(Archive, Serialize, Debug, Clone, Copy)]
+pub struct Frame {
+    pub continued: bool,
+    pub continues: bool,
+}
 
 #[derive(Archive, Serialize, Debug, Clone)]
 pub struct TextFragment {
     pub text: String,
     pub rect: Rectangle,
     pub style: Style,
+    /// Styles `style` is nested in, outermost first.
+    pub outer_styles: Vec<Style>,
+    /// `\w` attributes of the word this fragment shows, if any.
+    pub attributes: Vec<(String, String)>,
     pub word_spacing: f32,
+    /// Source path of the figure this fragment shows instead of text.
+    pub image: Option<String>,
+    /// Whether the text runs right to left, to be drawn from the right.
+    pub rtl: bool,
+    /// Set on the fragment that draws a boxed block's frame instead of text.
+    pub frame: Option<Frame>,
 }
 
 impl TextFragment {
-    pub fn new(text: String, rect: Rectangle, style: Style, word_spacing: f32) -> Self {
+    pub fn new(
+        text: String,
+        rect: Rectangle,
+        style: Style,
+        outer_styles: Vec<Style>,
+        attributes: Vec<(String, String)>,
+        word_spacing: f32,
+    ) -> Self {
         Self {
             text,
             rect,
             style,
+            outer_styles,
+            attributes,
             word_spacing,
+            image: None,
+            rtl: false,
+            frame: None,
+        }
+    }
+
+    /// Take the direction of text at bidi `level`.
+    fn with_level(mut self, level: Level) -> Self {
+        self.rtl = level.is_rtl();
+        self
+    }
+
+    /// A fragment showing the image at `source` in `rect`.
+    pub fn image(source: String, rect: Rectangle) -> Self {
+        Self {
+            image: Some(source),
+            ..Self::new(
+                String::new(),
+                rect,
+                Style::Normal,
+                Vec::new(),
+                Vec::new(),
+                0.0,
+            )
         }
     }
+
+    /// A fragment drawing a boxed block's frame in `rect`, in `style`.
+    pub fn frame(frame: Frame, rect: Rectangle, style: Style) -> Self {
+        Self {
+            frame: Some(frame),
+            ..Self::new(String::new(), rect, style, Vec::new(), Vec::new(), 0.0)
+        }
+    }
+}
+
+/// The items of `line` in the order they are placed from the left, each with
+/// its bidi level (UAX #9). Right-to-left pages are mirrored once laid out,
+/// so their items are placed in reverse visual order.
+fn placement_order(
+    items: &[InlineItem],
+    line: &BrokenLine,
+    direction: TextDirection,
+) -> Vec<(usize, Level)> {
+    let range = line.item_range.clone();
+    let paragraph_level = match direction {
+        TextDirection::Ltr => Level::ltr(),
+        TextDirection::Rtl => Level::rtl(),
+    };
+
+    // Only text the line actually shows takes part
+    let shown = |idx: usize| match items[idx].kind {
+        ItemKind::Fill => "",
+        ItemKind::Discretionary if idx + 1 < range.end => "",
+        _ => items[idx].text.as_str(),
+    };
+    let mut text = String::new();
+    let mut starts = Vec::with_capacity(range.len());
+    for idx in range.clone() {
+        starts.push(text.len());
+        text.push_str(shown(idx));
+    }
+
+    // Plain left-to-right text keeps its order
+    let has_rtl = text
+        .chars()
+        .any(|c| matches!(bidi_class(c), BidiClass::R | BidiClass::AL));
+    if !has_rtl && direction == TextDirection::Ltr {
+        return range.map(|idx| (idx, Level::ltr())).collect();
+    }
+    let bidi = BidiInfo::new(&text, Some(paragraph_level));
+    // A line showing no text has every item at the paragraph's level
+    let levels = match bidi.paragraphs.first() {
+        Some(paragraph) => bidi.reordered_levels(paragraph, 0..text.len()),
+        None => Vec::new(),
+    };
+
+    // Empty items go with the item before them, fills with the paragraph
+    let mut item_levels: Vec<Level> = Vec::with_capacity(range.len());
+    for (n, idx) in range.clone().enumerate() {
+        let level = match items[idx].kind {
+            ItemKind::Fill => paragraph_level,
+            _ if shown(idx).is_empty() => item_levels.last().copied().unwrap_or(paragraph_level),
+            _ => levels[starts[n]],
+        };
+        item_levels.push(level);
+    }
+
+    let mut order = BidiInfo::reorder_visual(&item_levels);
+    if direction == TextDirection::Rtl {
+        order.reverse();
+    }
+    order
+        .into_iter()
+        .map(|n| (range.start + n, item_levels[n]))
+        .collect()
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn extract_fragments(
     items: &[InlineItem],
     line: &BrokenLine,
@@ -35,20 +158,37 @@ pub fn extract_fragments(
     line_width: f32,
     is_last_line: bool,
     alignment: &Alignment,
+    direction: TextDirection,
 ) -> Vec<TextFragment> {
     if line.item_range.is_empty() {
         return vec![];
     }
 
+    let fill_count = line
+        .item_range
+        .clone()
+        .filter(|&idx| matches!(items[idx].kind, ItemKind::Fill))
+        .count();
+    let slack = line_width - line.content_width;
+
+    // Fill items take all the slack, overriding the alignment
+    let fill_width = if fill_count > 0 {
+        slack.max(0.0) / fill_count as f32
+    } else {
+        0.0
+    };
+
     let word_spacing = match alignment {
-        Alignment::Justified if !is_last_line && line.glue_count > 0 => {
-            (line_width - line.content_width) / line.glue_count as f32
+        Alignment::Justified if fill_count == 0 && !is_last_line && line.glue_count > 0 => {
+            slack / line.glue_count as f32
         }
         _ => 0.0,
     };
 
     let start_left = match alignment {
-        Alignment::Center => left_offset + (line_width - line.content_width) / 2.0,
+        _ if fill_count > 0 => left_offset,
+        Alignment::Center => left_offset + slack / 2.0,
+        Alignment::Right => left_offset + slack,
         _ => left_offset,
     };
 
@@ -56,28 +196,70 @@ pub fn extract_fragments(
     let mut left = start_left;
     let mut current_text = String::new();
     let mut current_style: Option<Style> = None;
+    let mut current_outer: Vec<Style> = Vec::new();
+    let mut current_attributes: Vec<(String, String)> = Vec::new();
     let mut current_left = left;
     let mut current_width = 0.0f32;
     let mut current_word_spacing = 0.0f32;
+    // A fragment holds one bidi level, its text in logical order
+    let mut current_level = Level::ltr();
+    let mut last_idx = line.item_range.start;
 
-    for idx in line.item_range.clone() {
+    for (idx, level) in placement_order(items, line, direction) {
         let item = &items[idx];
         let segment = &item.text;
 
-        let effective_width = if matches!(item.kind, ItemKind::Glue) {
+        // Only a discretionary the line breaks at is drawn
+        if matches!(item.kind, ItemKind::Discretionary) && idx + 1 < line.item_range.end {
+            continue;
+        }
+
+        if matches!(item.kind, ItemKind::Fill) {
+            // Close the current fragment and start the next one past the gap
+            if let Some(style) = current_style.take() {
+                fragments.push(
+                    TextFragment::new(
+                        mem::take(&mut current_text),
+                        Rectangle {
+                            top,
+                            left: current_left,
+                            width: current_width,
+                            height: line_height,
+                        },
+                        style,
+                        mem::take(&mut current_outer),
+                        mem::take(&mut current_attributes),
+                        current_word_spacing,
+                    )
+                    .with_level(current_level),
+                );
+            }
+            left += item.width + fill_width;
+            current_left = left;
+            current_width = 0.0;
+            current_word_spacing = 0.0;
+            continue;
+        }
+
+        let effective_width = if item.stretches() {
             item.width + word_spacing
         } else {
             item.width
         };
 
-        let item_word_spacing = if matches!(item.kind, ItemKind::Glue) {
-            word_spacing
-        } else {
-            0.0
-        };
+        let item_word_spacing = if item.stretches() { word_spacing } else { 0.0 };
 
-        if current_style == Some(item.style) {
-            current_text.push_str(segment);
+        if current_style == Some(item.style)
+            && current_outer == item.outer_styles
+            && current_attributes == item.attributes
+            && current_level == level
+        {
+            // Runs placed against their logical order build their text backwards
+            if idx < last_idx {
+                current_text.insert_str(0, segment);
+            } else {
+                current_text.push_str(segment);
+            }
             current_width += effective_width;
             if item_word_spacing > current_word_spacing {
                 current_word_spacing = item_word_spacing;
@@ -85,47 +267,63 @@ pub fn extract_fragments(
             left += effective_width;
         } else {
             if let Some(style) = current_style {
-                fragments.push(TextFragment::new(
-                    mem::take(&mut current_text),
-                    Rectangle {
-                        top,
-                        left: current_left,
-                        width: current_width,
-                        height: line_height,
-                    },
-                    style,
-                    current_word_spacing,
-                ));
+                fragments.push(
+                    TextFragment::new(
+                        mem::take(&mut current_text),
+                        Rectangle {
+                            top,
+                            left: current_left,
+                            width: current_width,
+                            height: line_height,
+                        },
+                        style,
+                        mem::take(&mut current_outer),
+                        mem::take(&mut current_attributes),
+                        current_word_spacing,
+                    )
+                    .with_level(current_level),
+                );
             }
             left += effective_width;
-            if matches!(item.kind, ItemKind::Glue) {
+            if item.is_glue() {
                 current_text = String::new();
                 current_style = Some(item.style);
+                current_outer = item.outer_styles.clone();
+                current_attributes = item.attributes.clone();
                 current_left = left;
                 current_width = 0.0;
                 current_word_spacing = item_word_spacing;
             } else {
                 current_text = segment.to_string();
                 current_style = Some(item.style);
+                current_outer = item.outer_styles.clone();
+                current_attributes = item.attributes.clone();
                 current_left = left - effective_width;
                 current_width = effective_width;
                 current_word_spacing = item_word_spacing;
             }
+            current_level = level;
         }
+        last_idx = idx;
     }
 
     if let Some(style) = current_style {
-        fragments.push(TextFragment::new(
-            current_text,
-            Rectangle {
-                top,
-                left: current_left,
-                width: current_width,
-                height: line_height,
-            },
-            style,
-            current_word_spacing,
-        ));
+        fragments.push(
+            TextFragment::new(
+                current_text,
+                Rectangle {
+                    top,
+                    left: current_left,
+                    width: current_width,
+                    height: line_height,
+                },
+                style,
+                current_outer,
+                current_attributes,
+                current_word_spacing,
+            )
+            .with_level(current_level),
+        );
     }
 
     fragments
@@ -147,3 +345,91 @@ pub fn usize_to_letters(mut i: usize) -> String {
 
     s.chars().rev().collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::painter::layout::Section;
+
+    fn item(kind: ItemKind, text: &str) -> InlineItem {
+        InlineItem {
+            text: text.to_string(),
+            style: Style::Normal,
+            outer_styles: Vec::new(),
+            attributes: Vec::new(),
+            width: 10.0,
+            kind,
+            section: Section::Body,
+            index_id: None,
+        }
+    }
+
+    fn line(items: &[InlineItem]) -> BrokenLine {
+        BrokenLine {
+            item_range: 0..items.len(),
+            content_width: 10.0 * items.len() as f32,
+            glue_count: 0,
+        }
+    }
+
+    #[test]
+    fn places_items_in_visual_order() {
+        use ItemKind::*;
+        let (ltr, rtl) = (Level::ltr(), Level::rtl());
+        let mixed = [
+            item(Word, "abc"),
+            item(Glue, " "),
+            item(Word, "אב"),
+            item(Glue, " "),
+            item(Word, "גד"),
+        ];
+        let cases = [
+            (&mixed[..1], TextDirection::Ltr, vec![(0, ltr)]),
+            (
+                &mixed[..],
+                TextDirection::Ltr,
+                vec![(0, ltr), (1, ltr), (4, rtl), (3, rtl), (2, rtl)],
+            ),
+            (
+                &mixed[2..],
+                TextDirection::Rtl,
+                vec![(0, rtl), (1, rtl), (2, rtl)],
+            ),
+            // Nothing shown, yet still a right-to-left line
+            (
+                &[item(Word, ""), item(Fill, "")][..],
+                TextDirection::Rtl,
+                vec![(0, rtl), (1, rtl)],
+            ),
+        ];
+        for (items, direction, expected) in cases {
+            let order = placement_order(items, &line(items), direction);
+            assert_eq!(order, expected, "{items:?} {direction:?}");
+        }
+    }
+
+    #[test]
+    fn marks_right_to_left_fragments() {
+        use ItemKind::*;
+        let items = [
+            item(Word, "abc"),
+            item(Glue, " "),
+            item(Word, "אב"),
+            item(Glue, " "),
+            item(Word, "גד"),
+        ];
+        let fragments = extract_fragments(
+            &items,
+            &line(&items),
+            0.0,
+            10.0,
+            0.0,
+            100.0,
+            true,
+            &Alignment::Left,
+            TextDirection::Ltr,
+        );
+        let texts: Vec<_> = fragments.iter().map(|f| (f.text.as_str(), f.rtl)).collect();
+        assert_eq!(texts, vec![("abc ", false), ("אב גד", true)]);
+    }
+}
@@ -8,18 +8,43 @@ use super::Section;
 pub enum ItemKind {
     Word,
     Glue,
+    /// A break opportunity between letters, where there is no space to break
+    /// at (UAX #14). Empty, and never stretches when the line is justified.
+    LetterBreak,
+    /// Takes up whatever width is left on its line, pushing the rest of the
+    /// line flush right. Not a break opportunity.
+    Fill,
+    /// A break opportunity inside a word. Takes no room unless the line
+    /// breaks there, when its text (a hyphen) ends the line.
+    Discretionary,
 }
 
 #[derive(Debug, Clone)]
 pub struct InlineItem {
     pub text: String,
     pub style: Style,
+    /// Styles `style` is nested in, outermost first.
+    pub outer_styles: Vec<Style>,
+    /// `\w` attributes (lemma, strong, x-morph...), empty outside of words.
+    pub attributes: Vec<(String, String)>,
     pub width: f32,
     pub kind: ItemKind,
     pub section: Section,
     pub index_id: Option<usize>,
 }
 
+impl InlineItem {
+    /// Whether justification may widen this item: glue holding a space.
+    pub fn stretches(&self) -> bool {
+        matches!(self.kind, ItemKind::Glue)
+    }
+
+    /// Whether the line may break here, leaving this item out.
+    pub fn is_glue(&self) -> bool {
+        matches!(self.kind, ItemKind::Glue | ItemKind::LetterBreak)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct BrokenLine {
     pub item_range: ops::Range<usize>,
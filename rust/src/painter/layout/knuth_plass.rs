@@ -0,0 +1,319 @@
+//! Total-fit line breaking after Knuth and Plass: rather than filling each
+//! line in turn, every way of breaking the paragraph is weighed at once and
+//! the one with the least demerits wins, so that no line is left much looser
+//! than its neighbours when justified.
+
+use std::collections::HashMap;
+
+use super::inline::ItemKind;
+
+/// Added to every line's badness, so that fewer lines are preferred.
+const LINE_PENALTY: f32 = 10.0;
+/// Badness of a line that cannot stretch to its width.
+const MAX_BADNESS: f32 = 10_000.0;
+/// Penalty for breaking at a discretionary, so that hyphens are a last resort.
+const HYPHEN_PENALTY: f32 = 50.0;
+/// Demerits of a line wider than its width, taken only when nothing fits.
+const OVERFULL_DEMERITS: f32 = 1.0e12;
+
+/// One item of the paragraph as the breaker sees it.
+#[derive(Debug, Clone, Copy)]
+pub struct BreakItem {
+    pub width: f32,
+    pub kind: ItemKind,
+    /// Whether the line may break here; only glue and discretionaries
+    /// outside groups can.
+    pub breakable: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    demerits: f32,
+    /// The break before this one and its line class.
+    previous: Option<(usize, usize)>,
+}
+
+/// Running totals up to each item, so a line's widths are two lookups away.
+struct Totals {
+    width: Vec<f32>,
+    glue: Vec<f32>,
+    fills: Vec<usize>,
+}
+
+impl Totals {
+    fn new(items: &[BreakItem]) -> Self {
+        let mut totals = Self {
+            width: vec![0.0],
+            glue: vec![0.0],
+            fills: vec![0],
+        };
+        for item in items {
+            let (width, glue, fill) = match item.kind {
+                ItemKind::Glue => (item.width, item.width, 0),
+                ItemKind::LetterBreak => (0.0, 0.0, 0),
+                ItemKind::Fill => (item.width, 0.0, 1),
+                ItemKind::Word => (item.width, 0.0, 0),
+                // Only takes room where the line breaks
+                ItemKind::Discretionary => (0.0, 0.0, 0),
+            };
+            totals.width.push(totals.width.last().unwrap() + width);
+            totals.glue.push(totals.glue.last().unwrap() + glue);
+            totals.fills.push(totals.fills.last().unwrap() + fill);
+        }
+        totals
+    }
+}
+
+/// Choose where to break `items`, returning the indices of the glue and
+/// discretionary items to break at. `width_fn` gives each line's (left offset, width) as for
+/// `LineBreaker`; from line `distinct_lines` on, every line must be as wide.
+/// The last line is left loose, as are lines holding a fill.
+pub fn optimal_breaks(
+    items: &[BreakItem],
+    width_fn: &dyn Fn(usize) -> (f32, f32),
+    distinct_lines: usize,
+) -> Vec<usize> {
+    let end = items.len();
+    let totals = Totals::new(items);
+    let skip_glue = |mut i: usize| {
+        while i < end && matches!(items[i].kind, ItemKind::Glue | ItemKind::LetterBreak) {
+            i += 1;
+        }
+        i
+    };
+
+    // Candidate breaks, in order, ending with the end of the paragraph
+    let mut candidates: Vec<usize> = (0..end).filter(|&i| items[i].breakable).collect();
+    candidates.push(end);
+
+    // Best way of reaching each break, keyed by (break, line class). Breaks
+    // are positions in `candidates`; usize::MAX is the paragraph start.
+    let start = usize::MAX;
+    let mut nodes: HashMap<(usize, usize), Node> = HashMap::new();
+    nodes.insert(
+        (start, 0),
+        Node {
+            demerits: 0.0,
+            previous: None,
+        },
+    );
+
+    let mut from_breaks = vec![start];
+    from_breaks.extend(0..candidates.len());
+    for from in from_breaks {
+        let line_start = match from {
+            usize::MAX => skip_glue(0),
+            _ if candidates[from] == end => continue,
+            _ => skip_glue(candidates[from] + 1),
+        };
+        for class in 0..=distinct_lines {
+            let Some(node) = nodes.get(&(from, class)).copied() else {
+                continue;
+            };
+            let (_, line_width) = width_fn(class);
+            let next_class = (class + 1).min(distinct_lines);
+            let first = if from == start { 0 } else { from + 1 };
+
+            let mut tried = false;
+            for (to, &position) in candidates.iter().enumerate().skip(first) {
+                // The last line drops its trailing glue
+                let mut line_end = position;
+                if position == end {
+                    while line_end > line_start
+                        && matches!(
+                            items[line_end - 1].kind,
+                            ItemKind::Glue | ItemKind::LetterBreak
+                        )
+                    {
+                        line_end -= 1;
+                    }
+                }
+                if line_end <= line_start {
+                    continue;
+                }
+
+                let hyphenated =
+                    position < end && matches!(items[position].kind, ItemKind::Discretionary);
+                let mut natural = totals.width[line_end] - totals.width[line_start];
+                if hyphenated {
+                    natural += items[position].width;
+                }
+                let overfull = natural > line_width;
+                // A line that cannot fit is only taken when it is the first
+                // one tried, so that an over-long word cannot strand the paragraph
+                if overfull && tried {
+                    break;
+                }
+                tried = true;
+
+                let loose = position == end || totals.fills[line_end] > totals.fills[line_start];
+                let demerits = if overfull {
+                    OVERFULL_DEMERITS
+                } else {
+                    let stretch = totals.glue[line_end] - totals.glue[line_start];
+                    let slack = line_width - natural;
+                    let badness = if loose {
+                        0.0
+                    } else if stretch > 0.0 {
+                        (100.0 * (slack / stretch).powi(3)).min(MAX_BADNESS)
+                    } else if slack > 0.0 {
+                        MAX_BADNESS
+                    } else {
+                        0.0
+                    };
+                    let penalty = if hyphenated { HYPHEN_PENALTY } else { 0.0 };
+                    (LINE_PENALTY + badness).powi(2) + penalty.powi(2)
+                };
+
+                let total = node.demerits + demerits;
+                let better = nodes
+                    .get(&(to, next_class))
+                    .is_none_or(|existing| total < existing.demerits);
+                if better {
+                    nodes.insert(
+                        (to, next_class),
+                        Node {
+                            demerits: total,
+                            previous: Some((from, class)),
+                        },
+                    );
+                }
+                if overfull {
+                    break;
+                }
+            }
+        }
+    }
+
+    // Walk back from the cheapest way of reaching the end
+    let last = candidates.len() - 1;
+    let Some(mut key) = (0..=distinct_lines)
+        .filter(|&class| nodes.contains_key(&(last, class)))
+        .min_by(|a, b| {
+            nodes[&(last, *a)]
+                .demerits
+                .total_cmp(&nodes[&(last, *b)].demerits)
+        })
+        .map(|class| (last, class))
+    else {
+        return Vec::new();
+    };
+    let mut breaks = Vec::new();
+    while let Some(previous) = nodes[&key].previous {
+        if key.0 != last {
+            breaks.push(candidates[key.0]);
+        }
+        if previous.0 == start {
+            break;
+        }
+        key = previous;
+    }
+    breaks.reverse();
+    breaks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::painter::Style;
+    use crate::painter::layout::Section;
+    use crate::painter::layout::inline::InlineItem;
+    use crate::painter::layout::line_breaker::LineBreaker;
+
+    const GLUE: f32 = 5.0;
+
+    fn item(kind: ItemKind, width: f32) -> BreakItem {
+        BreakItem {
+            width,
+            kind,
+            breakable: matches!(kind, ItemKind::Glue | ItemKind::Discretionary),
+        }
+    }
+
+    /// Words of the given widths with glue between them.
+    fn words(widths: &[f32]) -> Vec<BreakItem> {
+        let mut items = Vec::new();
+        for (i, &width) in widths.iter().enumerate() {
+            if i > 0 {
+                items.push(item(ItemKind::Glue, GLUE));
+            }
+            items.push(item(ItemKind::Word, width));
+        }
+        items
+    }
+
+    fn breaks(items: &[BreakItem], width: f32) -> Vec<usize> {
+        optimal_breaks(items, &|_| (0.0, width), 0)
+    }
+
+    #[test]
+    fn sets_an_over_long_word_on_its_own_line() {
+        let items = words(&[50.0, 200.0, 30.0]);
+        assert_eq!(breaks(&items, 100.0), vec![1, 3]);
+    }
+
+    #[test]
+    fn leaves_a_line_with_a_fill_loose() {
+        // 40 X | 45 45 | 40 40, where X is empty. As a word it leaves the
+        // first line short with nothing to stretch, so the lines are evened
+        // out instead; as a fill, that line may stay short.
+        let paragraph = |x: ItemKind| {
+            let mut items = vec![item(ItemKind::Word, 40.0), item(x, 0.0)];
+            items.push(item(ItemKind::Glue, 10.0));
+            for width in [45.0, 45.0, 40.0, 40.0] {
+                items.push(item(ItemKind::Word, width));
+                items.push(item(ItemKind::Glue, 10.0));
+            }
+            items.pop();
+            items
+        };
+        assert_eq!(breaks(&paragraph(ItemKind::Word), 100.0), vec![4, 8]);
+        assert_eq!(breaks(&paragraph(ItemKind::Fill), 100.0), vec![2, 6]);
+    }
+
+    #[test]
+    fn narrows_the_lines_beside_a_drop_cap() {
+        let items = words(&[20.0; 6]);
+        let width_fn = |line: usize| if line < 2 { (50.0, 50.0) } else { (0.0, 100.0) };
+        assert_eq!(optimal_breaks(&items, &width_fn, 2), vec![3, 7]);
+        assert_eq!(breaks(&items, 100.0), vec![7]);
+    }
+
+    #[test]
+    fn agrees_with_greedy_on_short_paragraphs() {
+        let cases: [&[f32]; 5] = [
+            &[30.0, 30.0, 30.0],
+            &[30.0, 30.0, 30.0, 10.0],
+            &[60.0, 30.0, 20.0, 40.0],
+            &[45.0, 45.0, 45.0, 45.0],
+            &[90.0, 20.0],
+        ];
+        for widths in cases {
+            let items = words(widths);
+            let inline: Vec<InlineItem> = items
+                .iter()
+                .map(|item| InlineItem {
+                    text: if matches!(item.kind, ItemKind::Glue) {
+                        " "
+                    } else {
+                        "w"
+                    }
+                    .to_string(),
+                    style: Style::Normal,
+                    outer_styles: Vec::new(),
+                    attributes: Vec::new(),
+                    width: item.width,
+                    kind: item.kind,
+                    section: Section::Body,
+                    index_id: None,
+                })
+                .collect();
+            let lines: Vec<_> = LineBreaker::new(&inline, Box::new(|_| (0.0, 100.0))).collect();
+            let greedy: Vec<usize> = lines[..lines.len() - 1]
+                .iter()
+                .map(|line| line.item_range.end)
+                .collect();
+            assert_eq!(breaks(&items, 100.0), greedy, "{widths:?}");
+        }
+    }
+}
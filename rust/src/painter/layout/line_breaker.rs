@@ -2,11 +2,35 @@ use super::inline::{BrokenLine, InlineItem, ItemKind};
 
 pub type WidthFn<'a> = Box<dyn Fn(usize) -> (f32, f32) + 'a>;
 
+/// How paragraphs are broken into lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub enum LineBreaking {
+    /// Fill each line as far as it goes.
+    Greedy = 0,
+    /// Choose the breaks of a justified paragraph all together, keeping its
+    /// spacing as even as possible (see `knuth_plass`).
+    Optimal = 1,
+}
+
+impl TryFrom<u32> for LineBreaking {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, u32> {
+        match value {
+            0 => Ok(Self::Greedy),
+            1 => Ok(Self::Optimal),
+            _ => Err(value),
+        }
+    }
+}
+
 pub struct LineBreaker<'a> {
     items: &'a [InlineItem],
     cursor: usize,
     line_index: usize,
     width_fn: WidthFn<'a>,
+    breaks: &'a [usize],
 }
 
 impl<'a> LineBreaker<'a> {
@@ -16,9 +40,17 @@ impl<'a> LineBreaker<'a> {
             cursor: 0,
             line_index: 0,
             width_fn,
+            breaks: &[],
         }
     }
 
+    /// Always break at these glue or discretionary items, as planned by the
+    /// optimal breaker. They must be in order.
+    pub fn with_breaks(mut self, breaks: &'a [usize]) -> Self {
+        self.breaks = breaks;
+        self
+    }
+
     pub fn cursor(&self) -> usize {
         self.cursor
     }
@@ -35,9 +67,7 @@ impl<'a> Iterator for LineBreaker<'a> {
         let (_left_offset, max_width) = (self.width_fn)(self.line_index);
 
         // Skip leading glue on all lines
-        while self.cursor < self.items.len()
-            && matches!(self.items[self.cursor].kind, ItemKind::Glue)
-        {
+        while self.cursor < self.items.len() && self.items[self.cursor].is_glue() {
             self.cursor += 1;
         }
 
@@ -47,22 +77,58 @@ impl<'a> Iterator for LineBreaker<'a> {
 
         let start = self.cursor;
         let mut width = 0.0f32;
-        let mut last_break: Option<(usize, f32, u32)> = None;
+        // Where the line would end: the end of its items, where the next line
+        // resumes, the width taken and the glue count. A glue break leaves the
+        // glue out; a discretionary one keeps its hyphen.
+        let mut last_break: Option<(usize, usize, f32, u32)> = None;
         let mut glue_count = 0u32;
 
         while self.cursor < self.items.len() {
             let item = &self.items[self.cursor];
             match item.kind {
-                ItemKind::Glue => {
-                    last_break = Some((self.cursor, width, glue_count));
-                    glue_count += 1;
+                ItemKind::Glue | ItemKind::LetterBreak
+                    if self.cursor > start && self.breaks.binary_search(&self.cursor).is_ok() =>
+                {
+                    let brk = self.cursor;
+                    self.cursor = brk + 1;
+                    self.line_index += 1;
+                    return Some(BrokenLine {
+                        item_range: start..brk,
+                        content_width: width,
+                        glue_count,
+                    });
+                }
+                ItemKind::Discretionary
+                    if self.cursor > start && self.breaks.binary_search(&self.cursor).is_ok() =>
+                {
+                    self.cursor += 1;
+                    self.line_index += 1;
+                    return Some(BrokenLine {
+                        item_range: start..self.cursor,
+                        content_width: width + item.width,
+                        glue_count,
+                    });
+                }
+                ItemKind::Glue | ItemKind::LetterBreak => {
+                    last_break = Some((self.cursor, self.cursor + 1, width, glue_count));
+                    if item.stretches() {
+                        glue_count += 1;
+                    }
                     width += item.width;
                     self.cursor += 1;
                 }
-                ItemKind::Word => {
+                ItemKind::Discretionary => {
+                    // Only a break if the hyphen fits
+                    if self.cursor > start && width + item.width <= max_width {
+                        let end = self.cursor + 1;
+                        last_break = Some((end, end, width + item.width, glue_count));
+                    }
+                    self.cursor += 1;
+                }
+                ItemKind::Word | ItemKind::Fill => {
                     if width + item.width > max_width && self.cursor > start {
-                        if let Some((brk, w, gc)) = last_break {
-                            self.cursor = brk + 1;
+                        if let Some((brk, resume, w, gc)) = last_break {
+                            self.cursor = resume;
                             self.line_index += 1;
                             return Some(BrokenLine {
                                 item_range: start..brk,
@@ -89,10 +155,16 @@ impl<'a> Iterator for LineBreaker<'a> {
         let mut end = self.cursor;
         let mut trimmed_width = width;
         let mut trimmed_glue = glue_count;
-        while end > start && matches!(self.items[end - 1].kind, ItemKind::Glue) {
+        while end > start && self.items[end - 1].is_glue() {
             end -= 1;
             trimmed_width -= self.items[end].width;
-            trimmed_glue -= 1;
+            if self.items[end].stretches() {
+                trimmed_glue -= 1;
+            }
+        }
+        // A line ending in a discretionary breaks there, so it shows its hyphen
+        if end > start && matches!(self.items[end - 1].kind, ItemKind::Discretionary) {
+            trimmed_width += self.items[end - 1].width;
         }
 
         self.line_index += 1;
@@ -103,3 +175,57 @@ impl<'a> Iterator for LineBreaker<'a> {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::painter::Style;
+    use crate::painter::layout::Section;
+
+    fn item(kind: ItemKind, width: f32) -> InlineItem {
+        let text = match kind {
+            ItemKind::Glue => " ",
+            ItemKind::Word => "w",
+            _ => "",
+        };
+        InlineItem {
+            text: text.to_string(),
+            style: Style::Normal,
+            outer_styles: Vec::new(),
+            attributes: Vec::new(),
+            width,
+            kind,
+            section: Section::Body,
+            index_id: None,
+        }
+    }
+
+    fn lines(items: &[InlineItem], width: f32) -> Vec<(std::ops::Range<usize>, u32)> {
+        LineBreaker::new(items, Box::new(move |_| (0.0, width)))
+            .map(|line| (line.item_range, line.glue_count))
+            .collect()
+    }
+
+    #[test]
+    fn breaks_between_letters_without_stretching_there() {
+        use ItemKind::*;
+        let items = [
+            item(Word, 30.0),
+            item(LetterBreak, 0.0),
+            item(Word, 30.0),
+            item(Glue, 5.0),
+            item(Word, 30.0),
+        ];
+        assert_eq!(lines(&items, 100.0), vec![(0..5, 1)]);
+        assert_eq!(lines(&items, 70.0), vec![(0..3, 0), (4..5, 0)]);
+        assert_eq!(lines(&items, 40.0), vec![(0..1, 0), (2..3, 0), (4..5, 0)]);
+    }
+
+    #[test]
+    fn reads_raw_line_breaking() {
+        for breaking in [LineBreaking::Greedy, LineBreaking::Optimal] {
+            assert_eq!(LineBreaking::try_from(breaking as u32), Ok(breaking));
+        }
+        assert_eq!(LineBreaking::try_from(2), Err(2));
+    }
+}
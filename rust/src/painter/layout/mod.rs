@@ -2,18 +2,24 @@
 pub mod artefact;
 #[allow(dead_code)]
 pub mod container;
+#[allow(dead_code)]
+pub mod figure;
 pub mod fragment;
 #[allow(dead_code)]
 pub mod inline;
+pub mod knuth_plass;
 pub mod line_breaker;
 #[allow(dead_code)]
 pub mod scaffold;
 #[allow(dead_code)]
 pub mod state;
 #[allow(dead_code)]
+pub mod table;
+#[allow(dead_code)]
 pub mod template;
 
 use std::collections::HashMap;
+use std::fmt;
 
 use rkyv::{Archive, Deserialize, Serialize, vec::ArchivedVec};
 use usfm::BookIdentifier;
@@ -32,16 +38,110 @@ pub enum Section {
 pub enum Alignment {
     Left,
     Center,
+    Right,
     Justified,
 }
 
+/// The direction a translation's paragraphs run in. Right-to-left pages are
+/// laid out as if left-to-right and mirrored when finalized, so that indents,
+/// drop caps, alignment and footnotes all start from the right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub enum TextDirection {
+    Ltr = 0,
+    Rtl = 1,
+}
+
+impl TryFrom<u32> for TextDirection {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, u32> {
+        match value {
+            0 => Ok(Self::Ltr),
+            1 => Ok(Self::Rtl),
+            _ => Err(value),
+        }
+    }
+}
+
+/// A verse number as written: a single verse, a bridge such as `3-4`, or a
+/// segment such as `12a`.
+#[derive(
+    Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Serialize, Archive, Deserialize,
+)]
+#[rkyv(derive(Debug, PartialEq, Eq, Hash))]
+pub struct Verse {
+    pub start: u16,
+    pub end: u16,
+    pub segment: Option<char>,
+}
+
+impl Verse {
+    pub fn new(start: u16, end: u16, segment: Option<char>) -> Self {
+        Self {
+            start,
+            end: end.max(start),
+            segment,
+        }
+    }
+
+    pub fn contains(&self, verse: u16) -> bool {
+        (self.start..=self.end).contains(&verse)
+    }
+
+    /// Read a published verse number such as `3`, `3-4` or `12a`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (range, segment) = match text.chars().last() {
+            Some(c) if c.is_alphabetic() => (&text[..text.len() - c.len_utf8()], Some(c)),
+            _ => (text, None),
+        };
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+            None => {
+                let verse = range.parse().ok()?;
+                (verse, verse)
+            }
+        };
+        (start <= end).then(|| Self::new(start, end, segment))
+    }
+}
+
+impl From<u16> for Verse {
+    fn from(verse: u16) -> Self {
+        Self::new(verse, verse, None)
+    }
+}
+
+impl fmt::Display for Verse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.start)?;
+        if self.end > self.start {
+            write!(f, "-{}", self.end)?;
+        }
+        if let Some(segment) = self.segment {
+            write!(f, "{}", segment)?;
+        }
+        Ok(())
+    }
+}
+
+impl ArchivedVerse {
+    pub fn contains(&self, verse: u16) -> bool {
+        (self.start.to_native()..=self.end.to_native()).contains(&verse)
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Archive, Deserialize)]
 #[rkyv(derive(Debug, PartialEq, Eq, Hash))]
 pub struct Index {
     pub book: BookIdentifier,
+    /// The book's `\h` header.
     pub header: String,
     pub chapter: Option<u16>,
-    pub verse: Option<u16>,
+    pub verse: Option<Verse>,
+    /// Text of the section heading this index marks, if any.
+    pub heading: Option<String>,
 }
 
 impl Index {
@@ -49,15 +149,106 @@ impl Index {
         book: BookIdentifier,
         header: String,
         chapter: Option<u16>,
-        verse: Option<u16>,
+        verse: Option<Verse>,
     ) -> Self {
         Self {
             book,
             header,
             chapter,
             verse,
+            heading: None,
         }
     }
+
+    pub fn with_heading(mut self, heading: String) -> Self {
+        self.heading = Some(heading);
+        self
+    }
+}
+
+/// Layout of the archived [`Index`], bumped whenever it changes so that
+/// indices cached on disk are rebuilt rather than misread.
+///
+/// 2: a verse is a [`Verse`] bridge or segment instead of a single number.
+/// 3: book headers are trimmed.
+/// 4: section headings are indexed by their own `heading`, not the header.
+pub const INDEX_VERSION: u32 = 4;
+
+const VERSE_INDEX_MAGIC: [u8; 4] = *b"SIDX";
+
+/// The verses of a book, as archived for the search index, tagged with the
+/// [`INDEX_VERSION`] they were written in.
+#[derive(Debug, Serialize, Archive, Deserialize)]
+pub struct VerseIndex {
+    magic: [u8; 4],
+    pub version: u32,
+    pub verses: Vec<Index>,
+}
+
+impl VerseIndex {
+    pub fn new(verses: Vec<Index>) -> Self {
+        Self {
+            magic: VERSE_INDEX_MAGIC,
+            version: INDEX_VERSION,
+            verses,
+        }
+    }
+}
+
+impl ArchivedVerseIndex {
+    /// Whether the bytes are a tagged verse index at all, rather than an
+    /// untagged list from before versioning.
+    pub fn is_tagged(&self) -> bool {
+        self.magic == VERSE_INDEX_MAGIC
+    }
+}
+
+/// An [`Index`] as archived before versioning, when a verse was a single
+/// number. Search indices built then still hold a bare list of these.
+#[derive(Debug, Serialize, Archive, Deserialize)]
+pub struct IndexV1 {
+    pub book: BookIdentifier,
+    pub header: String,
+    pub chapter: Option<u16>,
+    pub verse: Option<u16>,
+}
+
+impl From<IndexV1> for Index {
+    fn from(index: IndexV1) -> Self {
+        Index::new(
+            index.book,
+            index.header,
+            index.chapter,
+            index.verse.map(Verse::from),
+        )
+    }
+}
+
+/// The first page showing a verse, including a verse inside a bridge such
+/// as `3-4`. Segments of the same verse may fall on different pages.
+pub fn verse_page(
+    page_map: &ArchivedIndices,
+    book: &str,
+    chapter: u16,
+    verse: u16,
+) -> Option<usize> {
+    page_map
+        .iter()
+        .filter(|(index, _)| {
+            index.book.to_identifier() == book
+                && index.chapter.as_ref().map(|c| c.to_native()) == Some(chapter)
+                && index.verse.as_ref().is_some_and(|v| v.contains(verse))
+        })
+        .filter_map(|(_, page)| page.to_native().try_into().ok())
+        .min()
+}
+
+/// A span between two indices, both inclusive, such as a cross-reference target.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Archive, Deserialize)]
+#[rkyv(derive(Debug, PartialEq, Eq, Hash))]
+pub struct IndexRange {
+    pub start: Index,
+    pub end: Index,
 }
 
 #[allow(dead_code)]
@@ -68,3 +259,53 @@ pub type Page = Vec<TextFragment>;
 #[allow(dead_code)]
 pub type ArchivedIndices = <Indices as Archive>::Archived;
 pub type Indices = HashMap<Index, usize>;
+/// Targets of each linked cross-reference, by link id.
+pub type CrossRefs = Vec<Vec<IndexRange>>;
+#[allow(dead_code)]
+pub type ArchivedCrossRefs = <CrossRefs as Archive>::Archived;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_raw_text_direction() {
+        for direction in [TextDirection::Ltr, TextDirection::Rtl] {
+            assert_eq!(TextDirection::try_from(direction as u32), Ok(direction));
+        }
+        assert_eq!(TextDirection::try_from(2), Err(2));
+    }
+
+    #[test]
+    fn parses_published_verses() {
+        let cases = [
+            ("3", Some(Verse::new(3, 3, None))),
+            ("3-4", Some(Verse::new(3, 4, None))),
+            ("12a", Some(Verse::new(12, 12, Some('a')))),
+            ("3-4b", Some(Verse::new(3, 4, Some('b')))),
+            (" 7 ", Some(Verse::new(7, 7, None))),
+            ("4-3", None),
+            ("a", None),
+            ("", None),
+            ("3-", None),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(Verse::parse(text), expected, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn tags_verse_indices() {
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&VerseIndex::new(Vec::new())).unwrap();
+        let archived = rkyv::access::<ArchivedVerseIndex, rkyv::rancor::Error>(&bytes).unwrap();
+        assert!(archived.is_tagged());
+        assert_eq!(archived.version, INDEX_VERSION);
+    }
+
+    #[test]
+    fn displays_as_parsed() {
+        for text in ["3", "3-4", "12a", "3-4b"] {
+            assert_eq!(Verse::parse(text).unwrap().to_string(), text);
+        }
+    }
+}
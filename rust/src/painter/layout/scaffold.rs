@@ -1,10 +1,12 @@
+use crate::painter::{Rectangle, Style};
+
 use super::artefact::ArtefactAnchor;
 use super::container::StackDirection;
-use super::fragment::{TextFragment, extract_fragments};
+use super::fragment::{Frame, TextFragment, extract_fragments};
 use super::inline::BrokenLine;
 use super::line_breaker::LineBreaker;
 use super::template::{ContainerFill, Template};
-use super::{Index, Indices};
+use super::{Index, Indices, TextDirection};
 
 #[allow(dead_code)]
 pub struct Scaffold {
@@ -13,16 +15,18 @@ pub struct Scaffold {
     pub top_cursor: f32,
     pub bottom_cursor: f32,
     pub templates: Vec<Template>,
+    pub direction: TextDirection,
 }
 
 impl Scaffold {
-    pub fn new(width: f32, height: f32) -> Self {
+    pub fn new(width: f32, height: f32, direction: TextDirection) -> Self {
         Self {
             width,
             height,
             top_cursor: 0.0,
             bottom_cursor: height,
             templates: Vec::new(),
+            direction,
         }
     }
 
@@ -54,6 +58,23 @@ impl Scaffold {
         Ok(())
     }
 
+    /// Take back the last template pushed, giving its space back.
+    pub fn pop(&mut self) -> Option<Template> {
+        let template = self.templates.pop()?;
+        for fill in template.containers.values() {
+            let container_height = fill.total_height();
+            match fill.direction {
+                StackDirection::TopDown => {
+                    self.top_cursor -= container_height;
+                }
+                StackDirection::BottomUp => {
+                    self.bottom_cursor += container_height;
+                }
+            }
+        }
+        Some(template)
+    }
+
     /// Finalize scaffold into a Page, recording indices.
     pub fn finalize(
         &self,
@@ -62,12 +83,31 @@ impl Scaffold {
         indices: &mut Indices,
     ) -> Vec<TextFragment> {
         let mut all_fragments = Vec::new();
+        // Boxed blocks on this page: (box id, top, bottom, frame)
+        let mut frames: Vec<(usize, f32, f32, Frame)> = Vec::new();
 
         // Pass 1: TopDown containers (body text, headers, etc.)
         let mut y_top = 0.0f32;
         for template in &self.templates {
             for (_, fill) in template.containers.iter().filter(|(_, f)| f.direction == StackDirection::TopDown) {
                 let h = fill.total_height();
+                if let Some(mark) = fill.boxed {
+                    match frames.last_mut() {
+                        Some((id, _, bottom, frame)) if *id == mark.id => {
+                            *bottom = y_top + h;
+                            frame.continues = !mark.closes;
+                        }
+                        _ => frames.push((
+                            mark.id,
+                            y_top,
+                            y_top + h,
+                            Frame {
+                                continued: !mark.opens,
+                                continues: !mark.closes,
+                            },
+                        )),
+                    }
+                }
                 let frags = self.extract_container(
                     fill, y_top, index_registry, page_index, indices,
                 );
@@ -75,7 +115,7 @@ impl Scaffold {
                 for artefact in &fill.artefacts {
                     for frag in &artefact.fragments {
                         let mut placed = frag.clone();
-                        placed.rect.top += y_top + artefact.padding.top;
+                        placed.rect.top += y_top + fill.spacing.0 + artefact.padding.top;
                         all_fragments.push(placed);
                     }
                 }
@@ -96,7 +136,7 @@ impl Scaffold {
                 for artefact in &fill.artefacts {
                     for frag in &artefact.fragments {
                         let mut placed = frag.clone();
-                        placed.rect.top += y_footer + artefact.padding.top;
+                        placed.rect.top += y_footer + fill.spacing.0 + artefact.padding.top;
                         all_fragments.push(placed);
                     }
                 }
@@ -105,7 +145,25 @@ impl Scaffold {
             }
         }
 
-        all_fragments
+        // Frames go first so that they are drawn behind the text
+        let frames = frames.into_iter().map(|(_, top, bottom, frame)| {
+            let rect = Rectangle {
+                top,
+                left: 0.0,
+                width: self.width,
+                height: bottom - top,
+            };
+            TextFragment::frame(frame, rect, Style::Sidebar)
+        });
+        let mut page: Vec<TextFragment> = frames.chain(all_fragments).collect();
+
+        // Right-to-left pages were laid out from the left; mirror them
+        if self.direction == TextDirection::Rtl {
+            for fragment in page.iter_mut() {
+                fragment.rect.left = self.width - fragment.rect.left - fragment.rect.width;
+            }
+        }
+        page
     }
 
     fn extract_container(
@@ -143,17 +201,14 @@ impl Scaffold {
             (left_offset, available_width - left_offset - right_artefact)
         });
 
-        let mut breaker = LineBreaker::new(&fill.items, width_fn);
-        let mut lines: Vec<BrokenLine> = Vec::new();
-        while let Some(bl) = breaker.next() {
-            lines.push(bl);
-        }
+        let breaker = LineBreaker::new(&fill.items, width_fn).with_breaks(&fill.breaks);
+        let lines: Vec<BrokenLine> = breaker.collect();
 
         let num_lines = lines.len();
         for (line_idx, broken_line) in lines.iter().enumerate() {
             // Only the last line of the paragraph (not just this template) skips justification
             let is_last = line_idx == num_lines - 1 && fill.is_paragraph_end;
-            let y = y_start + (line_idx as f32 * line_height);
+            let y = y_start + fill.spacing.0 + (line_idx as f32 * line_height);
 
             let (left_offset, line_width) = {
                 let ind = if line_idx == 0 { fill.indent.0 } else { fill.indent.1 };
@@ -173,10 +228,10 @@ impl Scaffold {
 
             // Record indices
             for item_idx in broken_line.item_range.clone() {
-                if let Some(index_id) = fill.items[item_idx].index_id {
-                    if index_id < index_registry.len() {
-                        indices.insert(index_registry[index_id].clone(), page_index);
-                    }
+                if let Some(index_id) = fill.items[item_idx].index_id
+                    && index_id < index_registry.len()
+                {
+                    indices.insert(index_registry[index_id].clone(), page_index);
                 }
             }
 
@@ -189,6 +244,7 @@ impl Scaffold {
                 line_width,
                 is_last,
                 &fill.alignment,
+                self.direction,
             );
             fragments.extend(frags);
         }
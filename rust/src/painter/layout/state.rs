@@ -2,31 +2,169 @@ use crate::painter::Style;
 
 use super::fragment::usize_to_letters;
 
+const CALLER_SYMBOLS: [&str; 4] = ["*", "†", "‡", "§"];
+
+/// How automatic footnote callers are numbered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub enum CallerSequence {
+    /// a, b, ... z, aa, ab, ...
+    Letters = 0,
+    /// 1, 2, 3, ...
+    Numerals = 1,
+    /// *, †, ‡, §, then doubled: **, ††, ...
+    Symbols = 2,
+}
+
+impl TryFrom<u32> for CallerSequence {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, u32> {
+        match value {
+            0 => Ok(Self::Letters),
+            1 => Ok(Self::Numerals),
+            2 => Ok(Self::Symbols),
+            _ => Err(value),
+        }
+    }
+}
+
+/// When automatic footnote callers start again from the beginning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub enum CallerReset {
+    Page = 0,
+    Chapter = 1,
+    Book = 2,
+}
+
+impl TryFrom<u32> for CallerReset {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, u32> {
+        match value {
+            0 => Ok(Self::Page),
+            1 => Ok(Self::Chapter),
+            2 => Ok(Self::Book),
+            _ => Err(value),
+        }
+    }
+}
+
+/// Where footnotes and cross-references are laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub enum NotePlacement {
+    /// In the footer of the page their caller is on.
+    Page = 0,
+    /// In a notes block after each chapter.
+    Chapter = 1,
+    /// In a notes block at the end of the book.
+    Book = 2,
+}
+
+impl TryFrom<u32> for NotePlacement {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, u32> {
+        match value {
+            0 => Ok(Self::Page),
+            1 => Ok(Self::Chapter),
+            2 => Ok(Self::Book),
+            _ => Err(value),
+        }
+    }
+}
+
+/// Attribute linking a note's caller in the text to its entry in the notes block.
+pub const NOTE_LINK: &str = "note-link";
+/// Attribute holding the whole text of a `\w` word, which may be split
+/// across fragments or share one with the text beside it.
+pub const WORD_TEXT: &str = "word";
+
+/// A run of a note's text, as it was added to the buffer.
+#[derive(Debug, Clone)]
+pub struct NoteSegment {
+    pub text: String,
+    pub style: Style,
+    pub outer_styles: Vec<Style>,
+    pub attributes: Vec<(String, String)>,
+}
+
+/// A note held back for the notes block.
+#[derive(Debug, Clone)]
+pub struct Endnote {
+    pub caller: String,
+    pub link: usize,
+    pub segments: Vec<NoteSegment>,
+}
+
+#[derive(Clone)]
 pub struct LayoutState {
     pub caller_counter: usize,
+    pub caller_sequence: CallerSequence,
 }
 
 impl LayoutState {
-    pub fn new() -> Self {
-        Self { caller_counter: 0 }
+    pub fn new(caller_sequence: CallerSequence) -> Self {
+        Self {
+            caller_counter: 0,
+            caller_sequence,
+        }
     }
 
     pub fn reset(&mut self) {
         self.caller_counter = 0;
     }
 
-    /// Increment counter and return the letter + style for the new caller.
+    fn caller(&self, n: usize) -> String {
+        match self.caller_sequence {
+            CallerSequence::Letters => usize_to_letters(n),
+            CallerSequence::Numerals => (n + 1).to_string(),
+            CallerSequence::Symbols => {
+                CALLER_SYMBOLS[n % CALLER_SYMBOLS.len()].repeat(n / CALLER_SYMBOLS.len() + 1)
+            }
+        }
+    }
+
+    /// Increment counter and return the caller + style for the new note.
     pub fn get_next_caller(&mut self) -> (String, Style) {
-        let letter = usize_to_letters(self.caller_counter);
+        let caller = self.caller(self.caller_counter);
         self.caller_counter += 1;
-        (letter, Style::Caller)
+        (caller, Style::Caller)
     }
 
-    /// Return the letter + style for the most recently assigned caller.
+    /// Return the caller + style for the most recently assigned note.
     pub fn get_current_caller(&mut self) -> (String, Style) {
         if self.caller_counter == 0 {
             return (String::new(), Style::Caller);
         }
-        (usize_to_letters(self.caller_counter - 1), Style::Caller)
+        (self.caller(self.caller_counter - 1), Style::Caller)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_raw_note_options() {
+        use CallerSequence::*;
+        for sequence in [Letters, Numerals, Symbols] {
+            assert_eq!(CallerSequence::try_from(sequence as u32), Ok(sequence));
+        }
+        for reset in [CallerReset::Page, CallerReset::Chapter, CallerReset::Book] {
+            assert_eq!(CallerReset::try_from(reset as u32), Ok(reset));
+        }
+        for placement in [
+            NotePlacement::Page,
+            NotePlacement::Chapter,
+            NotePlacement::Book,
+        ] {
+            assert_eq!(NotePlacement::try_from(placement as u32), Ok(placement));
+        }
+        assert_eq!(NotePlacement::try_from(7), Err(7));
+        assert_eq!(CallerSequence::try_from(3), Err(3));
+        assert_eq!(CallerReset::try_from(u32::MAX), Err(u32::MAX));
     }
 }
@@ -0,0 +1,97 @@
+use super::fragment::{TextFragment, extract_fragments};
+use super::inline::InlineItem;
+use super::line_breaker::LineBreaker;
+use super::{Alignment, TextDirection, Verse};
+use crate::painter::Style;
+
+/// Horizontal space between neighbouring columns.
+pub const COLUMN_GAP: f32 = 10.0;
+
+/// One cell of a table row, as written in the source (`\th`, `\tc`, `\tcr`...).
+#[derive(Debug, Clone)]
+pub struct TableCell {
+    /// The cell's text, in runs of one style each.
+    pub segments: Vec<(String, Style)>,
+    pub header: bool,
+    pub alignment: Alignment,
+}
+
+/// A `\tr` row and the verses that begin in it.
+#[derive(Debug, Clone, Default)]
+pub struct TableRow {
+    pub cells: Vec<TableCell>,
+    pub verses: Vec<Verse>,
+}
+
+/// A cell after shaping, ready to be measured and broken into lines.
+#[derive(Debug, Clone)]
+pub struct ShapedCell {
+    pub items: Vec<InlineItem>,
+    pub alignment: Alignment,
+    pub line_height: f32,
+}
+
+impl ShapedCell {
+    /// Width of the cell's text on a single line.
+    pub fn natural_width(&self) -> f32 {
+        self.items.iter().map(|i| i.width).sum()
+    }
+}
+
+/// The width of each column: the widest cell in it, scaled down evenly
+/// when the whole table would not fit in `available_width`.
+pub fn column_widths(rows: &[Vec<ShapedCell>], available_width: f32) -> Vec<f32> {
+    let num_columns = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let mut widths = vec![0.0f32; num_columns];
+    for row in rows {
+        for (column, cell) in row.iter().enumerate() {
+            widths[column] = widths[column].max(cell.natural_width());
+        }
+    }
+
+    let gaps = COLUMN_GAP * num_columns.saturating_sub(1) as f32;
+    let total: f32 = widths.iter().sum();
+    let room = (available_width - gaps).max(0.0);
+    if total > room && total > 0.0 {
+        let scale = room / total;
+        for width in widths.iter_mut() {
+            *width *= scale;
+        }
+    }
+    widths
+}
+
+/// Lay out one row on the column grid. Cells wrap within their column;
+/// fragments are positioned relative to the top of the row. Returns the
+/// fragments and the height of the tallest cell.
+pub fn layout_row(
+    row: &[ShapedCell],
+    widths: &[f32],
+    direction: TextDirection,
+) -> (Vec<TextFragment>, f32) {
+    let mut fragments = Vec::new();
+    let mut height = 0.0f32;
+    let mut left = 0.0;
+
+    for (cell, &width) in row.iter().zip(widths) {
+        let breaker = LineBreaker::new(&cell.items, Box::new(move |_| (0.0, width)));
+        let lines: Vec<_> = breaker.collect();
+        for (line_idx, line) in lines.iter().enumerate() {
+            fragments.extend(extract_fragments(
+                &cell.items,
+                line,
+                line_idx as f32 * cell.line_height,
+                cell.line_height,
+                left,
+                width,
+                line_idx == lines.len() - 1,
+                &cell.alignment,
+                direction,
+            ));
+        }
+        height = height.max(lines.len() as f32 * cell.line_height);
+        left += width + COLUMN_GAP;
+    }
+
+    (fragments, height)
+}
@@ -7,6 +7,18 @@ use super::container::StackDirection;
 use super::inline::InlineItem;
 use super::line_breaker::LineBreaker;
 
+/// Padding between a boxed block's frame and its text.
+pub const BOX_PADDING: f32 = 8.0;
+
+/// Marks a container as part of a boxed block (a sidebar), and whether the
+/// box opens or closes in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoxMark {
+    pub id: usize,
+    pub opens: bool,
+    pub closes: bool,
+}
+
 /// Tracks the fill state of one container within a template.
 #[derive(Debug, Clone)]
 pub struct ContainerFill {
@@ -18,8 +30,13 @@ pub struct ContainerFill {
     pub line_height: f32,
     pub alignment: Alignment,
     pub indent: (f32, f32),
+    /// Space above and below the lines, kept inside the container.
+    pub spacing: (f32, f32),
     pub artefacts: Vec<Artefact>,
     pub is_paragraph_end: bool,
+    pub boxed: Option<BoxMark>,
+    /// Glue items the lines must break at, from the optimal breaker.
+    pub breaks: Vec<usize>,
 }
 
 impl ContainerFill {
@@ -40,8 +57,11 @@ impl ContainerFill {
             line_height,
             alignment,
             indent,
+            spacing: (0.0, 0.0),
             artefacts: Vec::new(),
             is_paragraph_end: false,
+            boxed: None,
+            breaks: Vec::new(),
         }
     }
 
@@ -68,7 +88,7 @@ impl ContainerFill {
                 .sum();
             (left_offset, available_width - left_offset - right_artefact)
         });
-        let mut breaker = LineBreaker::new(&self.items, width_fn);
+        let mut breaker = LineBreaker::new(&self.items, width_fn).with_breaks(&self.breaks);
         let mut count = 0;
         while breaker.next().is_some() {
             count += 1;
@@ -94,9 +114,24 @@ impl ContainerFill {
         self.num_lines = self.count_lines();
     }
 
+    /// Push several items ignoring max_lines, counting lines once.
+    pub fn extend(&mut self, items: impl IntoIterator<Item = InlineItem>) {
+        self.items.extend(items);
+        self.num_lines = self.count_lines();
+    }
+
+    /// Break the line at the glue just pushed.
+    pub fn mark_break(&mut self) {
+        if let Some(last) = self.items.len().checked_sub(1) {
+            self.breaks.push(last);
+            self.num_lines = self.count_lines();
+        }
+    }
+
     /// Truncate to n items and recalculate line state.
     pub fn truncate(&mut self, n: usize) {
         self.items.truncate(n);
+        self.breaks.retain(|&b| b < n);
         self.num_lines = self.count_lines();
     }
 
@@ -119,7 +154,7 @@ impl ContainerFill {
             }
         }
 
-        height
+        height + self.spacing.0 + self.spacing.1
     }
 
     pub fn is_empty(&self) -> bool {
@@ -131,6 +166,8 @@ impl ContainerFill {
 /// Usually one line in the active container plus associated expanded content.
 pub struct Template {
     pub containers: HashMap<Section, ContainerFill>,
+    /// A page must not end after this template: it would leave an orphan or
+    /// split a block kept together.
     pub hot: bool,
 }
 
@@ -183,6 +220,13 @@ impl Template {
         fill.force_push(item.clone());
     }
 
+    /// Break the container's line at the glue just pushed to it.
+    pub fn mark_break(&mut self, section: Section) {
+        if let Some(fill) = self.containers.get_mut(&section) {
+            fill.mark_break();
+        }
+    }
+
     /// Truncate a container back to n items.
     pub fn truncate(&mut self, section: Section, n: usize) {
         if let Some(fill) = self.containers.get_mut(&section) {
@@ -1,13 +1,18 @@
 use super::Painter;
 
 mod book;
+mod chapter;
 mod character;
 mod cross_ref;
 mod element;
+mod endnotes;
 mod footnote;
+mod intro;
 mod note_element;
 mod paragraph;
 mod poetry;
+mod sidebar;
+mod table;
 
 pub trait Paint {
     fn paint(&self, painter: &mut Painter);
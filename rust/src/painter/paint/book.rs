@@ -1,48 +1,142 @@
-use usfm::ArchivedBook;
+use std::mem;
 
-use crate::painter::{Painter, Style};
-use crate::painter::layout::Section;
-use crate::painter::layout::artefact::{Artefact, ArtefactAnchor, ArtefactPadding};
+use usfm::{ArchivedBook, ArchivedElementType};
+
+use crate::painter::{BlockFormat, NotePlacement, Painter, Style};
 
 use super::Paint;
+use super::chapter::ChapterMark;
+use super::element::plain_text;
+use super::endnotes::paint_endnotes;
+use super::paragraph;
+use super::poetry;
+use super::sidebar;
+use super::table;
+
+/// The style of a `\mt`/`\mte` line, and whether it ends the book's introduction.
+fn title_line(ty: &ArchivedElementType) -> Option<(Style, bool)> {
+    match ty {
+        ArchivedElementType::MajorTitle(1) => Some((Style::Title, false)),
+        ArchivedElementType::MajorTitle(_) => Some((Style::SubTitle, false)),
+        ArchivedElementType::MajorTitleEnding(1) => Some((Style::Title, true)),
+        ArchivedElementType::MajorTitleEnding(_) => Some((Style::SubTitle, true)),
+        _ => None,
+    }
+}
 
 impl Paint for ArchivedBook {
     fn paint(&self, painter: &mut Painter) {
         use usfm::ArchivedBookContents as Content;
-        for content in self.contents.iter() {
+        let mut title = Vec::new();
+        let mut title_ending = false;
+        let mut book_label: Option<String> = None;
+        let mut chapter: Option<ChapterMark> = None;
+        let mut seen_chapter = false;
+        // The block of a paragraph that the next, \nb, paragraph carries on
+        let mut open_paragraph: Option<BlockFormat> = None;
+        let mut contents = self.contents.iter().peekable();
+        while let Some(content) = contents.next() {
+            if let Content::Element(element) = content {
+                // Consecutive title lines are gathered into a single block
+                if let Some((style, ending)) = title_line(&element.ty) {
+                    if title_ending != ending && !title.is_empty() {
+                        painter.paint_title(&mem::take(&mut title));
+                    }
+                    title.push((plain_text(element), style));
+                    title_ending = ending;
+                    continue;
+                }
+                // \cl before the first chapter labels every chapter
+                match (&element.ty, chapter.as_mut()) {
+                    (ArchivedElementType::ChapterLabel, Some(chapter)) => {
+                        chapter.label = Some(plain_text(element));
+                        continue;
+                    }
+                    (ArchivedElementType::ChapterLabel, None) if !seen_chapter => {
+                        book_label = Some(plain_text(element));
+                        continue;
+                    }
+                    (ArchivedElementType::ChapterPublishedLabel, Some(chapter)) => {
+                        chapter.published = Some(plain_text(element));
+                        continue;
+                    }
+                    _ => (),
+                }
+            }
+            if let (Content::AltChapter(n), Some(chapter)) = (content, chapter.as_mut()) {
+                chapter.alt = Some(n.to_native());
+                continue;
+            }
+            if !title.is_empty() {
+                painter.paint_title(&mem::take(&mut title));
+            }
+            if let Some(chapter) = chapter.take() {
+                chapter.paint(painter, book_label.as_deref());
+            }
+
             match content {
                 Content::Id { code, .. } => {
                     painter.index_book(code);
                 }
-                Content::Paragraph(paragraph) => paragraph.paint(painter),
-                Content::Poetry(poetry) => poetry.paint(painter),
+                // The blocks between \esb and \esbe are boxed together
+                Content::Paragraph(paragraph) if sidebar::marker(paragraph).is_some() => {
+                    match sidebar::marker(paragraph) {
+                        Some("esbe") => painter.end_sidebar(),
+                        _ => painter.begin_sidebar(),
+                    }
+                }
+                // Consecutive \tr rows are gathered into a single table
+                Content::Paragraph(paragraph) if table::is_row(paragraph) => {
+                    let mut rows = vec![table::row(paragraph, painter)];
+                    while let Some(Content::Paragraph(next)) = contents.peek()
+                        && table::is_row(next)
+                    {
+                        rows.push(table::row(next, painter));
+                        contents.next();
+                    }
+                    painter.paint_table(&rows);
+                }
+                Content::Paragraph(paragraph) => {
+                    let joined = open_paragraph.is_some();
+                    let format = open_paragraph
+                        .take()
+                        .unwrap_or_else(|| paragraph::format(&paragraph.style));
+                    paragraph::paint_text(paragraph, painter, format.line_style, joined);
+                    match contents.peek() {
+                        Some(Content::Paragraph(next)) if paragraph::continues(next) => {
+                            open_paragraph = Some(format);
+                        }
+                        _ => painter.paint_block(format),
+                    }
+                }
+                Content::Poetry(poetry) => {
+                    let couplet = matches!(
+                        contents.peek(),
+                        Some(Content::Poetry(next)) if poetry::finishes_couplet(poetry, next)
+                    );
+                    poetry::paint(poetry, painter, couplet);
+                }
                 Content::Element(element) => element.paint(painter),
                 Content::Chapter(n) => {
                     let chapter_num = n.to_native();
+                    if painter.get_options().note_placement() == NotePlacement::Chapter {
+                        paint_endnotes(painter);
+                    }
                     painter.index_chapter(chapter_num);
-
-                    let chapter_text = chapter_num.to_string();
-                    let fragment = painter.raw(&chapter_text, Style::Chapter);
-                    let padding = painter.get_dimensions().drop_cap_padding;
-
-                    let artefact = Artefact::new(
-                        ArtefactPadding {
-                            top: 0.0,
-                            bottom: 0.0,
-                            left: 0.0,
-                            right: padding,
-                        },
-                        fragment.rect.width,
-                        fragment.rect.height,
-                        ArtefactAnchor::Left,
-                        true,
-                        2,
-                        vec![fragment],
-                    );
-                    painter.add_artefact(Section::Body, artefact);
+                    chapter = Some(ChapterMark::new(chapter_num));
+                    seen_chapter = true;
                 }
                 _ => (),
             }
         }
+        if !title.is_empty() {
+            painter.paint_title(&title);
+        }
+        if let Some(chapter) = chapter {
+            chapter.paint(painter, book_label.as_deref());
+        }
+        // A sidebar left open closes with the book
+        painter.end_sidebar();
+        paint_endnotes(painter);
     }
 }
@@ -0,0 +1,104 @@
+use crate::painter::layout::Section;
+use crate::painter::layout::artefact::{Artefact, ArtefactAnchor, ArtefactPadding};
+use crate::painter::{Alignment, BlockFormat, Painter, Style};
+
+const CHAPTER_LABEL: BlockFormat =
+    BlockFormat::new(Style::ChapterLabel, Alignment::Center, (0.0, 0.0))
+        .with_spacing(0.75, 0.25)
+        .as_heading();
+
+/// A chapter as declared by `\c` and the `\cl`, `\cp` and `\ca` markers
+/// that follow it. Painted once the chapter's first block comes along.
+pub(super) struct ChapterMark {
+    pub number: u16,
+    pub label: Option<String>,
+    pub published: Option<String>,
+    pub alt: Option<u16>,
+}
+
+impl ChapterMark {
+    pub fn new(number: u16) -> Self {
+        Self {
+            number,
+            label: None,
+            published: None,
+            alt: None,
+        }
+    }
+
+    /// A chapter's own `\cl` stands in for its number in the drop cap, unless
+    /// labels are drawn as headings. A book-wide `\cl` reads with the number,
+    /// as in "Psalm 23", which is too long for a drop cap, so it is always a
+    /// heading. A `\cp` published number stands in for the number, in the drop
+    /// cap and after a book-wide label alike.
+    pub fn paint(&self, painter: &mut Painter, book_label: Option<&str>) {
+        let headings = painter.get_options().chapter_label_headings != 0;
+        match (&self.label, book_label) {
+            (Some(label), _) if headings => self.paint_heading(painter, label),
+            (Some(label), _) => self.paint_drop_cap(painter, Some(label.clone())),
+            (None, Some(label)) => {
+                let number = self
+                    .published
+                    .clone()
+                    .unwrap_or_else(|| self.number.to_string());
+                self.paint_heading(painter, &format!("{} {}", label, number))
+            }
+            (None, None) => self.paint_drop_cap(painter, None),
+        }
+    }
+
+    fn paint_heading(&self, painter: &mut Painter, label: &str) {
+        painter.set_container(Section::Body);
+        painter
+            .push_properties(Style::ChapterLabel, Section::Body)
+            .add_text(label);
+        if let Some(alt) = self.alt {
+            painter
+                .add_text(" ")
+                .push_properties(Style::AltChapter, Section::Body)
+                .add_text(format!("({})", alt))
+                .pop_properties();
+        }
+        painter.pop_properties();
+        painter.paint_block(CHAPTER_LABEL);
+    }
+
+    fn paint_drop_cap(&self, painter: &mut Painter, label: Option<String>) {
+        let text = self
+            .published
+            .clone()
+            .or(label)
+            .unwrap_or_else(|| self.number.to_string());
+        let mut fragments = vec![painter.raw(&text, Style::Chapter)];
+
+        // The alternate number sits centred under the drop cap
+        if let Some(alt) = self.alt {
+            let mut fragment = painter.raw(&format!("({})", alt), Style::AltChapter);
+            fragment.rect.top = fragments[0].rect.height;
+            fragments.push(fragment);
+        }
+        let width = fragments.iter().map(|f| f.rect.width).fold(0.0, f32::max);
+        let height = fragments.iter().map(|f| f.rect.height).sum();
+        for fragment in fragments.iter_mut() {
+            fragment.rect.left = (width - fragment.rect.width) / 2.0;
+        }
+
+        let padding = painter.get_dimensions().drop_cap_padding;
+        let artefact = Artefact::new(
+            ArtefactPadding {
+                top: 0.0,
+                bottom: 0.0,
+                left: 0.0,
+                right: padding,
+            },
+            width,
+            height,
+            ArtefactAnchor::Left,
+            true,
+            // The alternate number makes the drop cap taller
+            painter.line_span(height),
+            fragments,
+        );
+        painter.add_artefact(Section::Body, artefact);
+    }
+}
@@ -1,18 +1,95 @@
-use usfm::ArchivedCharacter;
+use rkyv::rancor::Error;
+use usfm::{ArchivedCharacter, ArchivedCharacterType};
 
-use crate::painter::{Painter, Style};
+use crate::normalize::{EMPTY_LINK, FIGURE_SIZE, FIGURE_SOURCE, NOTE_CALLER};
 use crate::painter::layout::Section;
+use crate::painter::layout::figure::{DEFAULT_ASPECT_RATIO, Figure, FigurePlacement};
+use crate::painter::layout::state::WORD_TEXT;
+use crate::painter::{Painter, Style};
+
+use super::{Paint, element::push_character_text};
+
+pub(super) fn character_style(ty: &ArchivedCharacterType) -> Option<Style> {
+    use ArchivedCharacterType as Type;
+    match ty {
+        Type::Word => Some(Style::Word),
+        Type::Addition => Some(Style::Addition),
+        Type::Deity => Some(Style::Deity),
+        Type::Jesus => Some(Style::Jesus),
+        Type::Bold => Some(Style::Bold),
+        Type::Italic => Some(Style::Italic),
+        Type::BoldItalic => Some(Style::BoldItalic),
+        Type::SmallCap => Some(Style::SmallCap),
+        Type::Selah => Some(Style::Selah),
+        Type::Transliterated => Some(Style::Transliterated),
+        Type::Proper => Some(Style::Proper),
+        Type::IntroOutline => Some(Style::IntroOutline),
+        _ => None,
+    }
+}
+
+/// The caller a `\+jmp` carries, when it was moved into a note from the
+/// note's opening marker.
+pub(super) fn note_caller(character: &ArchivedCharacter) -> Option<char> {
+    if !matches!(character.ty, ArchivedCharacterType::Link) {
+        return None;
+    }
+    character
+        .attributes
+        .iter()
+        .find(|attribute| attribute.0 == NOTE_CALLER)
+        .and_then(|attribute| attribute.1.chars().next())
+}
 
-use super::Paint;
+/// The figure a `\jmp` stands for, when it was rewritten from a `\fig`.
+fn figure(character: &ArchivedCharacter) -> Option<Figure> {
+    if !matches!(character.ty, ArchivedCharacterType::Link) {
+        return None;
+    }
+    let attribute = |name: &str| {
+        character
+            .attributes
+            .iter()
+            .find(|attribute| attribute.0 == name)
+            .map(|attribute| attribute.1.as_str())
+    };
+    let source = attribute(FIGURE_SOURCE)?;
+    let mut caption = String::new();
+    push_character_text(&character.contents, &mut caption);
+    Some(Figure {
+        source: source.to_string(),
+        caption: caption.replace(EMPTY_LINK, ""),
+        aspect_ratio: DEFAULT_ASPECT_RATIO,
+        placement: FigurePlacement::from_size(attribute(FIGURE_SIZE).unwrap_or("")),
+    })
+}
 
 impl Paint for ArchivedCharacter {
     fn paint(&self, painter: &mut Painter) {
         use usfm::ArchivedCharacterContents as Content;
-        use usfm::ArchivedCharacterType;
 
+        if let Some(figure) = figure(self) {
+            painter.paint_figure(&figure);
+            return;
+        }
+        // Already shown as the note's caller
+        if note_caller(self).is_some() {
+            return;
+        }
+
+        let style = character_style(&self.ty);
+        if let Some(style) = style {
+            painter.push_properties(style, Section::Body);
+        }
+        // Lemma, Strong's number and morphology go along with the word's text
         let is_word = matches!(self.ty, ArchivedCharacterType::Word);
         if is_word {
-            painter.push_properties(Style::Word, Section::Body);
+            let mut attributes: Vec<(String, String)> =
+                rkyv::deserialize::<_, Error>(&self.attributes).unwrap();
+            let mut word = String::new();
+            push_character_text(&self.contents, &mut word);
+            attributes.push((WORD_TEXT.to_string(), word.trim().to_string()));
+            painter.set_word_attributes(attributes);
         }
 
         for content in self.contents.iter() {
@@ -25,6 +102,9 @@ impl Paint for ArchivedCharacter {
         }
 
         if is_word {
+            painter.set_word_attributes(Vec::new());
+        }
+        if style.is_some() {
             painter.pop_properties();
         }
     }
@@ -1,23 +1,84 @@
-use usfm::ArchivedCrossRef;
+use usfm::{ArchivedCrossRef, ArchivedCrossRefElementStyle};
 
 use crate::painter::{Painter, Style, layout::Section};
 
-use super::Paint;
+use super::element::push_character_text;
+use super::{Paint, note_element};
+
+/// Attribute carrying the id of a linked cross-reference's targets.
+pub const CROSS_REF_LINK: &str = "x-link";
+
+fn is_target(style: &ArchivedCrossRefElementStyle) -> bool {
+    use ArchivedCrossRefElementStyle as ElementStyle;
+    matches!(
+        style,
+        ElementStyle::Target
+            | ElementStyle::ExtraTarget
+            | ElementStyle::OldTarget
+            | ElementStyle::NewTarget
+            | ElementStyle::DeuteroTarget
+    )
+}
+
+fn element_style(style: &ArchivedCrossRefElementStyle) -> Style {
+    use ArchivedCrossRefElementStyle as ElementStyle;
+    match style {
+        ElementStyle::Reference | ElementStyle::Origin => Style::NoteReference,
+        ElementStyle::Keyword => Style::NoteKeyword,
+        ElementStyle::Quote | ElementStyle::InlineQuote => Style::NoteQuote,
+        ElementStyle::Target
+        | ElementStyle::ExtraTarget
+        | ElementStyle::OldTarget
+        | ElementStyle::NewTarget => Style::NoteTarget,
+        ElementStyle::DeuteroTarget => Style::NoteDeutero,
+    }
+}
 
 impl Paint for ArchivedCrossRef {
     fn paint(&self, painter: &mut Painter) {
         use usfm::ArchivedCrossRefElement as Element;
+        if painter.get_options().show_cross_references == 0 {
+            return;
+        }
 
         // Cross references use the same group pattern as footnotes
-        painter.begin_footnote();
+        let first = self.elements.iter().find_map(|element| match element {
+            Element::Element(element) => Some(&element.contents[..]),
+            _ => None,
+        });
+        painter.begin_footnote(&note_element::caller(&self.caller, first));
 
         painter.push_properties(Style::CrossRef, Section::Footer);
         for content in self.elements.iter() {
             match content {
                 Element::Reference(note_ref) => {
-                    painter.add_text(format!("{}: ", note_ref.verse));
+                    painter
+                        .push_properties(Style::NoteReference, Section::Footer)
+                        .add_text(format!("{}: ", note_ref.verse))
+                        .pop_properties();
+                }
+                Element::Element(note_element) => {
+                    painter.push_properties(element_style(&note_element.style), Section::Footer);
+                    // Target text links to the passages it names
+                    let link = if is_target(&note_element.style) {
+                        let mut text = String::new();
+                        push_character_text(&note_element.contents, &mut text);
+                        painter.link_references(&text)
+                    } else {
+                        None
+                    };
+                    if let Some(link) = link {
+                        painter.set_word_attributes(vec![(
+                            CROSS_REF_LINK.to_string(),
+                            link.to_string(),
+                        )]);
+                    }
+                    note_element.paint(painter);
+                    if link.is_some() {
+                        painter.set_word_attributes(Vec::new());
+                    }
+                    painter.pop_properties();
                 }
-                Element::Element(note_element) => note_element.paint(painter),
             }
         }
         painter.pop_properties();
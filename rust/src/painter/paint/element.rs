@@ -1,20 +1,122 @@
-use usfm::ArchivedElement;
+use usfm::{
+    ArchivedCharacterContents, ArchivedElement, ArchivedElementContents, ArchivedElementType,
+};
 
-use crate::painter::Painter;
+use crate::painter::{Alignment, BlockFormat, Painter, Style, layout::Section};
 
-use super::Paint;
+use super::{Paint, intro};
+
+const MAJOR_SECTION: BlockFormat =
+    BlockFormat::new(Style::MajorSection, Alignment::Center, (0.0, 0.0))
+        .with_spacing(1.0, 0.25)
+        .as_heading();
+const MAJOR_REFERENCE: BlockFormat =
+    BlockFormat::new(Style::MajorReference, Alignment::Center, (0.0, 0.0))
+        .with_spacing(0.0, 0.5)
+        .as_heading();
+const SECTION: BlockFormat = BlockFormat::new(Style::Section, Alignment::Center, (0.0, 0.0))
+    .with_spacing(0.75, 0.25)
+    .as_heading();
+const SUB_SECTION: BlockFormat = BlockFormat::new(Style::SubSection, Alignment::Center, (0.0, 0.0))
+    .with_spacing(0.5, 0.25)
+    .as_heading();
+const MINOR_SECTION: BlockFormat =
+    BlockFormat::new(Style::MinorSection, Alignment::Left, (0.0, 0.0))
+        .with_spacing(0.5, 0.0)
+        .as_heading();
+const PARALLEL: BlockFormat = BlockFormat::new(Style::Parallel, Alignment::Center, (0.0, 0.0))
+    .with_spacing(0.0, 0.5)
+    .as_heading();
+const DESCRIPTIVE: BlockFormat = BlockFormat::new(Style::Descriptive, Alignment::Left, (0.0, 0.0))
+    .with_spacing(0.0, 0.25)
+    .as_heading();
 
 impl Paint for ArchivedElement {
     fn paint(&self, painter: &mut Painter) {
-        use usfm::ArchivedElementContents as Content;
-        use usfm::ArchivedElementType;
+        use usfm::ArchivedElementType as Type;
+
+        let format = match &self.ty {
+            Type::Header => {
+                for content in self.contents.iter() {
+                    if let ArchivedElementContents::Line(header) = content {
+                        painter.index_header(header);
+                        painter.paint_heading(header);
+                    }
+                }
+                return;
+            }
+            Type::MajorSection(_) => MAJOR_SECTION,
+            Type::MajorReference | Type::Reference => MAJOR_REFERENCE,
+            Type::Section(1) => SECTION,
+            Type::Section(2) => SUB_SECTION,
+            Type::Section(_) => MINOR_SECTION,
+            Type::Parallel => PARALLEL,
+            Type::Descriptive => DESCRIPTIVE,
+            ty => match intro::format(ty) {
+                Some(format) if painter.get_options().include_introductions != 0 => format,
+                _ => return,
+            },
+        };
+
+        painter.set_container(Section::Body);
+        // Every heading, introduction headings included, gets its own index entry
+        if format.line_style != Style::Intro {
+            painter.index_heading(plain_text(self));
+        }
+        // A hidden heading leaves its index marker for the text that follows
+        if is_section_heading(&self.ty) && painter.get_options().show_headings == 0 {
+            return;
+        }
+        painter.push_properties(format.line_style, Section::Body);
         for content in self.contents.iter() {
-            match (&self.ty, content) {
-                (ArchivedElementType::Header, Content::Line(header)) => {
-                    painter.index_header(header);
-                    painter.paint_heading(header);
+            match content {
+                ArchivedElementContents::Line(text) => {
+                    painter.add_text(text);
                 }
-                _ => (),
+                ArchivedElementContents::Character(character) => character.paint(painter),
+                ArchivedElementContents::Footnote(footnote) => footnote.paint(painter),
+                ArchivedElementContents::CrossRef(cross_ref) => cross_ref.paint(painter),
+            }
+        }
+        painter.pop_properties();
+        painter.paint_block(format);
+    }
+}
+
+/// Section headings and the references under them, which readers may hide.
+fn is_section_heading(ty: &ArchivedElementType) -> bool {
+    use ArchivedElementType as Type;
+    matches!(
+        ty,
+        Type::MajorSection(_)
+            | Type::MajorReference
+            | Type::Reference
+            | Type::Section(_)
+            | Type::Parallel
+    )
+}
+
+/// The element's text with character markup flattened and notes dropped.
+pub(super) fn plain_text(element: &ArchivedElement) -> String {
+    let mut text = String::new();
+    for content in element.contents.iter() {
+        match content {
+            ArchivedElementContents::Line(line) => text.push_str(line),
+            ArchivedElementContents::Character(character) => {
+                push_character_text(&character.contents, &mut text)
+            }
+            _ => (),
+        }
+    }
+    text.trim().to_string()
+}
+
+pub(super) fn push_character_text(contents: &[ArchivedCharacterContents], text: &mut String) {
+    for content in contents {
+        match content {
+            ArchivedCharacterContents::Line(line) => text.push_str(line),
+            ArchivedCharacterContents::Character(character) => {
+                push_character_text(&character.contents, text)
             }
         }
     }
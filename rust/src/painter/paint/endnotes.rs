@@ -0,0 +1,35 @@
+use crate::painter::{Alignment, BlockFormat, Painter, Style, layout::Section};
+
+const NOTE_INDENT: f32 = 10.0;
+
+const NOTES_HEADING: BlockFormat = BlockFormat::new(Style::Section, Alignment::Center, (0.0, 0.0))
+    .with_spacing(0.75, 0.25)
+    .as_heading();
+const NOTE: BlockFormat = BlockFormat::new(Style::Footnote, Alignment::Left, (0.0, NOTE_INDENT));
+
+/// Paint the notes gathered since the last notes block under the caller's
+/// notes title, one block per note. Does nothing if there are none.
+pub(super) fn paint_endnotes(painter: &mut Painter) {
+    let notes = painter.take_endnotes();
+    if notes.is_empty() {
+        return;
+    }
+
+    let title = painter.get_options().notes_title().to_string();
+    if !title.is_empty() {
+        painter
+            .set_container(Section::Body)
+            .push_properties(Style::Section, Section::Body)
+            .add_text(title)
+            .pop_properties();
+        painter.paint_block(NOTES_HEADING);
+    }
+
+    for note in notes.iter() {
+        painter
+            .push_properties(Style::Footnote, Section::Body)
+            .add_endnote(note)
+            .pop_properties();
+        painter.paint_block(NOTE);
+    }
+}
@@ -1,24 +1,54 @@
-use usfm::ArchivedFootnote;
+use usfm::{ArchivedFootnote, ArchivedFootnoteElementStyle};
 
 use crate::painter::{Painter, Style, layout::Section};
 
-use super::Paint;
+use super::{Paint, note_element};
+
+fn element_style(style: &ArchivedFootnoteElementStyle) -> Style {
+    use ArchivedFootnoteElementStyle as ElementStyle;
+    match style {
+        ElementStyle::Reference => Style::NoteReference,
+        ElementStyle::TranslationQuote => Style::NoteQuote,
+        ElementStyle::AltTranslationQuote => Style::NoteAltQuote,
+        ElementStyle::Keyword => Style::NoteKeyword,
+        ElementStyle::Label => Style::NoteLabel,
+        ElementStyle::Witness => Style::NoteWitness,
+        ElementStyle::Paragraph | ElementStyle::Text | ElementStyle::ReferenceMark => {
+            Style::NoteText
+        }
+        ElementStyle::DeuteroText => Style::NoteDeutero,
+    }
+}
 
 impl Paint for ArchivedFootnote {
     fn paint(&self, painter: &mut Painter) {
         use usfm::ArchivedFootnoteElement as Element;
+        if painter.get_options().show_footnotes == 0 {
+            return;
+        }
 
         // Group: caller in body + expanded footnote content in footer
-        painter.begin_footnote();
+        let first = self.elements.iter().find_map(|element| match element {
+            Element::Element(element) => Some(&element.contents[..]),
+            _ => None,
+        });
+        painter.begin_footnote(&note_element::caller(&self.caller, first));
 
         // Footnote content (already in expanded/footer context)
         painter.push_properties(Style::Footnote, Section::Footer);
         for element in self.elements.iter() {
             match element {
                 Element::Reference(note_ref) => {
-                    painter.add_text(format!("{}: ", note_ref.verse));
+                    painter
+                        .push_properties(Style::NoteReference, Section::Footer)
+                        .add_text(format!("{}: ", note_ref.verse))
+                        .pop_properties();
+                }
+                Element::Element(note_element) => {
+                    painter.push_properties(element_style(&note_element.style), Section::Footer);
+                    note_element.paint(painter);
+                    painter.pop_properties();
                 }
-                Element::Element(note_element) => note_element.paint(painter),
             }
         }
         painter.pop_properties();
@@ -0,0 +1,43 @@
+use usfm::ArchivedElementType;
+
+use crate::painter::{Alignment, BlockFormat, Style};
+
+const INTRO_INDENT: f32 = 20.0;
+
+const INTRO_TITLE: BlockFormat =
+    BlockFormat::new(Style::IntroTitle, Alignment::Center, (0.0, 0.0)).with_spacing(1.0, 0.5);
+const INTRO_SECTION: BlockFormat =
+    BlockFormat::new(Style::IntroSection, Alignment::Center, (0.0, 0.0)).with_spacing(0.75, 0.25);
+
+/// How an introduction element is laid out, or `None` for markers that
+/// paint nothing (`\ib`, `\ie`) and for elements outside the introduction.
+pub(super) fn format(ty: &ArchivedElementType) -> Option<BlockFormat> {
+    use ArchivedElementType as Type;
+    let block = |alignment, first: f32, rest: f32| {
+        BlockFormat::new(
+            Style::Intro,
+            alignment,
+            (INTRO_INDENT * first, INTRO_INDENT * rest),
+        )
+    };
+    let format = match ty {
+        Type::MajorIntro(_) | Type::MajorTitleEndingIntro(_) => INTRO_TITLE,
+        Type::SectionIntro(_) | Type::OutlineIntro => INTRO_SECTION,
+        Type::Intro | Type::BridgeIntro => block(Alignment::Justified, 1.0, 0.0),
+        Type::IndentedIntro => block(Alignment::Justified, 2.0, 1.0),
+        Type::MarginIntro => block(Alignment::Justified, 0.0, 0.0),
+        Type::MarginIndentedIntro | Type::MarginQuotedIntro => {
+            block(Alignment::Justified, 1.0, 1.0)
+        }
+        Type::QuotedIntro => block(Alignment::Justified, 2.0, 1.0),
+        Type::RightIntro => block(Alignment::Right, 0.0, 0.0),
+        Type::PoetryIntro(level) => block(Alignment::Left, *level as f32, 3.0),
+        // Outline entries and list items hang, so their text lines up past the reference
+        Type::ListIntro(level) | Type::EntryIntro(level) => {
+            let level = *level as f32;
+            block(Alignment::Left, level - 1.0, level)
+        }
+        _ => return None,
+    };
+    Some(format)
+}
@@ -1,10 +1,27 @@
 use core::{fmt::Debug, hash::Hash};
-use rkyv::Archive;
-use usfm::ArchivedNoteElement;
+use rkyv::{Archive, rancor::Error};
+use usfm::{ArchivedCaller, ArchivedCharacterContents, ArchivedNoteElement, Caller};
 
 use crate::painter::Painter;
 
 use super::Paint;
+use super::character::note_caller;
+
+/// A note's caller, or the explicit one, such as `*`, that normalize moved
+/// to the start of the note's `first` element.
+pub(super) fn caller(
+    caller: &ArchivedCaller,
+    first: Option<&[ArchivedCharacterContents]>,
+) -> Caller {
+    let explicit = match first.and_then(|contents| contents.first()) {
+        Some(ArchivedCharacterContents::Character(character)) => note_caller(character),
+        _ => None,
+    };
+    match explicit {
+        Some(explicit) => Caller::Some(explicit),
+        None => rkyv::deserialize::<_, Error>(caller).unwrap(),
+    }
+}
 
 impl<NoteStyle: Archive> Paint for ArchivedNoteElement<NoteStyle>
 where
@@ -1,38 +1,139 @@
-use usfm::ArchivedParagraph;
+use usfm::{
+    ArchivedCharacterType, ArchivedParagraph, ArchivedParagraphContents, ArchivedParagraphStyle,
+};
 
-use crate::painter::{Painter, Style, layout::Section};
+use crate::painter::{
+    Alignment, BlockFormat, Painter, Style,
+    layout::{Section, Verse},
+};
 
-use super::Paint;
+use super::{Paint, element::push_character_text};
 
-impl Paint for ArchivedParagraph {
-    fn paint(&self, painter: &mut Painter) {
-        use usfm::ArchivedParagraphContents as Content;
-        painter.set_container(Section::Body);
-        painter.push_properties(Style::Normal, Section::Body);
-        for content in self.contents.iter() {
-            match content {
-                Content::Verse(verse_num) => {
-                    let v = verse_num.to_native();
-                    if v > 1 {
-                        painter
-                            .add_text(" ")
-                            .push_properties(Style::Verse, Section::Body)
-                            .index_verse(v)
-                            .add_text(v.to_string())
-                            .pop_properties();
-                    } else {
-                        painter.index_verse(v);
-                    }
+const PARAGRAPH_INDENT: f32 = 20.0;
+
+pub(super) fn format(style: &ArchivedParagraphStyle) -> BlockFormat {
+    use ArchivedParagraphStyle as ParagraphKind;
+    let block = |alignment, first: f32, rest: f32| {
+        BlockFormat::new(
+            Style::Normal,
+            alignment,
+            (PARAGRAPH_INDENT * first, PARAGRAPH_INDENT * rest),
+        )
+    };
+    match style {
+        ParagraphKind::Normal => block(Alignment::Justified, 1.0, 0.0),
+        // \nb is joined to the paragraph before it, except after a chapter break
+        ParagraphKind::Margin | ParagraphKind::Basic => block(Alignment::Justified, 0.0, 0.0),
+        ParagraphKind::Opening => block(Alignment::Justified, 1.0, 0.0).with_spacing(0.5, 0.5),
+        ParagraphKind::Indented(level) => {
+            let level = *level as f32;
+            block(Alignment::Justified, level + 1.0, level)
+        }
+        // Also \li list items, which are rewritten to \ph before parsing
+        ParagraphKind::HangingIndented(level) => {
+            let level = *level as f32;
+            block(Alignment::Justified, level - 1.0, level)
+        }
+        ParagraphKind::MarginIndented
+        | ParagraphKind::EmbeddedOpening
+        | ParagraphKind::EmbeddedClosing => block(Alignment::Justified, 1.0, 1.0),
+        ParagraphKind::Embedded => block(Alignment::Justified, 2.0, 1.0),
+        ParagraphKind::Centered => block(Alignment::Center, 0.0, 0.0),
+        ParagraphKind::Right
+        | ParagraphKind::Closure
+        | ParagraphKind::EmbeddedRefrain
+        | ParagraphKind::LiturgicalNote => block(Alignment::Right, 0.0, 0.0),
+    }
+}
+
+/// Whether a paragraph carries on the last line of the one before it.
+pub(super) fn continues(paragraph: &ArchivedParagraph) -> bool {
+    matches!(paragraph.style, ArchivedParagraphStyle::Basic)
+}
+
+/// The text of a `\vp` published verse, which stands in for the number of
+/// the `\v` before it.
+pub(super) fn published_verse(content: &ArchivedParagraphContents) -> Option<String> {
+    match content {
+        ArchivedParagraphContents::Character(character)
+            if matches!(character.ty, ArchivedCharacterType::PublishedVerse) =>
+        {
+            let mut text = String::new();
+            push_character_text(&character.contents, &mut text);
+            Some(text.trim().to_string())
+        }
+        _ => None,
+    }
+}
+
+/// The verse indexed for a `\v` number and the label drawn for it. A
+/// published bridge or segment of that verse is indexed as the range it names.
+pub(super) fn verse_label(number: u16, published: Option<String>) -> (Verse, String) {
+    match published {
+        Some(text) => {
+            let verse = Verse::parse(&text)
+                .filter(|verse| verse.start == number)
+                .unwrap_or(number.into());
+            (verse, text)
+        }
+        None => (number.into(), number.to_string()),
+    }
+}
+
+/// Buffer a paragraph's text without ending its block, so that a `\nb`
+/// paragraph after it can carry on the same line. `joined` marks such a
+/// paragraph, which is spaced from the text before it.
+pub(super) fn paint_text(
+    paragraph: &ArchivedParagraph,
+    painter: &mut Painter,
+    line_style: Style,
+    joined: bool,
+) {
+    use usfm::ArchivedParagraphContents as Content;
+    painter.set_container(Section::Body);
+    painter.push_properties(line_style, Section::Body);
+    // A verse number brings its own space
+    if joined && !matches!(paragraph.contents.first(), Some(Content::Verse(_))) {
+        painter.add_text(" ");
+    }
+    let mut contents = paragraph.contents.iter().peekable();
+    while let Some(content) = contents.next() {
+        match content {
+            Content::Verse(verse_num) => {
+                let published = contents.peek().and_then(|next| published_verse(next));
+                if published.is_some() {
+                    contents.next();
                 }
-                Content::Line(text) => {
-                    painter.add_text(text);
+                let (v, label) = verse_label(verse_num.to_native(), published);
+                if v.start > 1 && painter.get_options().show_verse_numbers != 0 {
+                    painter
+                        .add_text(" ")
+                        .push_properties(Style::Verse, Section::Body)
+                        .index_verse(v)
+                        .add_text(label)
+                        .pop_properties();
+                } else if v.start > 1 {
+                    // The verse is indexed even when its number is not drawn
+                    painter.add_text(" ").index_verse(v);
+                } else {
+                    painter.index_verse(v);
                 }
-                Content::Character(character) => character.paint(painter),
-                Content::Footnote(footnote) => footnote.paint(painter),
-                Content::CrossRef(cross_ref) => cross_ref.paint(painter),
             }
+            Content::Line(text) => {
+                painter.add_text(text);
+            }
+            Content::Character(character) => character.paint(painter),
+            Content::Footnote(footnote) => footnote.paint(painter),
+            Content::CrossRef(cross_ref) => cross_ref.paint(painter),
         }
-        painter.pop_properties();
-        painter.paint_paragraph();
+    }
+    painter.pop_properties();
+}
+
+impl Paint for ArchivedParagraph {
+    fn paint(&self, painter: &mut Painter) {
+        let format = format(&self.style);
+        paint_text(self, painter, format.line_style, false);
+        painter.paint_block(format);
     }
 }
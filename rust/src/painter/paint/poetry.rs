@@ -1,41 +1,94 @@
-use usfm::ArchivedPoetry;
+use usfm::{ArchivedCharacterType, ArchivedPoetry, ArchivedPoetryStyle};
 
-use crate::painter::{Style, layout::Section};
+use crate::painter::{Alignment, BlockFormat, Painter, Style, layout::Section};
 
-use super::Paint;
+use super::{
+    Paint,
+    paragraph::{published_verse, verse_label},
+};
 
-impl Paint for ArchivedPoetry {
-    fn paint(&self, painter: &mut crate::painter::Painter) {
-        use usfm::ArchivedParagraphContents as Content;
-        use usfm::ArchivedPoetryStyle as PoetryKind;
-        painter.set_container(Section::Body);
-        painter.push_properties(Style::Normal, Section::Body);
-        for content in self.contents.iter() {
-            match content {
-                Content::Verse(verse_num) => {
+const POETRY_INDENT: f32 = 20.0;
+
+fn format(style: &ArchivedPoetryStyle) -> BlockFormat {
+    use ArchivedPoetryStyle as PoetryKind;
+    match style {
+        // A poetry line is short; its wrapped part stays with it
+        PoetryKind::Normal(indent_level) | PoetryKind::Embedded(indent_level) => BlockFormat::new(
+            Style::Normal,
+            Alignment::Left,
+            (POETRY_INDENT * *indent_level as f32, POETRY_INDENT * 3.0),
+        )
+        .kept_together(),
+        PoetryKind::Right => BlockFormat::new(Style::Normal, Alignment::Right, (0.0, 0.0)),
+        PoetryKind::Centered => BlockFormat::new(Style::Normal, Alignment::Center, (0.0, 0.0)),
+        PoetryKind::AcrosticHeading => {
+            BlockFormat::new(Style::AcrosticHeading, Alignment::Center, (0.0, 0.0))
+                .with_spacing(0.5, 0.0)
+                .as_heading()
+        }
+        PoetryKind::Descriptive => {
+            BlockFormat::new(Style::Descriptive, Alignment::Left, (0.0, 0.0))
+                .with_spacing(0.25, 0.0)
+                .as_heading()
+        }
+    }
+}
+
+/// Whether `next` finishes the couplet `poetry` opens, indented deeper as a
+/// `\q2` after a `\q1`.
+pub(super) fn finishes_couplet(poetry: &ArchivedPoetry, next: &ArchivedPoetry) -> bool {
+    use ArchivedPoetryStyle as PoetryKind;
+    match (&poetry.style, &next.style) {
+        (PoetryKind::Normal(level), PoetryKind::Normal(next_level)) => next_level > level,
+        _ => false,
+    }
+}
+
+/// Paint a poetry line. The first line of a couplet stays on the page with
+/// the line that finishes it.
+pub(super) fn paint(poetry: &ArchivedPoetry, painter: &mut Painter, couplet: bool) {
+    use usfm::ArchivedParagraphContents as Content;
+    let mut format = format(&poetry.style);
+    if couplet {
+        format = format.kept_with_next();
+    }
+    painter.set_container(Section::Body);
+    painter.push_properties(format.line_style, Section::Body);
+    let mut contents = poetry.contents.iter().peekable();
+    while let Some(content) = contents.next() {
+        match content {
+            Content::Verse(verse_num) => {
+                let published = contents.peek().and_then(|next| published_verse(next));
+                if published.is_some() {
+                    contents.next();
+                }
+                let (verse, label) = verse_label(verse_num.to_native(), published);
+                painter.add_text(" ");
+                // The verse is indexed even when its number is not drawn
+                if painter.get_options().show_verse_numbers != 0 {
                     painter
-                        .add_text(" ")
                         .push_properties(Style::Verse, Section::Body)
-                        .index_verse(verse_num.to_native())
-                        .add_text(verse_num.to_string())
+                        .index_verse(verse)
+                        .add_text(label)
                         .pop_properties();
+                } else {
+                    painter.index_verse(verse);
                 }
-                Content::Line(text) => {
-                    painter.add_text(text);
-                }
-                Content::Character(character) => character.paint(painter),
-                _ => (),
             }
-        }
-        match self.style {
-            PoetryKind::Normal(indent_level) => {
-                painter.pop_properties();
-                painter.paint_paragraph_with_indent(
-                    20.0 * indent_level as f32,
-                    20.0 * 3.0,
-                );
+            Content::Line(text) => {
+                painter.add_text(text);
+            }
+            Content::Character(character) => {
+                // Selah floats to the right margin of its line
+                if matches!(character.ty, ArchivedCharacterType::Selah) {
+                    painter.add_fill();
+                }
+                character.paint(painter);
             }
-            _ => painter.clean(),
+            Content::Footnote(footnote) => footnote.paint(painter),
+            Content::CrossRef(cross_ref) => cross_ref.paint(painter),
         }
     }
+    painter.pop_properties();
+    painter.paint_block(format);
 }
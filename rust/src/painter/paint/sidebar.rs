@@ -0,0 +1,20 @@
+use usfm::{ArchivedCharacterType, ArchivedParagraph, ArchivedParagraphContents};
+
+use crate::normalize::SIDEBAR;
+
+/// `esb` or `esbe`, when a paragraph was rewritten from the marker that
+/// opens or closes a sidebar.
+pub(super) fn marker(paragraph: &ArchivedParagraph) -> Option<&str> {
+    match paragraph.contents.as_slice() {
+        [ArchivedParagraphContents::Character(character)]
+            if matches!(character.ty, ArchivedCharacterType::Link) =>
+        {
+            character
+                .attributes
+                .iter()
+                .find(|attribute| attribute.0 == SIDEBAR)
+                .map(|attribute| attribute.1.as_str())
+        }
+        _ => None,
+    }
+}
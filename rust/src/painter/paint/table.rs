@@ -0,0 +1,132 @@
+use usfm::{ArchivedCharacterType, ArchivedParagraph, ArchivedParagraphContents};
+
+use crate::log;
+use crate::normalize::TABLE_CELL;
+use crate::painter::layout::table::{TableCell, TableRow};
+use crate::painter::{Alignment, Painter, Style};
+
+use super::character::character_style;
+use super::element::push_character_text;
+use super::paragraph::{published_verse, verse_label};
+
+/// The marker of the cell a `\jmp` opens, when it was rewritten from a table cell.
+fn cell_marker(content: &ArchivedParagraphContents) -> Option<&str> {
+    match content {
+        ArchivedParagraphContents::Character(character)
+            if matches!(character.ty, ArchivedCharacterType::Link) =>
+        {
+            character
+                .attributes
+                .iter()
+                .find(|attribute| attribute.0 == TABLE_CELL)
+                .map(|attribute| attribute.1.as_str())
+        }
+        _ => None,
+    }
+}
+
+/// Whether a paragraph was rewritten from a `\tr` row, which may open
+/// with a verse before its first cell.
+pub(super) fn is_row(paragraph: &ArchivedParagraph) -> bool {
+    paragraph
+        .contents
+        .iter()
+        .find(|content| !matches!(content, ArchivedParagraphContents::Verse(_)))
+        .and_then(cell_marker)
+        .is_some()
+}
+
+/// An empty cell for a marker such as `th1` or `tcr2`: a header for `th`,
+/// aligned by the `r` or `c` after it.
+fn cell(marker: &str) -> TableCell {
+    let alignment = match marker.chars().nth(2) {
+        Some('r') => Alignment::Right,
+        Some('c') => Alignment::Center,
+        _ => Alignment::Left,
+    };
+    TableCell {
+        segments: Vec::new(),
+        header: marker.starts_with("th"),
+        alignment,
+    }
+}
+
+/// The style of a cell's own text.
+fn text_style(cell: &TableCell) -> Style {
+    if cell.header {
+        Style::TableHeader
+    } else {
+        Style::Normal
+    }
+}
+
+fn push_segment(cell: &mut TableCell, text: &str, style: Style) {
+    match cell.segments.last_mut() {
+        Some((last, last_style)) if *last_style == style => last.push_str(text),
+        _ => cell.segments.push((text.to_string(), style)),
+    }
+}
+
+/// Gather a row's cells and the verses that begin in it. Notes cannot be
+/// placed in a cell and are left out.
+pub(super) fn row(paragraph: &ArchivedParagraph, painter: &Painter) -> TableRow {
+    use usfm::ArchivedParagraphContents as Content;
+    let mut row = TableRow::default();
+    let mut contents = paragraph.contents.iter().peekable();
+    while let Some(content) = contents.next() {
+        if let Some(marker) = cell_marker(content) {
+            row.cells.push(cell(marker));
+            continue;
+        }
+        if let Content::Verse(verse_num) = content {
+            let published = contents.peek().and_then(|next| published_verse(next));
+            if published.is_some() {
+                contents.next();
+            }
+            let (v, label) = verse_label(verse_num.to_native(), published);
+            row.verses.push(v);
+            match row.cells.last_mut() {
+                Some(cell) if v.start > 1 && painter.get_options().show_verse_numbers != 0 => {
+                    let style = text_style(cell);
+                    if cell
+                        .segments
+                        .last()
+                        .is_some_and(|(text, _)| !text.ends_with(' '))
+                    {
+                        push_segment(cell, " ", style);
+                    }
+                    push_segment(cell, &label, Style::Verse);
+                    push_segment(cell, " ", style);
+                }
+                _ => (),
+            }
+            continue;
+        }
+        let Some(cell) = row.cells.last_mut() else {
+            continue;
+        };
+        let style = text_style(cell);
+        match content {
+            Content::Line(text) => push_segment(cell, text, style),
+            Content::Character(character) => {
+                let mut text = String::new();
+                push_character_text(&character.contents, &mut text);
+                push_segment(cell, &text, character_style(&character.ty).unwrap_or(style));
+            }
+            Content::Footnote(_) | Content::CrossRef(_) => {
+                log!("[USFM] Note in a table cell left out");
+            }
+            Content::Verse(_) => (),
+        }
+    }
+    for cell in row.cells.iter_mut() {
+        if let Some((first, _)) = cell.segments.first_mut() {
+            *first = first.trim_start().to_string();
+        }
+        if let Some((last, _)) = cell.segments.last_mut() {
+            *last = last.trim_end().to_string();
+        }
+        cell.segments.retain(|(text, _)| !text.is_empty());
+    }
+    row
+}
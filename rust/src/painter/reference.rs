@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+
+use rkyv::rancor::Error;
+use usfm::{ArchivedBook, ArchivedElementContents, ArchivedElementType, BookIdentifier};
+
+use super::layout::{Index, IndexRange, Verse};
+
+/// A target book: its identifier and the `\h` header its indices are keyed by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookTarget {
+    pub book: BookIdentifier,
+    pub header: String,
+}
+
+/// The translation's own names for its books (`\h`, `\toc1`..`\toc3` and
+/// the three-letter code), used to resolve cross-reference targets.
+#[derive(Debug, Clone, Default)]
+pub struct BookNames {
+    names: HashMap<String, BookTarget>,
+}
+
+fn normalize(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('.', "")
+        .to_lowercase()
+}
+
+impl BookNames {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, book: &ArchivedBook) {
+        use usfm::ArchivedBookContents as Content;
+        let Some(code) = book.contents.iter().find_map(|content| match content {
+            Content::Id { code, .. } => Some(code),
+            _ => None,
+        }) else {
+            return;
+        };
+
+        let mut names = vec![code.to_identifier().to_string()];
+        let mut header = String::new();
+        for content in book.contents.iter() {
+            let Content::Element(element) = content else {
+                continue;
+            };
+            if !matches!(
+                element.ty,
+                ArchivedElementType::Header | ArchivedElementType::Contents(1..=3)
+            ) {
+                continue;
+            }
+            for content in element.contents.iter() {
+                if let ArchivedElementContents::Line(text) = content {
+                    let text = text.trim();
+                    if matches!(element.ty, ArchivedElementType::Header) && header.is_empty() {
+                        header = text.to_string();
+                    }
+                    names.push(text.to_string());
+                }
+            }
+        }
+
+        let target = BookTarget {
+            book: rkyv::deserialize::<_, Error>(code).unwrap(),
+            header,
+        };
+        for name in names {
+            let name = normalize(&name);
+            if !name.is_empty() {
+                self.names.entry(name).or_insert_with(|| target.clone());
+            }
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&BookTarget> {
+        self.names.get(&normalize(name))
+    }
+}
+
+/// A chapter and optional verse, as in `33:6` or `23`.
+fn parse_point(text: &str) -> Option<(u16, Option<u16>)> {
+    let text = text.trim();
+    match text.split_once([':', '.']) {
+        Some((chapter, verse)) => Some((
+            chapter.trim().parse().ok()?,
+            Some(verse.trim().parse().ok()?),
+        )),
+        None => Some((text.parse().ok()?, None)),
+    }
+}
+
+/// Where the chapter/verse part of a reference starts: the first digit
+/// that begins a word and is followed by nothing but numbers and punctuation.
+fn spec_start(part: &str) -> Option<usize> {
+    part.char_indices()
+        .filter(|&(i, c)| {
+            c.is_ascii_digit() && part[..i].chars().last().is_none_or(char::is_whitespace)
+        })
+        .map(|(i, _)| i)
+        .find(|&i| {
+            part[i..]
+                .chars()
+                .all(|c| c.is_ascii_digit() || c.is_whitespace() || ":.,-–".contains(c))
+        })
+}
+
+/// Parse target text such as `Gen 1:1; Ps 33:6-9, 12` into index ranges.
+/// References without a book name stay in the previous book, starting with
+/// `book`; ones without a chapter stay in the previous chapter. Parts naming
+/// a book the translation does not have are skipped.
+pub fn parse_references(
+    text: &str,
+    names: &BookNames,
+    book: Option<&BookTarget>,
+    chapter: Option<u16>,
+) -> Vec<IndexRange> {
+    let mut ranges = Vec::new();
+    let mut book = book.cloned();
+    let mut chapter = chapter;
+
+    for part in text.split(';') {
+        let part = part.trim();
+        let Some(start) = spec_start(part) else {
+            continue;
+        };
+        let name = part[..start].trim();
+        if !name.is_empty() {
+            book = names.get(name).cloned();
+        }
+        let Some(target) = book.as_ref() else {
+            continue;
+        };
+
+        // Once a verse has been given, bare numbers are verses in the same chapter
+        let mut in_verses = false;
+        for item in part[start..].split(',') {
+            let (from, to) = match item.split_once(['-', '–']) {
+                Some((from, to)) => (from, Some(to)),
+                None => (item, None),
+            };
+            let from = match parse_point(from) {
+                Some((c, Some(v))) => {
+                    in_verses = true;
+                    (c, Some(v))
+                }
+                Some((n, None)) if in_verses => match chapter {
+                    Some(c) => (c, Some(n)),
+                    None => continue,
+                },
+                Some((n, None)) => (n, None),
+                None => continue,
+            };
+            let to = match to.map(parse_point) {
+                None => from,
+                Some(Some((c, Some(v)))) => (c, Some(v)),
+                // `6-9` ends at a verse, `23-24` at a chapter
+                Some(Some((n, None))) if from.1.is_some() => (from.0, Some(n)),
+                Some(Some((n, None))) => (n, None),
+                Some(None) => continue,
+            };
+            chapter = Some(to.0);
+
+            let index = |(c, v): (u16, Option<u16>)| {
+                Index::new(
+                    target.book.clone(),
+                    target.header.clone(),
+                    Some(c),
+                    v.map(Verse::from),
+                )
+            };
+            ranges.push(IndexRange {
+                start: index(from),
+                end: index(to),
+            });
+        }
+    }
+
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use usfm::{ArchivedBook, parse};
+
+    use super::*;
+
+    fn book_names() -> BookNames {
+        let mut names = BookNames::new();
+        for usfm in [
+            "\\id GEN\n\\h  Genesis \n\\toc1 The Book of Genesis\n\\toc2 Genesis\n\\toc3 Gn\n",
+            "\\id PSA\n\\h Psalms\n\\toc3 Ps.\n",
+        ] {
+            let bytes = rkyv::to_bytes::<Error>(&parse(usfm)).unwrap();
+            names.add(rkyv::access::<ArchivedBook, Error>(&bytes).unwrap());
+        }
+        names
+    }
+
+    fn index(book: BookIdentifier, chapter: u16, verse: Option<u16>) -> Index {
+        let header = match book {
+            BookIdentifier::Genesis => "Genesis",
+            _ => "Psalms",
+        };
+        Index::new(
+            book,
+            header.to_string(),
+            Some(chapter),
+            verse.map(Verse::from),
+        )
+    }
+
+    #[test]
+    fn parses_references() {
+        use BookIdentifier::{Genesis as Gen, Psalms as Ps};
+        let range =
+            |book: BookIdentifier, from: (u16, Option<u16>), to: (u16, Option<u16>)| IndexRange {
+                start: index(book.clone(), from.0, from.1),
+                end: index(book, to.0, to.1),
+            };
+        let cases = [
+            ("Gen 1:1", vec![range(Gen, (1, Some(1)), (1, Some(1)))]),
+            ("gn. 1.1", vec![range(Gen, (1, Some(1)), (1, Some(1)))]),
+            (
+                "Ps 33:6-9, 12",
+                vec![
+                    range(Ps, (33, Some(6)), (33, Some(9))),
+                    range(Ps, (33, Some(12)), (33, Some(12))),
+                ],
+            ),
+            ("Ps 23-24", vec![range(Ps, (23, None), (24, None))]),
+            ("Gn 2:4–3:1", vec![range(Gen, (2, Some(4)), (3, Some(1)))]),
+            (
+                "Gen 1:1; Ps 23",
+                vec![
+                    range(Gen, (1, Some(1)), (1, Some(1))),
+                    range(Ps, (23, None), (23, None)),
+                ],
+            ),
+            (
+                "The Book of Genesis 5:1; 6:2",
+                vec![
+                    range(Gen, (5, Some(1)), (5, Some(1))),
+                    range(Gen, (6, Some(2)), (6, Some(2))),
+                ],
+            ),
+            ("Exod 3:14", vec![]),
+            ("", vec![]),
+        ];
+        let names = book_names();
+        for (text, expected) in cases {
+            assert_eq!(
+                parse_references(text, &names, None, None),
+                expected,
+                "{text:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn continues_in_the_current_book_and_chapter() {
+        let names = book_names();
+        let current = names.get("Psalms").cloned();
+        let ranges = parse_references("4, 7; 5:2", &names, current.as_ref(), Some(3));
+        let starts: Vec<_> = ranges.into_iter().map(|range| range.start).collect();
+        assert_eq!(
+            starts,
+            vec![
+                index(BookIdentifier::Psalms, 4, None),
+                index(BookIdentifier::Psalms, 7, None),
+                index(BookIdentifier::Psalms, 5, Some(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn trims_book_names() {
+        let names = book_names();
+        let genesis = names.get("Genesis").unwrap();
+        assert_eq!(genesis.header, "Genesis");
+        assert_eq!(names.get("PSA").unwrap().book, BookIdentifier::Psalms);
+    }
+}
@@ -1,22 +1,34 @@
 use std::{
     collections::HashMap,
     ffi::c_char,
+    rc::Rc,
     slice::from_raw_parts,
     str::from_utf8_unchecked,
 };
 
+use icu_segmenter::{LineSegmenter, options::LineBreakOptions};
 use rkyv::{api::low::deserialize, rancor::Error};
 use skia_safe::{
     Font, FontMetrics, FontMgr, FontStyle, Typeface,
+    font_style::{Slant, Weight, Width},
     textlayout::{
         FontCollection, ParagraphBuilder, ParagraphStyle, RectHeightStyle, RectWidthStyle,
         TextStyle as ParagraphTextStyle, TypefaceFontProvider,
     },
 };
 
-use super::{Style, Text, layout::ArchivedPage};
+use super::hyphenation::{Hyphenator, normalize_language};
+use super::{Decoration, Style, Text, layout::ArchivedPage};
 use super::layout::{InlineItem, ItemKind, Section};
 
+/// Drawn at the end of a line broken inside a word.
+const HYPHEN: &str = "-";
+/// U+00AD, marking where a word may be broken.
+const SOFT_HYPHEN: char = '\u{AD}';
+
+/// Colour of `\wj` text when the app has not registered `Style::Jesus`.
+const RED_LETTER: u32 = 0xFFCC_0000;
+
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
 pub struct TextStyle {
@@ -26,7 +38,14 @@ pub struct TextStyle {
     pub height: f32,
     pub letter_spacing: f32,
     pub word_spacing: f32,
-    pub underline: i32,
+    pub decoration: Decoration,
+    /// CSS-style weight; 0 means the regular weight.
+    pub font_weight: i32,
+    pub italic: i32,
+    pub small_caps: i32,
+    /// ARGB colours, drawn by the app; 0 leaves the theme's colour.
+    pub color: u32,
+    pub background_color: u32,
 }
 
 impl TextStyle {
@@ -39,12 +58,85 @@ impl TextStyle {
         }
     }
 
+    fn font_style(&self) -> FontStyle {
+        let weight = if self.font_weight > 0 {
+            self.font_weight
+        } else {
+            *Weight::NORMAL
+        };
+        let slant = if self.italic != 0 {
+            Slant::Italic
+        } else {
+            Slant::Upright
+        };
+        FontStyle::new(Weight::from(weight), Width::NORMAL, slant)
+    }
+
+    /// Apply every property of `inner` that differs from `normal`, so a nested
+    /// character style only changes what it sets apart from body text.
+    fn overlay(&mut self, inner: &TextStyle, normal: &TextStyle) {
+        if inner.font_family() != normal.font_family() {
+            self.font_family = inner.font_family;
+            self.font_family_len = inner.font_family_len;
+        }
+        if inner.font_size != normal.font_size {
+            self.font_size = inner.font_size;
+        }
+        if inner.height != normal.height {
+            self.height = inner.height;
+        }
+        if inner.letter_spacing != normal.letter_spacing {
+            self.letter_spacing = inner.letter_spacing;
+        }
+        if inner.word_spacing != normal.word_spacing {
+            self.word_spacing = inner.word_spacing;
+        }
+        if inner.decoration != normal.decoration {
+            self.decoration = inner.decoration;
+        }
+        if inner.font_weight != normal.font_weight {
+            self.font_weight = inner.font_weight;
+        }
+        if inner.italic != normal.italic {
+            self.italic = inner.italic;
+        }
+        if inner.small_caps != normal.small_caps {
+            self.small_caps = inner.small_caps;
+        }
+        if inner.color != normal.color {
+            self.color = inner.color;
+        }
+        if inner.background_color != normal.background_color {
+            self.background_color = inner.background_color;
+        }
+    }
+}
+
+/// Derive a text style the app has not registered from its fallback.
+fn derive_style(style: &Style, mut text_style: TextStyle) -> TextStyle {
+    match style {
+        Style::Italic | Style::Addition | Style::Selah | Style::Transliterated => {
+            text_style.italic = 1;
+        }
+        Style::Bold => text_style.font_weight = 700,
+        Style::BoldItalic => {
+            text_style.font_weight = 700;
+            text_style.italic = 1;
+        }
+        Style::SmallCap | Style::Deity => text_style.small_caps = 1,
+        Style::Word => text_style.decoration = Decoration::Underline,
+        Style::Jesus => text_style.color = RED_LETTER,
+        _ => (),
+    }
+    text_style
 }
 
 #[derive(Debug, Clone)]
 pub struct Renderer {
     font_provider: TypefaceFontProvider,
     style_collection: HashMap<Style, TextStyle>,
+    /// Hyphenation patterns by language tag.
+    hyphenators: HashMap<String, Rc<Hyphenator>>,
 }
 
 impl Renderer {
@@ -52,6 +144,7 @@ impl Renderer {
         Self {
             font_provider: TypefaceFontProvider::new(),
             style_collection: HashMap::new(),
+            hyphenators: HashMap::new(),
         }
     }
 
@@ -63,8 +156,51 @@ impl Renderer {
         self.style_collection.insert(style, text_style);
     }
 
+    pub fn register_hyphenation(&mut self, language: &str, patterns: &str) {
+        self.hyphenators.insert(
+            normalize_language(language),
+            Rc::new(Hyphenator::new(patterns)),
+        );
+    }
+
+    /// The patterns for a language tag, falling back to its primary
+    /// language (`de` for `de-CH`).
+    pub fn hyphenator(&self, language: &str) -> Option<Rc<Hyphenator>> {
+        let language = normalize_language(language);
+        let primary = language.split('-').next().unwrap_or_default();
+        self.hyphenators
+            .get(&language)
+            .or_else(|| self.hyphenators.get(primary))
+            .cloned()
+    }
+
+    /// Look up the registered text style, deriving it from `Style::fallback`
+    /// for styles the app has not registered.
+    pub fn text_style(&self, style: &Style) -> TextStyle {
+        if let Some(text_style) = self.style_collection.get(style) {
+            return *text_style;
+        }
+        let fallback = style
+            .fallback()
+            .unwrap_or_else(|| panic!("Style {:?} is not registered", style));
+        derive_style(style, self.text_style(&fallback))
+    }
+
+    /// The text style of `style` nested inside `outer_styles`, outermost first.
+    pub fn composed_style(&self, style: &Style, outer_styles: &[Style]) -> TextStyle {
+        let Some((first, rest)) = outer_styles.split_first() else {
+            return self.text_style(style);
+        };
+        let normal = self.text_style(&Style::Normal);
+        let mut composed = self.text_style(first);
+        for inner in rest.iter().chain([style]) {
+            composed.overlay(&self.text_style(inner), &normal);
+        }
+        composed
+    }
+
     pub fn line_height(&self, style: &Style) -> f32 {
-        let text_style = &self.style_collection[style];
+        let text_style = self.text_style(style);
         text_style.height * text_style.font_size
     }
 
@@ -91,34 +227,43 @@ impl Renderer {
         ParagraphBuilder::new(&paragraph_style, font_collection)
     }
 
-    pub fn get_style(&self, style: &Style) -> ParagraphTextStyle {
-        let text_style = &self.style_collection[style];
+    pub fn get_style(&self, text_style: &TextStyle) -> ParagraphTextStyle {
         let mut paragraph_text_style = ParagraphTextStyle::new();
         paragraph_text_style
             .set_font_size(text_style.font_size)
             .set_font_families(&[text_style.font_family()])
+            .set_font_style(text_style.font_style())
             .set_height(text_style.height)
             .set_letter_spacing(text_style.letter_spacing)
             .set_word_spacing(text_style.word_spacing);
+        if text_style.small_caps != 0 {
+            paragraph_text_style.add_font_feature("smcp", 1);
+        }
         paragraph_text_style
     }
 
     #[allow(dead_code)]
     pub fn get_metrics(&self, style: &Style) -> FontMetrics {
-        let text_style = &self.style_collection[style];
+        let text_style = self.text_style(style);
         let font_mgr: FontMgr = self.font_provider.clone().into();
         let typeface = font_mgr
-            .match_family_style(text_style.font_family(), FontStyle::normal())
+            .match_family_style(text_style.font_family(), text_style.font_style())
             .unwrap();
         let font = Font::from_typeface(typeface, text_style.font_size);
         font.metrics().1
     }
 
     pub fn page(&self, page: &ArchivedPage) -> Vec<Text> {
+        // Images are drawn from page_images
         page.iter()
+            .filter(|fragment| fragment.image.is_none())
             .map(|fragment| {
-                let mut style =
-                    self.style_collection[&deserialize::<_, Error>(&fragment.style).unwrap()];
+                let outer_styles: Vec<Style> =
+                    deserialize::<_, Error>(&fragment.outer_styles).unwrap();
+                let mut style = self.composed_style(
+                    &deserialize::<_, Error>(&fragment.style).unwrap(),
+                    &outer_styles,
+                );
                 style.word_spacing += fragment.word_spacing.to_native();
                 let text = fragment.text.as_bytes();
                 let len = text.len();
@@ -128,21 +273,48 @@ impl Renderer {
                     len,
                     deserialize::<_, Error>(&fragment.rect).unwrap(),
                     style,
+                    fragment.rtl as i32,
                 )
             })
             .collect()
     }
 }
 
+/// Break opportunities between letters in `text` (UAX #14, with dictionaries
+/// for Thai, Lao, Khmer and Burmese), where there is no space to break at.
+/// Soft hyphens are left to the word parts of [`shape_segments`]. The text
+/// should be a whole paragraph, so that words running across styles are
+/// seen whole.
+pub fn letter_breaks(text: &str) -> Vec<usize> {
+    LineSegmenter::new_dictionary(LineBreakOptions::default())
+        .segment_str(text)
+        .filter(|&i| 0 < i && i < text.len())
+        .filter(|&i| {
+            let before = text[..i].chars().next_back();
+            let after = text[i..].chars().next();
+            !before.is_some_and(|c| c.is_whitespace() || c == SOFT_HYPHEN)
+                && !after.is_some_and(char::is_whitespace)
+        })
+        .collect()
+}
+
 /// Shape a list of (text, style) segments into InlineItems with Skia-measured widths.
 ///
 /// All segments are laid out as a single Skia paragraph at infinite width for measurement,
 /// then split on word/whitespace boundaries and style boundaries.
 /// Each InlineItem carries its text directly as a String.
+/// `outer_styles` are the styles every segment is nested in, outermost first.
+/// Words are split into discretionary breaks at their soft hyphens or, failing
+/// those, where `hyphenator` allows. `letter_breaks` are byte offsets into the
+/// segments' joined text where a line may break between letters (see
+/// [`letter_breaks`]); 0 allows a break before the first one.
 pub fn shape_segments(
     renderer: &Renderer,
     segments: &[(String, Style)],
+    outer_styles: &[Style],
     section: Section,
+    hyphenator: Option<&Hyphenator>,
+    letter_breaks: &[usize],
 ) -> Vec<InlineItem> {
     // Concatenate all segment text for shaping
     let full_text: String = segments.iter().map(|(t, _)| t.as_str()).collect();
@@ -154,7 +326,7 @@ pub fn shape_segments(
 
     // Build Skia paragraph from all segments
     for (text, style) in segments {
-        builder.push_style(&renderer.get_style(style));
+        builder.push_style(&renderer.get_style(&renderer.composed_style(style, outer_styles)));
         builder.add_text(text);
         builder.pop();
     }
@@ -191,7 +363,7 @@ pub fn shape_segments(
     style_boundaries.sort();
     style_boundaries.dedup();
 
-    // Split text into segments at word/whitespace and style boundaries
+    // Split text into segments at word/whitespace, style and letter break boundaries
     let text = &full_text;
     let mut seg_ranges: Vec<(usize, usize)> = Vec::new();
     let mut seg_start = 0;
@@ -204,13 +376,14 @@ pub fn shape_segments(
 
         let current_ws = ch.is_whitespace();
         let next_ws = if next < text.len() {
-            text[next..].chars().next().map_or(false, |c| c.is_whitespace())
+            text[next..].chars().next().is_some_and(|c| c.is_whitespace())
         } else {
             current_ws
         };
         let is_word_boundary = next < text.len() && current_ws != next_ws;
+        let is_letter_break = letter_breaks.binary_search(&next).is_ok();
 
-        if is_style_boundary || is_word_boundary {
+        if is_style_boundary || is_word_boundary || is_letter_break {
             if seg_start < next {
                 seg_ranges.push((seg_start, next));
             }
@@ -233,39 +406,120 @@ pub fn shape_segments(
         offsets
     };
 
-    // Measure each segment and produce InlineItems
-    let mut items: Vec<InlineItem> = Vec::new();
-    for (start, end) in seg_ranges {
-        let segment_text = &text[start..end];
-        let style = style_at(start);
-        let is_whitespace = segment_text.chars().all(|c| c.is_whitespace());
-
+    let measure = |start: usize, end: usize| -> f32 {
         let rects = paragraph.get_rects_for_range(
             utf16_offsets[start]..utf16_offsets[end],
             RectHeightStyle::Tight,
             RectWidthStyle::Tight,
         );
-        let width = if rects.is_empty() {
+        if rects.is_empty() {
             0.0
         } else {
             rects.iter().map(|r| r.rect.width()).sum()
-        };
-
-        let kind = if is_whitespace {
-            ItemKind::Glue
-        } else {
-            ItemKind::Word
-        };
+        }
+    };
+    let mut hyphen_widths: Vec<(Style, f32)> = Vec::new();
 
-        items.push(InlineItem {
-            text: segment_text.to_string(),
+    // Measure each segment and produce InlineItems
+    let mut items: Vec<InlineItem> = Vec::new();
+    for (start, end) in seg_ranges {
+        let segment_text = &text[start..end];
+        let style = style_at(start);
+        let is_whitespace = segment_text.chars().all(|c| c.is_whitespace());
+        let item = |text: &str, width: f32, kind: ItemKind| InlineItem {
+            text: text.to_string(),
             style,
+            outer_styles: outer_styles.to_vec(),
+            attributes: Vec::new(),
             width,
             kind,
             section,
             index_id: None,
-        });
+        };
+
+        if is_whitespace {
+            items.push(item(segment_text, measure(start, end), ItemKind::Glue));
+            continue;
+        }
+        if letter_breaks.binary_search(&start).is_ok() {
+            items.push(item("", 0.0, ItemKind::LetterBreak));
+        }
+
+        // Word parts, as (start, end) in `text`, between discretionary breaks
+        let mut parts: Vec<(usize, usize)> = Vec::new();
+        if segment_text.contains(SOFT_HYPHEN) {
+            let mut part_start = start;
+            for (i, _) in segment_text.match_indices(SOFT_HYPHEN) {
+                parts.push((part_start, start + i));
+                part_start = start + i + SOFT_HYPHEN.len_utf8();
+            }
+            parts.push((part_start, end));
+            parts.retain(|(part_start, part_end)| part_start < part_end);
+        } else {
+            let breaks = hyphenator.map_or(Vec::new(), |h| h.hyphenate(segment_text));
+            let mut part_start = start;
+            for i in breaks {
+                parts.push((part_start, start + i));
+                part_start = start + i;
+            }
+            parts.push((part_start, end));
+        }
+
+        for (n, &(part_start, part_end)) in parts.iter().enumerate() {
+            if n > 0 {
+                let hyphen_width = match hyphen_widths.iter().find(|(s, _)| *s == style) {
+                    Some(&(_, width)) => width,
+                    None => {
+                        let width = measure_text(renderer, HYPHEN, style, outer_styles);
+                        hyphen_widths.push((style, width));
+                        width
+                    }
+                };
+                items.push(item(HYPHEN, hyphen_width, ItemKind::Discretionary));
+            }
+            items.push(item(
+                &text[part_start..part_end],
+                measure(part_start, part_end),
+                ItemKind::Word,
+            ));
+        }
     }
 
     items
 }
+
+/// Width of `text` on its own in `style`.
+fn measure_text(renderer: &Renderer, text: &str, style: Style, outer_styles: &[Style]) -> f32 {
+    let mut builder = renderer.new_builder();
+    builder.push_style(&renderer.get_style(&renderer.composed_style(&style, outer_styles)));
+    builder.add_text(text);
+    builder.pop();
+    let mut paragraph = builder.build();
+    paragraph.layout(f32::INFINITY);
+    let utf16_len = text.encode_utf16().count();
+    paragraph
+        .get_rects_for_range(0..utf16_len, RectHeightStyle::Tight, RectWidthStyle::Tight)
+        .iter()
+        .map(|r| r.rect.width())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_letter_breaks() {
+        let cases: [(&str, &[usize]); 5] = [
+            ("hello world", &[]),
+            ("co\u{AD}op", &[]),
+            ("我们是中国人", &[3, 6, 9, 12, 15]),
+            // Thai has no spaces between words; the dictionary finds them
+            ("ภาษาไทย", &[12]),
+            ("我们 是", &[3]),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(letter_breaks(text), expected, "{text:?}");
+        }
+    }
+}
@@ -1,5 +1,12 @@
 #include <stdlib.h>
 
+typedef enum {
+  DECORATION_NONE = 0,
+  DECORATION_UNDERLINE = 1,
+  DECORATION_DOTTED = 2,
+  DECORATION_STRIKE = 3,
+} Decoration;
+
 typedef struct {
   const char* font_family;
   size_t font_family_len;
@@ -7,7 +14,12 @@ typedef struct {
   float height;
   float letter_spacing;
   float word_spacing;
-  int underline;
+  Decoration decoration;
+  int font_weight;
+  int italic;
+  int small_caps;
+  unsigned int color;
+  unsigned int background_color;
 } TextStyle;
 
 typedef enum {
@@ -20,6 +32,52 @@ typedef enum {
   CALLER = 9,
   FOOTNOTE = 10,
   CROSSREF = 11,
+
+  MAJOR_SECTION = 12,
+  MAJOR_REFERENCE = 13,
+  SECTION = 14,
+  SUB_SECTION = 15,
+  MINOR_SECTION = 16,
+  PARALLEL = 17,
+  DESCRIPTIVE = 18,
+  ACROSTIC_HEADING = 19,
+
+  ADDITION = 20,
+  DEITY = 21,
+  JESUS = 22,
+  BOLD = 23,
+  ITALIC = 24,
+  BOLD_ITALIC = 25,
+  SMALL_CAP = 26,
+  SELAH = 27,
+  TRANSLITERATED = 28,
+  PROPER = 29,
+
+  TITLE = 30,
+  SUB_TITLE = 31,
+
+  INTRO_TITLE = 32,
+  INTRO_SECTION = 33,
+  INTRO = 34,
+  INTRO_OUTLINE = 35,
+
+  TABLE_HEADER = 36,
+
+  CHAPTER_LABEL = 37,
+  ALT_CHAPTER = 38,
+
+  NOTE_REFERENCE = 39,
+  NOTE_QUOTE = 40,
+  NOTE_ALT_QUOTE = 41,
+  NOTE_KEYWORD = 42,
+  NOTE_LABEL = 43,
+  NOTE_WITNESS = 44,
+  NOTE_TEXT = 45,
+  NOTE_DEUTERO = 46,
+  NOTE_TARGET = 47,
+
+  FIGURE_CAPTION = 48,
+  SIDEBAR = 49,
 } Style;
 
 typedef struct {
@@ -34,8 +92,35 @@ typedef struct {
   size_t len;
   Rectangle rect;
   TextStyle style;
+  int rtl;
 } Text;
 
+typedef struct {
+  const char* key;
+  size_t key_len;
+  const char* value;
+  size_t value_len;
+} Attribute;
+
+typedef struct {
+  const char* source;
+  size_t source_len;
+  Rectangle rect;
+} Image;
+
+typedef struct {
+  Rectangle rect;
+  int continued;
+  int continues;
+} BoxFrame;
+
+typedef enum {
+  BOOK_NAME_HEADER = 0,
+  BOOK_NAME_LONG = 1,
+  BOOK_NAME_SHORT = 2,
+  BOOK_NAME_ABBREVIATION = 3,
+} BookName;
+
 typedef struct {
   float width;
   float height;
@@ -43,22 +128,87 @@ typedef struct {
   float drop_cap_padding;
 } Dimensions;
 
+typedef enum {
+  CALLER_LETTERS = 0,
+  CALLER_NUMERALS = 1,
+  CALLER_SYMBOLS = 2,
+} CallerSequence;
+
+typedef enum {
+  CALLER_RESET_PAGE = 0,
+  CALLER_RESET_CHAPTER = 1,
+  CALLER_RESET_BOOK = 2,
+} CallerReset;
+
+typedef enum {
+  NOTES_PAGE = 0,
+  NOTES_CHAPTER = 1,
+  NOTES_BOOK = 2,
+} NotePlacement;
+
+typedef enum {
+  TEXT_DIRECTION_LTR = 0,
+  TEXT_DIRECTION_RTL = 1,
+} TextDirection;
+
+typedef enum {
+  LINE_BREAKING_GREEDY = 0,
+  LINE_BREAKING_OPTIMAL = 1,
+} LineBreaking;
+
+typedef struct {
+  int include_introductions;
+  int chapter_label_headings;
+  CallerSequence caller_sequence;
+  CallerReset caller_reset;
+  NotePlacement note_placement;
+  const char* notes_title;
+  size_t notes_title_len;
+  int show_verse_numbers;
+  int show_footnotes;
+  int show_cross_references;
+  int show_headings;
+  LineBreaking line_breaking;
+  TextDirection text_direction;
+  const char* language;
+  size_t language_len;
+  int orphan_lines;
+  int widow_lines;
+} LayoutOptions;
+
 void free_error(char* error, size_t error_len);
 
 void* renderer();
 void register_font_family(void* renderer, char* family, size_t family_len, char* data, size_t len, char** out_error, size_t* out_error_len);
 void register_style(void* renderer, Style style, TextStyle* textStyle);
+void register_hyphenation(void* renderer, const char* language, size_t language_len, const char* patterns, size_t patterns_len, char** out_error, size_t* out_error_len);
 
 void serialize_usfm(const char* usfm, size_t usfm_len, const char** out, size_t* out_len, char** out_error, size_t* out_error_len);
 void* archived_book(const char* book, size_t book_len, char** out_error, size_t* out_error_len);
 void book_identifier(void* usfm, const char** out, size_t* out_len, char** out_error, size_t* out_error_len);
+void book_name(void* usfm, BookName name, const char** out, size_t* out_len, char** out_error, size_t* out_error_len);
+
+void* book_names();
+void add_book_names(void* book_names, void* usfm);
+void free_book_names(void* book_names);
 
-void* layout(void* renderer, void* usfm, Dimensions* dim, char** out_error, size_t* out_error_len);
+void* glossary(void* usfm);
+void free_glossary(void* glossary);
+void glossary_entry(void* glossary, void* archived_pages, size_t page, size_t fragment, const char** out, size_t* out_len, char** out_error, size_t* out_error_len);
+
+void* layout(void* renderer, void* usfm, Dimensions* dim, LayoutOptions options, void* book_names, char** out_error, size_t* out_error_len);
 void serialize_pages(void* painter, const char** out, size_t* out_len, char** out_error, size_t* out_error_len);
 void* archived_pages(const char* pages, size_t pages_len, char** out_error, size_t* out_error_len);
 size_t num_pages(void* archived_pages);
 void page(void* renderer, void* archived_pages, size_t n, const Text** out, size_t* out_len, char** out_error, size_t* out_error_len);
+void fragment_attributes(void* archived_pages, size_t page, size_t fragment, const Attribute** out, size_t* out_len, char** out_error, size_t* out_error_len);
+void free_attributes(const Attribute* attributes, size_t len);
+void page_images(void* archived_pages, size_t page, const Image** out, size_t* out_len, char** out_error, size_t* out_error_len);
+void free_images(const Image* images, size_t len);
+void page_frames(void* archived_pages, size_t page, const BoxFrame** out, size_t* out_len, char** out_error, size_t* out_error_len);
+void free_frames(const BoxFrame* frames, size_t len);
 
+unsigned int index_version(void);
 void serialize_indices(void* painter, const char** out, size_t* out_len, char** out_error, size_t* out_error_len);
 void* archived_indices(const char* indices, size_t indices_len, char** out_error, size_t* out_error_len);
 void get_index(
@@ -69,10 +219,20 @@ void get_index(
   size_t* out_book_len,
   const char** out_header,
   size_t* out_header_len,
+  const char** out_heading,
+  size_t* out_heading_len,
   unsigned short* out_chapter,
   unsigned short* out_verse,
+  unsigned short* out_verse_end,
+  unsigned int* out_segment,
   char** out_error,
   size_t* out_error_len
 );
+void find_verse(void* archived_indices, const char* book, size_t book_len, unsigned short chapter, unsigned short verse, size_t* out_page, char** out_error, size_t* out_error_len);
 void serialize_verses(void* painter, const char** out, size_t* out_len, char** out_error, size_t* out_error_len);
 void serialize_verse_ranges(void* painter, const char** out, size_t* out_len);
+
+void serialize_cross_refs(void* painter, const char** out, size_t* out_len, char** out_error, size_t* out_error_len);
+void* archived_cross_refs(const char* cross_refs, size_t cross_refs_len, char** out_error, size_t* out_error_len);
+void cross_ref_targets(void* archived_cross_refs, size_t link, const void*** out_starts, const void*** out_ends, size_t* out_len, char** out_error, size_t* out_error_len);
+void free_cross_ref_targets(const void** starts, const void** ends, size_t len);
@@ -39,6 +39,8 @@ void get_search_result(
   size_t* out_header_len,
   unsigned short* out_chapter,
   unsigned short* out_verse,
+  unsigned short* out_verse_end,
+  unsigned int* out_segment,
   char** out_error,
   size_t* out_error_len
 );
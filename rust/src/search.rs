@@ -8,14 +8,17 @@ use hnsw_rs::prelude::*;
 use ndarray::{Array2, ArrayD, Axis};
 use rkyv::deserialize;
 use rkyv::rancor::Error as RkyvError;
-use rkyv::vec::ArchivedVec;
+use rkyv::util::AlignedVec;
 use tokenizers::Tokenizer;
 use tract_onnx::prelude::*;
 
 use crate::error::SolaError;
 use crate::ffi::{read_bytes, read_ref, read_str, run_ffi, write_vec};
 use crate::log;
-use crate::painter::{ArchivedIndex, ArchivedIndices, Index};
+use crate::painter::{
+    ArchivedIndex, ArchivedIndices, ArchivedVerseIndex, INDEX_VERSION, Index, IndexV1, VerseIndex,
+    verse_page,
+};
 
 // ---------------------------------------------------------------------------
 // Types
@@ -33,7 +36,7 @@ struct SearchEngine {
     // because this struct lives behind a raw FFI pointer and is never moved.
     hnsw: Hnsw<'static, f32, DistCosine>,
     _io: Box<HnswIo>,
-    idx_bytes: Vec<u8>,
+    idx_bytes: AlignedVec,
 }
 
 // ---------------------------------------------------------------------------
@@ -103,6 +106,30 @@ fn encode_query(model: &Model, query_text: &str) -> Result<ArrayD<f32>, SolaErro
     Ok(&pooled_array / norm)
 }
 
+/// Bring a verse index to the current [`INDEX_VERSION`]. Indices built
+/// before versioning are a bare list of single verse numbers.
+fn upgrade_verse_index(bytes: &[u8]) -> Result<AlignedVec, SolaError> {
+    let mut aligned = AlignedVec::new();
+    aligned.extend_from_slice(bytes);
+    if let Ok(index) = rkyv::access::<ArchivedVerseIndex, RkyvError>(&aligned)
+        && index.is_tagged()
+    {
+        return match index.version.to_native() {
+            INDEX_VERSION => Ok(aligned),
+            version => Err(SolaError::IndexVersion(version)),
+        };
+    }
+    let legacy = rkyv::from_bytes::<Vec<IndexV1>, RkyvError>(&aligned)
+        .map_err(|e| SolaError::Deserialization(e.to_string()))?;
+    log!(
+        "[FFI] upgrading {} verses from an unversioned index",
+        legacy.len()
+    );
+    let verses = legacy.into_iter().map(Index::from).collect();
+    rkyv::to_bytes::<RkyvError>(&VerseIndex::new(verses))
+        .map_err(|e| SolaError::Serialization(e.to_string()))
+}
+
 // ---------------------------------------------------------------------------
 // FFI: Search engine
 // ---------------------------------------------------------------------------
@@ -134,7 +161,7 @@ pub extern "C" fn load_search_engine(
             let tokenizer_bytes = unsafe { read_bytes(tokenizer, tokenizer_len) };
             let hnsw_dir_str = unsafe { read_str(hnsw_dir, hnsw_dir_len) };
             let hnsw_basename_str = unsafe { read_str(hnsw_basename, hnsw_basename_len) };
-            let idx_bytes = unsafe { read_bytes(idx, idx_len) }.to_vec();
+            let idx_bytes = upgrade_verse_index(unsafe { read_bytes(idx, idx_len) })?;
 
             let onnx_model = tract_onnx::onnx()
                 .model_for_read(&mut Cursor::new(model_bytes))
@@ -229,6 +256,8 @@ pub extern "C" fn get_search_result(
     out_header_len: *mut usize,
     out_chapter: *mut u16,
     out_verse: *mut u16,
+    out_verse_end: *mut u16,
+    out_segment: *mut u32,
     out_error: *mut *mut c_char,
     out_error_len: *mut usize,
 ) {
@@ -236,10 +265,10 @@ pub extern "C" fn get_search_result(
         || {
             let engine = unsafe { read_ref::<SearchEngine>(engine) };
             let page_map = unsafe { read_ref::<ArchivedIndices>(page_map) };
-            let verse_refs =
-                rkyv::access::<ArchivedVec<ArchivedIndex>, RkyvError>(&engine.idx_bytes)
-                    .map_err(|e| SolaError::Search(e.to_string()))?;
+            let verse_refs = rkyv::access::<ArchivedVerseIndex, RkyvError>(&engine.idx_bytes)
+                .map_err(|e| SolaError::Search(e.to_string()))?;
             let verse_ref = verse_refs
+                .verses
                 .get(id)
                 .ok_or(SolaError::Search(format!("Invalid HNSW result id: {}", id)))?;
             log!(
@@ -248,9 +277,15 @@ pub extern "C" fn get_search_result(
                 verse_ref
             );
 
+            // The book may bridge the verse that the search index names alone
             let page_val: usize = page_map
                 .get(verse_ref)
                 .and_then(|p| p.to_native().try_into().ok())
+                .or_else(|| {
+                    let chapter = verse_ref.chapter.as_ref()?.to_native();
+                    let verse = verse_ref.verse.as_ref()?.start.to_native();
+                    verse_page(page_map, verse_ref.book.to_identifier(), chapter, verse)
+                })
                 .unwrap_or(0);
 
             let deserialized: Index = deserialize::<_, RkyvError>(verse_ref)
@@ -285,7 +320,9 @@ pub extern "C" fn get_search_result(
             *out_chapter = chapter;
         }
         if let Some(verse) = verse {
-            *out_verse = verse;
+            *out_verse = verse.start;
+            *out_verse_end = verse.end;
+            *out_segment = verse.segment.map_or(0, u32::from);
         }
     }
 }
@@ -310,7 +347,7 @@ pub extern "C" fn search_index(
             let query = unsafe { read_str(query, query_len) }.trim();
             let matches: Vec<*const c_void> = page_map
                 .keys()
-                .filter(|i| i.verse.is_none() && i.chapter.is_none())
+                .filter(|i| i.verse.is_none() && i.chapter.is_none() && i.heading.is_none())
                 .filter(|i| i.header.to_lowercase().contains(&query.to_lowercase()))
                 .map(|i: &ArchivedIndex| i as *const ArchivedIndex as *const c_void)
                 .take(5)
@@ -396,3 +433,40 @@ pub extern "C" fn page_map_builder_finish(
     }
     mem::forget(bytes);
 }
+
+#[cfg(test)]
+mod tests {
+    use usfm::BookIdentifier;
+
+    use super::*;
+    use crate::painter::Verse;
+
+    #[test]
+    fn upgrades_unversioned_indices() {
+        let legacy = vec![IndexV1 {
+            book: BookIdentifier::Genesis,
+            header: "Genesis".to_string(),
+            chapter: Some(1),
+            verse: Some(3),
+        }];
+        let bytes = rkyv::to_bytes::<RkyvError>(&legacy).unwrap();
+        let upgraded = upgrade_verse_index(&bytes).unwrap();
+        let index = rkyv::from_bytes::<VerseIndex, RkyvError>(&upgraded).unwrap();
+        assert_eq!(index.version, INDEX_VERSION);
+        assert_eq!(index.verses[0].verse, Some(Verse::new(3, 3, None)));
+    }
+
+    #[test]
+    fn keeps_current_indices() {
+        let verses = vec![Index::new(
+            BookIdentifier::Genesis,
+            "Genesis".to_string(),
+            Some(1),
+            Some(Verse::new(3, 4, None)),
+        )];
+        let bytes = rkyv::to_bytes::<RkyvError>(&VerseIndex::new(verses.clone())).unwrap();
+        let upgraded = upgrade_verse_index(&bytes).unwrap();
+        let index = rkyv::from_bytes::<VerseIndex, RkyvError>(&upgraded).unwrap();
+        assert_eq!(index.verses, verses);
+    }
+}